@@ -7,6 +7,15 @@
 Feature: Licenses management
 
     Scenario: Creating licenses
-        When I create license "key11" for vessel "00000000-0000-0000-0000-000000000001" of customer "00000000-0000-0000-0000-000000000005" with count 7 and expiration date "2011-01-30T14:58:00+01:00"
+        Given There is a product "key11"
+        When I create license "key11" for vessel "00000000-0000-0000-0000-000000000001" of customer "00000000-0000-0000-0000-000000000005" with count 7 and expiration date "2111-01-30T14:58:00+01:00"
         Then I can read license key
-        And License with that key exists with count 7 and expiration date "2011-01-30T14:58:00+01:00"
+        And License with that key exists with count 7 and expiration date "2111-01-30T14:58:00+01:00"
+
+    Scenario: Creating license with invalid key
+        When I create license "key 12" for vessel "00000000-0000-0000-0000-000000000001" of customer "00000000-0000-0000-0000-000000000005" with count 7 and expiration date "2111-01-30T14:58:00+01:00"
+        Then I get "Validation failed." API error response
+
+    Scenario: Creating license of unknown product
+        When I create license "key13" for vessel "00000000-0000-0000-0000-000000000001" of customer "00000000-0000-0000-0000-000000000005" with count 7 and expiration date "2111-01-30T14:58:00+01:00"
+        Then I get "Validation failed." API error response
@@ -15,8 +15,15 @@ Feature: Licenses management
         And License at position 1 has key "key6"
 
     Scenario: Listing licenses next page
+        Given There is a license "key11" for vessel "00000000-0000-0000-0000-00000000000e" of customer "00000000-0000-0000-0000-00000000000f" with count 4 and expiration date "2017-11-11T16:00:00+02:00"
+        And There is a license "key12" for vessel "00000000-0000-0000-0000-00000000000e" of customer "00000000-0000-0000-0000-00000000000f" with count 3 and expiration date "2009-03-23T10:00:00+02:00"
+        When I list licenses for vessel "00000000-0000-0000-0000-00000000000e" of customer "00000000-0000-0000-0000-00000000000f" with limit 1
+        And I list next page of licenses for vessel "00000000-0000-0000-0000-00000000000e" of customer "00000000-0000-0000-0000-00000000000f" with limit 1
+        Then I can read list of 1 licenses
+        And License at position 0 has key "key12"
+
+    Scenario: Listing licenses with forged page token
         Given There is a license "key7" for vessel "00000000-0000-0000-0000-00000000000c" of customer "00000000-0000-0000-0000-00000000000d" with count 4 and expiration date "2017-11-11T16:00:00+02:00"
         And There is a license "key8" for vessel "00000000-0000-0000-0000-00000000000c" of customer "00000000-0000-0000-0000-00000000000d" with count 3 and expiration date "2009-03-23T10:00:00+02:00"
         When I list licenses for vessel "00000000-0000-0000-0000-00000000000c" of customer "00000000-0000-0000-0000-00000000000d" with page token "key7"
-        Then I can read list of 1 licenses
-        And License at position 0 has key "key8"
+        Then I get "Invalid page token." API error response
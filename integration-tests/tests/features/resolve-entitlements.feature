@@ -0,0 +1,14 @@
+##
+# This file is part of the IVMS Online.
+#
+# @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+##
+
+Feature: Licenses management
+
+    Scenario: Resolving vessel entitlements
+        Given There is a license "key17" for vessel "00000000-0000-0000-0000-000000000015" of customer "00000000-0000-0000-0000-000000000016" with count 2 and expiration date "2011-01-30T14:58:00+01:00"
+        And There is a license "key18" for vessel "00000000-0000-0000-0000-000000000015" of customer "00000000-0000-0000-0000-000000000016" with count 3 and expiration date "2099-07-02T03:20:00+02:00"
+        And There is a license "key19" for vessel "00000000-0000-0000-0000-000000000015" of customer "00000000-0000-0000-0000-000000000016" with count 1 and expiration date "2100-01-30T14:58:00+01:00"
+        When I resolve entitlements of vessel "00000000-0000-0000-0000-000000000015" of customer "00000000-0000-0000-0000-000000000016"
+        Then I can read entitlements of licenses "key18,key19" with 4 seats expiring at "2099-07-02T03:20:00+02:00"
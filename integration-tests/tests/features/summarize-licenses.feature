@@ -0,0 +1,19 @@
+##
+# This file is part of the IVMS Online.
+#
+# @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+##
+
+Feature: Licenses management
+
+    Scenario: Summarizing vessel licenses
+        Given There is a license "key13" for vessel "00000000-0000-0000-0000-000000000010" of customer "00000000-0000-0000-0000-000000000011" with count 2 and expiration date "2011-01-30T14:58:00+01:00"
+        And There is a license "key14" for vessel "00000000-0000-0000-0000-000000000010" of customer "00000000-0000-0000-0000-000000000011" with count 3 and expiration date "2099-07-02T03:20:00+02:00"
+        When I summarize licenses for vessel "00000000-0000-0000-0000-000000000010" of customer "00000000-0000-0000-0000-000000000011"
+        Then I can read summary of 2 licenses with 1 active, 1 expired and 3 seats
+
+    Scenario: Summarizing customer licenses
+        Given There is a license "key15" for vessel "00000000-0000-0000-0000-000000000012" of customer "00000000-0000-0000-0000-000000000014" with count 2 and expiration date "2099-01-30T14:58:00+01:00"
+        And There is a license "key16" for vessel "00000000-0000-0000-0000-000000000013" of customer "00000000-0000-0000-0000-000000000014" with count 4 and expiration date "2099-07-02T03:20:00+02:00"
+        When I summarize licenses of customer "00000000-0000-0000-0000-000000000014"
+        Then I can read summary of 2 licenses with 2 active, 0 expired and 6 seats
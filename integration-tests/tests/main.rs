@@ -7,15 +7,17 @@
 #![feature(async_closure, future_join)]
 
 use aws_config::load_from_env;
+use aws_sdk_dynamodb::config::Builder as DynamoDbConfig;
 use aws_sdk_dynamodb::types::AttributeValue::{N, S};
 use aws_sdk_dynamodb::Client as DynamoDbClient;
+use aws_sdk_lambda::config::Builder as LambdaConfig;
 use aws_sdk_lambda::error::SdkError;
 use aws_sdk_lambda::operation::invoke::{InvokeError, InvokeOutput};
 use aws_sdk_lambda::Client as LambdaClient;
 use aws_smithy_types::Blob;
 use cucumber::{given, then, when, World};
 use futures::future::join_all;
-use serde_json::{from_slice, json, to_vec, Value};
+use serde_json::{from_slice, from_str, json, to_vec, Value};
 use std::collections::HashMap;
 use std::env::{var, VarError};
 use std::future::join;
@@ -34,14 +36,18 @@ macro_rules! serialize_blob {
 struct TestWorld {
     // initialization scope
     licenses_table: String,
+    products_table: String,
     creator_lambda: String,
     deleter_lambda: String,
     fetcher_lambda: String,
     lister_lambda: String,
+    summary_lambda: String,
+    entitlements_lambda: String,
     dynamodb: DynamoDbClient,
     lambda: LambdaClient,
     // test run scope
     cleanup_keys: Vec<(String, String, String)>,
+    cleanup_products: Vec<String>,
     invoke_response: Option<Result<InvokeOutput, SdkError<InvokeError>>>,
     customer_id: Option<String>,
     vessel_id: Option<String>,
@@ -54,13 +60,27 @@ impl TestWorld {
 
         Ok(Self {
             licenses_table: var("LICENSES_TABLE")?,
+            products_table: var("PRODUCTS_TABLE")?,
             creator_lambda: var("CREATOR_LAMBDA")?,
             deleter_lambda: var("DELETER_LAMBDA")?,
             fetcher_lambda: var("FETCHER_LAMBDA")?,
             lister_lambda: var("LISTER_LAMBDA")?,
-            dynamodb: DynamoDbClient::new(config),
-            lambda: LambdaClient::new(config),
+            summary_lambda: var("SUMMARY_LAMBDA")?,
+            entitlements_lambda: var("ENTITLEMENTS_LAMBDA")?,
+            dynamodb: match var("DYNAMODB_ENDPOINT_URL") {
+                Ok(endpoint_url) => {
+                    DynamoDbClient::from_conf(DynamoDbConfig::from(config).endpoint_url(endpoint_url).build())
+                }
+                Err(_) => DynamoDbClient::new(config),
+            },
+            lambda: match var("LAMBDA_ENDPOINT_URL") {
+                Ok(endpoint_url) => {
+                    LambdaClient::from_conf(LambdaConfig::from(config).endpoint_url(endpoint_url).build())
+                }
+                Err(_) => LambdaClient::new(config),
+            },
             cleanup_keys: vec![],
+            cleanup_products: vec![],
             invoke_response: None,
             customer_id: None,
             vessel_id: None,
@@ -88,11 +108,23 @@ async fn delete_license(
     }
 }
 
+async fn delete_product(world: &TestWorld, product_key: &str) {
+    world
+        .dynamodb
+        .delete_item()
+        .table_name(world.products_table.as_str())
+        .key("productKey", S(product_key.into()))
+        .send()
+        .await
+        .unwrap();
+}
+
 async fn list_licenses(
     world: &TestWorld,
     customer_id: String,
     vessel_id: String,
     page_token: Option<String>,
+    limit: Option<usize>,
 ) -> Result<InvokeOutput, SdkError<InvokeError>> {
     world
         .lambda
@@ -102,13 +134,14 @@ async fn list_licenses(
             "customerId": customer_id,
             "vesselId": vessel_id,
             "pageToken": page_token,
+            "limit": limit,
         }))
         .send()
         .await
 }
 
-fn extract_list(response: &Option<Result<InvokeOutput, SdkError<InvokeError>>>) -> Vec<Value> {
-    let response: HashMap<String, Value> = from_slice(
+fn extract_response(response: &Option<Result<InvokeOutput, SdkError<InvokeError>>>) -> HashMap<String, Value> {
+    from_slice(
         response
             .as_ref()
             .and_then(|response| response.as_ref().ok())
@@ -116,9 +149,11 @@ fn extract_list(response: &Option<Result<InvokeOutput, SdkError<InvokeError>>>)
             .unwrap()
             .as_ref(),
     )
-    .unwrap();
+    .unwrap()
+}
 
-    response["licenses"].as_array().unwrap().to_owned()
+fn extract_list(response: &Option<Result<InvokeOutput, SdkError<InvokeError>>>) -> Vec<Value> {
+    extract_response(response)["licenses"].as_array().unwrap().to_owned()
 }
 
 #[tokio_main]
@@ -137,8 +172,14 @@ async fn main() {
                         .await
                     });
 
+                    let products = cleanup
+                        .cleanup_products
+                        .iter()
+                        .map(|product_key| delete_product(&cleanup, product_key));
+
                     join!(
                         join_all(tasks),
+                        join_all(products),
                         delete_license(&cleanup, &cleanup.customer_id, &cleanup.vessel_id, &cleanup.license_key),
                     )
                     .await;
@@ -181,6 +222,21 @@ async fn there_is_a_license(
         .unwrap();
 }
 
+#[given(expr = "There is a product {string}")]
+async fn there_is_a_product(world: &mut TestWorld, product_key: String) {
+    world.cleanup_products.push(product_key.clone());
+
+    world
+        .dynamodb
+        .put_item()
+        .table_name(world.products_table.as_str())
+        .item("productKey", S(product_key.clone()))
+        .item("displayName", S(product_key))
+        .send()
+        .await
+        .unwrap();
+}
+
 #[given(expr = "There is no license {string} for vessel {string} of customer {string}")]
 async fn there_is_no_license(world: &mut TestWorld, license_key: String, vessel_id: String, customer_id: String) {
     delete_license(world, &Some(customer_id), &Some(vessel_id), &Some(license_key)).await;
@@ -255,12 +311,73 @@ async fn i_fetch_license(world: &mut TestWorld, license_key: String, vessel_id:
 
 #[when(expr = "I list licenses for vessel {string} of customer {string}")]
 async fn i_list_licenses(world: &mut TestWorld, vessel_id: String, customer_id: String) {
-    world.invoke_response = Some(list_licenses(world, customer_id, vessel_id, None).await);
+    world.invoke_response = Some(list_licenses(world, customer_id, vessel_id, None, None).await);
 }
 
 #[when(expr = "I list licenses for vessel {string} of customer {string} with page token {string}")]
 async fn i_list_licenses_page(world: &mut TestWorld, vessel_id: String, customer_id: String, page_token: String) {
-    world.invoke_response = Some(list_licenses(world, customer_id, vessel_id, Some(page_token)).await);
+    world.invoke_response = Some(list_licenses(world, customer_id, vessel_id, Some(page_token), None).await);
+}
+
+#[when(expr = "I list licenses for vessel {string} of customer {string} with limit {int}")]
+async fn i_list_licenses_limit(world: &mut TestWorld, vessel_id: String, customer_id: String, limit: usize) {
+    world.invoke_response = Some(list_licenses(world, customer_id, vessel_id, None, Some(limit)).await);
+}
+
+#[when(expr = "I list next page of licenses for vessel {string} of customer {string} with limit {int}")]
+async fn i_list_licenses_next_page(world: &mut TestWorld, vessel_id: String, customer_id: String, limit: usize) {
+    let page_token = extract_response(&world.invoke_response)["pageToken"]
+        .as_str()
+        .map(ToString::to_string);
+
+    world.invoke_response = Some(list_licenses(world, customer_id, vessel_id, page_token, Some(limit)).await);
+}
+
+#[when(expr = "I summarize licenses for vessel {string} of customer {string}")]
+async fn i_summarize_vessel_licenses(world: &mut TestWorld, vessel_id: String, customer_id: String) {
+    world.invoke_response = Some(
+        world
+            .lambda
+            .invoke()
+            .function_name(world.summary_lambda.to_string())
+            .payload(serialize_blob!({
+                "customerId": customer_id,
+                "vesselId": vessel_id,
+            }))
+            .send()
+            .await,
+    );
+}
+
+#[when(expr = "I summarize licenses of customer {string}")]
+async fn i_summarize_customer_licenses(world: &mut TestWorld, customer_id: String) {
+    world.invoke_response = Some(
+        world
+            .lambda
+            .invoke()
+            .function_name(world.summary_lambda.to_string())
+            .payload(serialize_blob!({
+                "customerId": customer_id,
+            }))
+            .send()
+            .await,
+    );
+}
+
+#[when(expr = "I resolve entitlements of vessel {string} of customer {string}")]
+async fn i_resolve_entitlements(world: &mut TestWorld, vessel_id: String, customer_id: String) {
+    world.invoke_response = Some(
+        world
+            .lambda
+            .invoke()
+            .function_name(world.entitlements_lambda.to_string())
+            .payload(serialize_blob!({
+                "customerId": customer_id,
+                "vesselId": vessel_id,
+            }))
+            .send()
+            .await,
+    );
 }
 
 // Then …
@@ -292,8 +409,9 @@ async fn i_get_api_error(world: &mut TestWorld, message: String) {
             .as_ref(),
     )
     .unwrap();
+    let error: Value = from_str(&response["errorMessage"]).unwrap();
 
-    assert_eq!(message, response["errorMessage"]);
+    assert_eq!(message, error["message"]);
 }
 
 #[then(expr = "I can read license key as {string}")]
@@ -403,3 +521,28 @@ async fn license_at_position_has_key(world: &mut TestWorld, position: usize, key
         licenses[position].as_object().unwrap()["licenseKey"].as_str().unwrap()
     );
 }
+
+#[then(expr = "I can read summary of {int} licenses with {int} active, {int} expired and {int} seats")]
+async fn i_can_read_summary(world: &mut TestWorld, total: usize, active: usize, expired: usize, seats: usize) {
+    let response = extract_response(&world.invoke_response);
+
+    assert_eq!(total as u64, response["total"].as_u64().unwrap());
+    assert_eq!(active as u64, response["active"].as_u64().unwrap());
+    assert_eq!(expired as u64, response["expired"].as_u64().unwrap());
+    assert_eq!(seats as u64, response["seats"].as_u64().unwrap());
+}
+
+#[then(expr = "I can read entitlements of licenses {string} with {int} seats expiring at {string}")]
+async fn i_can_read_entitlements(world: &mut TestWorld, license_keys: String, seats: usize, expires_at: String) {
+    let response = extract_response(&world.invoke_response);
+
+    let keys: Vec<_> = response["licenseKeys"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|key| key.as_str().unwrap())
+        .collect();
+    assert_eq!(license_keys, keys.join(","));
+    assert_eq!(seats as u64, response["seats"].as_u64().unwrap());
+    assert_eq!(expires_at.as_str(), response["expiresAt"].as_str().unwrap());
+}
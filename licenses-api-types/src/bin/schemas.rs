@@ -0,0 +1,33 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use licenses_api_types::{json_schemas, openapi};
+use serde_json::to_string_pretty;
+use std::env::args;
+use std::error::Error;
+use std::fs::{create_dir_all, write};
+use std::path::PathBuf;
+
+const DEFAULT_OUTPUT_DIR: &str = "target/schemas";
+
+/**
+Writes JSON Schema of every contract type (as `<Type>.schema.json`) and OpenAPI document of HTTP API (as
+`openapi.json`) into directory passed as the only argument.
+ */
+fn main() -> Result<(), Box<dyn Error>> {
+    let output_dir = PathBuf::from(args().nth(1).unwrap_or_else(|| DEFAULT_OUTPUT_DIR.into()));
+    create_dir_all(&output_dir)?;
+
+    for (name, schema) in json_schemas() {
+        write(
+            output_dir.join(format!("{name}.schema.json")),
+            to_string_pretty(&schema)?,
+        )?;
+    }
+    write(output_dir.join("openapi.json"), to_string_pretty(&openapi())?)?;
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Debug)]
+#[serde(untagged)]
+#[doc = "Single feature grant of the license."]
+pub enum Entitlement {
+    #[doc = "Feature switch."]
+    Flag(bool),
+    #[doc = "Numeric limit, eg. maximum number of routes."]
+    Limit(i64),
+    #[doc = "Arbitrary setting, eg. forecast model name."]
+    Value(String),
+}
+
+impl Entitlement {
+    /**
+    Combines two grants of the same entitlement - switches are enabled if any grant enables them, the highest limit
+    wins; in any other case (including type mismatch) `other` grant takes precedence.
+     */
+    pub fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Flag(current), Self::Flag(other)) => Self::Flag(current || other),
+            (Self::Limit(current), Self::Limit(other)) => Self::Limit(current.max(other)),
+            (_, other) => other,
+        }
+    }
+}
+
+#[doc = "Features and limits granted by license, keyed by entitlement name."]
+pub type Entitlements = BTreeMap<String, Entitlement>;
+
+#[cfg(test)]
+mod tests {
+    use crate::{Entitlement, Entitlements};
+    use serde_json::{from_str, json, to_value};
+
+    #[test]
+    fn deserialize_entitlements() {
+        let entitlements: Entitlements =
+            from_str("{\"routing\":true,\"maxRoutes\":20,\"forecastModel\":\"gfs\"}").unwrap();
+
+        assert_eq!(Some(&Entitlement::Flag(true)), entitlements.get("routing"));
+        assert_eq!(Some(&Entitlement::Limit(20)), entitlements.get("maxRoutes"));
+        assert_eq!(
+            Some(&Entitlement::Value("gfs".to_string())),
+            entitlements.get("forecastModel")
+        );
+    }
+
+    #[test]
+    fn serialize_entitlements() {
+        let entitlements = Entitlements::from([
+            ("routing".to_string(), Entitlement::Flag(false)),
+            ("maxRoutes".to_string(), Entitlement::Limit(5)),
+        ]);
+
+        assert_eq!(
+            json!({"maxRoutes": 5, "routing": false}),
+            to_value(entitlements).unwrap()
+        );
+    }
+
+    #[test]
+    fn merge_flags() {
+        assert_eq!(
+            Entitlement::Flag(true),
+            Entitlement::Flag(true).merge(Entitlement::Flag(false))
+        );
+        assert_eq!(
+            Entitlement::Flag(false),
+            Entitlement::Flag(false).merge(Entitlement::Flag(false))
+        );
+    }
+
+    #[test]
+    fn merge_limits() {
+        assert_eq!(
+            Entitlement::Limit(10),
+            Entitlement::Limit(10).merge(Entitlement::Limit(3))
+        );
+        assert_eq!(
+            Entitlement::Limit(10),
+            Entitlement::Limit(3).merge(Entitlement::Limit(10))
+        );
+    }
+
+    #[test]
+    fn merge_other() {
+        assert_eq!(
+            Entitlement::Value("ecmwf".to_string()),
+            Entitlement::Value("gfs".to_string()).merge(Entitlement::Value("ecmwf".to_string()))
+        );
+        assert_eq!(
+            Entitlement::Limit(1),
+            Entitlement::Flag(true).merge(Entitlement::Limit(1))
+        );
+    }
+}
@@ -0,0 +1,82 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::requests::{CreateLicenseRequest, EntitlementsRequest, LicenseRequest, ListLicensesRequest, SummaryRequest};
+use crate::responses::{EntitlementsResponse, LicenseResponse, ListLicensesResponse, SummaryResponse};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "operation", rename_all = "camelCase")]
+#[doc = "Operation envelope accepted by router Lambda."]
+pub enum ApiRequest {
+    Create(CreateLicenseRequest),
+    Delete(LicenseRequest),
+    Get(LicenseRequest),
+    List(ListLicensesRequest),
+    Summary(SummaryRequest),
+    Entitlements(EntitlementsRequest),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+#[doc = "Result of operation executed by router Lambda."]
+pub enum ApiResponse {
+    Create(String),
+    Get(LicenseResponse),
+    List(ListLicensesResponse),
+    Summary(SummaryResponse),
+    Entitlements(EntitlementsResponse),
+    Delete(()),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ApiRequest;
+    use serde_json::from_str;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000000");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+
+    fn request(operation: &str) -> ApiRequest {
+        from_str(&format!(
+            "{{\"operation\":\"{operation}\",\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"licenseKey\":\"tides\"}}"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn deserialize_operations() {
+        assert!(matches!(request("create"), ApiRequest::Create(_)));
+        assert!(matches!(request("delete"), ApiRequest::Delete(_)));
+        assert!(matches!(request("get"), ApiRequest::Get(_)));
+        assert!(matches!(request("list"), ApiRequest::List(_)));
+        assert!(matches!(request("summary"), ApiRequest::Summary(_)));
+        assert!(matches!(request("entitlements"), ApiRequest::Entitlements(_)));
+    }
+
+    #[test]
+    fn deserialize_list_filter() {
+        let request: ApiRequest = from_str(&format!(
+            "{{\"operation\":\"list\",\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"limit\":5,\"activeOnly\":true}}"
+        ))
+        .unwrap();
+
+        match request {
+            ApiRequest::List(request) => {
+                assert_eq!(Some(5), request.limit);
+                assert!(request.active_only);
+            }
+            _ => panic!("List request expected."),
+        }
+    }
+
+    #[test]
+    fn deserialize_unknown_operation() {
+        assert!(from_str::<ApiRequest>("{\"operation\":\"update\"}").is_err());
+    }
+}
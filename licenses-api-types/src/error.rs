@@ -0,0 +1,141 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[doc = "Stable error codes - existing values never change their meaning, new ones may be added."]
+pub enum ErrorCode {
+    #[doc = "Requested license does not exist."]
+    LicenseNotFound,
+    #[doc = "License with given key already exists."]
+    LicenseAlreadyExists,
+    #[doc = "Requested catalogue entry does not exist."]
+    ProductNotFound,
+    #[doc = "Requested customer pool does not exist."]
+    PoolNotFound,
+    #[doc = "Customer already has pool of given product."]
+    PoolAlreadyExists,
+    #[doc = "Pool has not enough free seats (or already ended)."]
+    InsufficientSeats,
+    #[doc = "Vessel already had trial of given product."]
+    TrialAlreadyUsed,
+    #[doc = "License is not a trial."]
+    NotTrial,
+    #[doc = "License is allocated from pool - it can only be released through the pool."]
+    PooledLicense,
+    #[doc = "Page token is malformed, forged or issued for different query."]
+    InvalidPageToken,
+    #[doc = "Request payload is malformed."]
+    InvalidRequest,
+    #[doc = "Request fields violate constraints - details list violations by field name."]
+    ValidationFailed,
+    #[doc = "There is no such HTTP resource."]
+    RouteNotFound,
+    #[doc = "HTTP resource does not support used method."]
+    MethodNotAllowed,
+    #[doc = "Storage state changed concurrently, so that the request no longer applies - repeating it won't help."]
+    Conflict,
+    #[doc = "Request rate exceeded storage capacity."]
+    Throttled,
+    #[doc = "Storage was temporarily unavailable."]
+    ServiceUnavailable,
+    #[doc = "Unexpected failure."]
+    InternalError,
+    #[doc = "Code unknown to this version of the contract."]
+    #[serde(other)]
+    Unknown,
+}
+
+impl ErrorCode {
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Throttled | Self::ServiceUnavailable)
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Public error description."]
+pub struct ErrorResponse {
+    #[doc = "Stable error code."]
+    pub code: ErrorCode,
+    #[doc = "Human-readable description."]
+    pub message: String,
+    #[doc = "Additional context, eg. affected license key."]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<String, String>,
+    #[doc = "Whether the same request may succeed when repeated later."]
+    pub retryable: bool,
+}
+
+impl ErrorResponse {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: BTreeMap::new(),
+            retryable: code.is_retryable(),
+        }
+    }
+
+    pub fn with_detail(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.details.insert(name.into(), value.into());
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Payload of failed Lambda invocation - error message carries serialized `ErrorResponse`."]
+pub struct InvocationError {
+    #[doc = "Error type, as reported by Lambda runtime."]
+    pub error_type: String,
+    #[doc = "Error description."]
+    pub error_message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ErrorCode, ErrorResponse};
+    use serde_json::{from_str, json, to_value};
+
+    #[test]
+    fn serialize_error() {
+        let value = to_value(
+            ErrorResponse::new(ErrorCode::LicenseNotFound, "License not found.").with_detail("licenseKey", "tides"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            json!({
+                "code": "LICENSE_NOT_FOUND",
+                "message": "License not found.",
+                "details": {"licenseKey": "tides"},
+                "retryable": false,
+            }),
+            value
+        );
+    }
+
+    #[test]
+    fn serialize_error_no_details() {
+        let value = to_value(ErrorResponse::new(ErrorCode::Throttled, "Too many requests.")).unwrap();
+
+        assert!(value.get("details").is_none());
+        assert_eq!(json!(true), value["retryable"]);
+    }
+
+    #[test]
+    fn deserialize_unknown_code() {
+        let error: ErrorResponse =
+            from_str("{\"code\":\"QUOTA_EXCEEDED\",\"message\":\"\",\"retryable\":false}").unwrap();
+
+        assert_eq!(ErrorCode::Unknown, error.code);
+    }
+}
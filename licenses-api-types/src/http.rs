@@ -0,0 +1,70 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::entitlements::Entitlements;
+use crate::seats::Seats;
+use chrono::{DateTime, FixedOffset};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Body of license creation HTTP request."]
+pub struct CreateLicenseBody {
+    #[doc = "License entry key."]
+    pub license_key: String,
+    #[doc = "Number of license activations or `\"unlimited\"` - product default when not specified."]
+    pub count: Option<Seats>,
+    #[doc = "Date when license ends."]
+    pub expires_at: Option<DateTime<FixedOffset>>,
+    #[doc = "Features and limits granted by license."]
+    #[serde(default)]
+    pub entitlements: Entitlements,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Body of license replacement HTTP request."]
+pub struct UpdateLicenseBody {
+    #[doc = "Number of license activations or `\"unlimited\"` - product default when not specified."]
+    pub count: Option<Seats>,
+    #[doc = "Date when license ends."]
+    pub expires_at: Option<DateTime<FixedOffset>>,
+    #[doc = "Features and limits granted by license."]
+    #[serde(default)]
+    pub entitlements: Entitlements,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Query string of licenses listing HTTP request."]
+pub struct ListLicensesQuery {
+    #[doc = "Token of the page to fetch, as returned by previous call."]
+    pub page_token: Option<String>,
+    #[doc = "Maximum number of licenses in page."]
+    pub limit: Option<u16>,
+    #[doc = "Prefix of returned license keys."]
+    pub license_key_prefix: Option<String>,
+    #[doc = "Return only licenses that already ended."]
+    #[serde(default)]
+    pub expired_only: bool,
+    #[doc = "Return only licenses that did not end yet."]
+    #[serde(default)]
+    pub active_only: bool,
+    #[doc = "List licenses in reversed key order."]
+    #[serde(default)]
+    pub descending: bool,
+    #[doc = "Moment in the past at which licenses are reconstructed from history."]
+    pub as_of: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Query string of single license HTTP request."]
+pub struct LicenseQuery {
+    #[doc = "Moment in the past at which license state is reconstructed from history."]
+    pub as_of: Option<DateTime<FixedOffset>>,
+}
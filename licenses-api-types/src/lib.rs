@@ -0,0 +1,39 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+mod entitlements;
+mod envelope;
+mod error;
+mod http;
+mod pools;
+mod products;
+mod requests;
+mod responses;
+mod schema;
+mod seats;
+mod trials;
+
+pub use crate::entitlements::{Entitlement, Entitlements};
+pub use crate::envelope::{ApiRequest, ApiResponse};
+pub use crate::error::{ErrorCode, ErrorResponse, InvocationError};
+pub use crate::http::{CreateLicenseBody, LicenseQuery, ListLicensesQuery, UpdateLicenseBody};
+pub use crate::pools::{
+    AllocateSeatsRequest, CreatePoolRequest, ListPoolsRequest, ListPoolsResponse, PoolResponse, PoolsRequest,
+    PoolsResponse, ReleaseSeatsRequest,
+};
+pub use crate::products::{
+    ListProductsResponse, ProductRequest, ProductResponse, ProductsRequest, ProductsResponse, SaveProductRequest,
+};
+pub use crate::requests::{
+    CreateLicenseRequest, EntitlementsRequest, LicenseRequest, ListLicensesRequest, SummaryRequest,
+    DEFAULT_EXPIRING_WITHIN_DAYS,
+};
+pub use crate::responses::{
+    EntitlementsResponse, LicenseResponse, ListLicensesResponse, SkippedLicense, SummaryResponse,
+};
+pub use crate::schema::{json_schemas, openapi};
+pub use crate::seats::Seats;
+pub use crate::trials::{ConvertTrialRequest, StartTrialRequest, Trial, TrialsRequest, TrialsResponse};
@@ -0,0 +1,168 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use chrono::{DateTime, FixedOffset};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Creates customer pool of seats shared by the whole fleet."]
+pub struct CreatePoolRequest {
+    #[doc = "Owner ID."]
+    pub customer_id: Uuid,
+    #[doc = "Product key - license key of licenses allocated from the pool."]
+    pub product_key: String,
+    #[doc = "Number of seats bought for the fleet."]
+    pub total_seats: u32,
+    #[doc = "Date when pool (and all licenses allocated from it) ends."]
+    pub expires_at: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Lists pools of the customer."]
+pub struct ListPoolsRequest {
+    #[doc = "Owner ID."]
+    pub customer_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Assigns seats from the pool to the vessel, as a license of pool product."]
+pub struct AllocateSeatsRequest {
+    #[doc = "Owner ID."]
+    pub customer_id: Uuid,
+    #[doc = "Pool product key."]
+    pub product_key: String,
+    #[doc = "Vessel ID."]
+    pub vessel_id: Uuid,
+    #[doc = "Number of seats assigned to the vessel."]
+    pub seats: u32,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Returns seats allocated to the vessel back to the pool."]
+pub struct ReleaseSeatsRequest {
+    #[doc = "Owner ID."]
+    pub customer_id: Uuid,
+    #[doc = "Pool product key."]
+    pub product_key: String,
+    #[doc = "Vessel ID."]
+    pub vessel_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Pool utilisation."]
+pub struct PoolResponse {
+    #[doc = "Product key."]
+    pub product_key: String,
+    #[doc = "Number of seats bought for the fleet."]
+    pub total_seats: u32,
+    #[doc = "Number of seats assigned to vessels."]
+    pub allocated_seats: u32,
+    #[doc = "Date when pool ends."]
+    pub expires_at: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Pools of the customer."]
+pub struct ListPoolsResponse {
+    #[doc = "All customer pools, ordered by product key."]
+    pub pools: Vec<PoolResponse>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "operation", rename_all = "camelCase")]
+#[doc = "Operation envelope accepted by pools management Lambda."]
+pub enum PoolsRequest {
+    Create(CreatePoolRequest),
+    List(ListPoolsRequest),
+    Allocate(AllocateSeatsRequest),
+    Release(ReleaseSeatsRequest),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+#[doc = "Result of operation executed by pools management Lambda."]
+pub enum PoolsResponse {
+    Create(String),
+    List(ListPoolsResponse),
+    Allocate(()),
+    Release(()),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ListPoolsResponse, PoolResponse, PoolsRequest};
+    use serde_json::{from_str, json, to_value};
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000000");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+
+    #[test]
+    fn deserialize_allocate() {
+        let request: PoolsRequest = from_str(&format!(
+            "{{\"operation\":\"allocate\",\"customerId\":\"{CUSTOMER_ID}\",\"productKey\":\"tides\",\"vesselId\":\"{VESSEL_ID}\",\"seats\":2}}"
+        ))
+        .unwrap();
+
+        match request {
+            PoolsRequest::Allocate(request) => {
+                assert_eq!(CUSTOMER_ID, request.customer_id);
+                assert_eq!("tides", request.product_key);
+                assert_eq!(VESSEL_ID, request.vessel_id);
+                assert_eq!(2, request.seats);
+            }
+            _ => panic!("Allocate request expected."),
+        }
+    }
+
+    #[test]
+    fn deserialize_operations() {
+        assert!(matches!(
+            from_str::<PoolsRequest>(&format!(
+                "{{\"operation\":\"create\",\"customerId\":\"{CUSTOMER_ID}\",\"productKey\":\"tides\",\"totalSeats\":50}}"
+            ))
+            .unwrap(),
+            PoolsRequest::Create(_)
+        ));
+        assert!(matches!(
+            from_str::<PoolsRequest>(&format!("{{\"operation\":\"list\",\"customerId\":\"{CUSTOMER_ID}\"}}")).unwrap(),
+            PoolsRequest::List(_)
+        ));
+        assert!(matches!(
+            from_str::<PoolsRequest>(&format!(
+                "{{\"operation\":\"release\",\"customerId\":\"{CUSTOMER_ID}\",\"productKey\":\"tides\",\"vesselId\":\"{VESSEL_ID}\"}}"
+            ))
+            .unwrap(),
+            PoolsRequest::Release(_)
+        ));
+    }
+
+    #[test]
+    fn serialize_list_response() {
+        let value = to_value(ListPoolsResponse {
+            pools: vec![PoolResponse {
+                product_key: "tides".to_string(),
+                total_seats: 50,
+                allocated_seats: 12,
+                expires_at: None,
+            }],
+        })
+        .unwrap();
+
+        assert_eq!(
+            json!({"pools": [{"productKey": "tides", "totalSeats": 50, "allocatedSeats": 12, "expiresAt": null}]}),
+            value
+        );
+    }
+}
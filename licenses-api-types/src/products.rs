@@ -0,0 +1,115 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::seats::Seats;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Creates (or replaces) catalogue entry."]
+pub struct SaveProductRequest {
+    #[doc = "Product key - license key of issued licenses."]
+    pub product_key: String,
+    #[doc = "Human-readable product name."]
+    pub display_name: String,
+    #[doc = "Number of activations of new licenses, when not specified explicitly."]
+    pub default_count: Option<Seats>,
+    #[doc = "Validity period, in days, of new licenses, when expiration date is not specified explicitly."]
+    pub default_duration_days: Option<u16>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Catalogue entry - product for which licenses can be issued."]
+pub struct ProductResponse {
+    #[doc = "Product key - license key of issued licenses."]
+    pub product_key: String,
+    #[doc = "Human-readable product name."]
+    pub display_name: String,
+    #[doc = "Number of activations of new licenses, when not specified explicitly."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_count: Option<Seats>,
+    #[doc = "Validity period, in days, of new licenses, when expiration date is not specified explicitly."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_duration_days: Option<u16>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Points single catalogue entry - used to fetch or delete it."]
+pub struct ProductRequest {
+    #[doc = "Product key."]
+    pub product_key: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Catalogue entries."]
+pub struct ListProductsResponse {
+    #[doc = "All products, ordered by key."]
+    pub products: Vec<ProductResponse>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "operation", rename_all = "camelCase")]
+#[doc = "Operation envelope accepted by catalogue management Lambda."]
+pub enum ProductsRequest {
+    Save(SaveProductRequest),
+    Get(ProductRequest),
+    List,
+    Delete(ProductRequest),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+#[doc = "Result of operation executed by catalogue management Lambda."]
+pub enum ProductsResponse {
+    Save(String),
+    Get(ProductResponse),
+    List(ListProductsResponse),
+    Delete(()),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ProductsRequest;
+    use serde_json::from_str;
+
+    #[test]
+    fn deserialize_save() {
+        let request: ProductsRequest = from_str(
+            "{\"operation\":\"save\",\"productKey\":\"tides.2023\",\"displayName\":\"Tides 2023\",\"defaultDurationDays\":365}",
+        )
+        .unwrap();
+
+        match request {
+            ProductsRequest::Save(product) => {
+                assert_eq!("tides.2023", product.product_key);
+                assert_eq!("Tides 2023", product.display_name);
+                assert!(product.default_count.is_none());
+                assert_eq!(Some(365), product.default_duration_days);
+            }
+            _ => panic!("Save request expected."),
+        }
+    }
+
+    #[test]
+    fn deserialize_operations() {
+        assert!(matches!(
+            from_str("{\"operation\":\"get\",\"productKey\":\"tides\"}").unwrap(),
+            ProductsRequest::Get(_)
+        ));
+        assert!(matches!(
+            from_str("{\"operation\":\"delete\",\"productKey\":\"tides\"}").unwrap(),
+            ProductsRequest::Delete(_)
+        ));
+        assert!(matches!(
+            from_str("{\"operation\":\"list\"}").unwrap(),
+            ProductsRequest::List
+        ));
+    }
+}
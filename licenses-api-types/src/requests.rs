@@ -0,0 +1,237 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::entitlements::Entitlements;
+use crate::seats::Seats;
+use chrono::{DateTime, FixedOffset};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const DEFAULT_EXPIRING_WITHIN_DAYS: u16 = 30;
+
+fn default_expiring_within_days() -> u16 {
+    DEFAULT_EXPIRING_WITHIN_DAYS
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Creates (or replaces) license."]
+pub struct CreateLicenseRequest {
+    #[doc = "Owner ID."]
+    pub customer_id: Uuid,
+    #[doc = "Vessel ID."]
+    pub vessel_id: Uuid,
+    #[doc = "License entry key."]
+    pub license_key: String,
+    #[doc = "Number of license activations or `\"unlimited\"` - product default when not specified."]
+    pub count: Option<Seats>,
+    #[doc = "Date when license ends."]
+    pub expires_at: Option<DateTime<FixedOffset>>,
+    #[doc = "Features and limits granted by license."]
+    #[serde(default)]
+    pub entitlements: Entitlements,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Points single license - used to fetch or delete it."]
+pub struct LicenseRequest {
+    #[doc = "Owner ID."]
+    pub customer_id: Uuid,
+    #[doc = "Vessel ID."]
+    pub vessel_id: Uuid,
+    #[doc = "License entry key."]
+    pub license_key: String,
+    #[doc = "Moment in the past at which license state is reconstructed from history - current state if omitted."]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub as_of: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Lists licenses of the vessel."]
+pub struct ListLicensesRequest {
+    #[doc = "Owner ID."]
+    pub customer_id: Uuid,
+    #[doc = "Vessel ID."]
+    pub vessel_id: Uuid,
+    #[doc = "Token of the page to fetch, as returned by previous call."]
+    pub page_token: Option<String>,
+    #[doc = "Maximum number of licenses in page."]
+    pub limit: Option<u16>,
+    #[doc = "Prefix of returned license keys."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license_key_prefix: Option<String>,
+    #[doc = "Return only licenses that already ended."]
+    #[serde(default)]
+    pub expired_only: bool,
+    #[doc = "Return only licenses that did not end yet."]
+    #[serde(default)]
+    pub active_only: bool,
+    #[doc = "List licenses in reversed key order."]
+    #[serde(default)]
+    pub descending: bool,
+    #[doc = "Moment in the past at which licenses are reconstructed from history - current state if omitted."]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub as_of: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Aggregates licenses of the vessel or of all customer vessels."]
+pub struct SummaryRequest {
+    #[doc = "Owner ID."]
+    pub customer_id: Uuid,
+    #[doc = "Vessel ID - whole customer is summarized if omitted."]
+    pub vessel_id: Option<Uuid>,
+    #[doc = "Horizon, in days, of licenses considered as expiring soon."]
+    #[serde(default = "default_expiring_within_days")]
+    pub expiring_within_days: u16,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Resolves effective entitlements of the vessel."]
+pub struct EntitlementsRequest {
+    #[doc = "Owner ID."]
+    pub customer_id: Uuid,
+    #[doc = "Vessel ID."]
+    pub vessel_id: Uuid,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        CreateLicenseRequest, LicenseRequest, ListLicensesRequest, Seats, SummaryRequest, DEFAULT_EXPIRING_WITHIN_DAYS,
+    };
+    use serde_json::{from_str, to_value};
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000000");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    const LICENSE_KEY: &str = "weather0";
+    const COUNT: Seats = Seats::Limited(2);
+    const PAGE_TOKEN: &str = "abc";
+    const LIMIT: u16 = 10;
+    const PREFIX: &str = "Test";
+
+    #[test]
+    fn deserialize_create_request() {
+        let input = format!(
+            "{{\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"licenseKey\":\"{LICENSE_KEY}\"}}"
+        );
+        let request: CreateLicenseRequest = from_str(&input).unwrap();
+
+        assert_eq!(CUSTOMER_ID, request.customer_id);
+        assert_eq!(LICENSE_KEY, request.license_key);
+        assert!(request.count.is_none());
+        assert!(request.entitlements.is_empty());
+    }
+
+    #[test]
+    fn deserialize_create_request_optional() {
+        let input = format!("{{\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"licenseKey\":\"{LICENSE_KEY}\",\"count\":{COUNT},\"expiresAt\":\"2011-01-30T14:58:00+01:00\"}}");
+        let request: CreateLicenseRequest = from_str(&input).unwrap();
+
+        assert_eq!(Some(COUNT), request.count);
+        assert_eq!("2011-01-30T14:58:00+01:00", request.expires_at.unwrap().to_rfc3339());
+    }
+
+    #[test]
+    fn deserialize_create_request_unlimited() {
+        let input = format!("{{\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"licenseKey\":\"{LICENSE_KEY}\",\"count\":\"unlimited\"}}");
+        let request: CreateLicenseRequest = from_str(&input).unwrap();
+
+        assert_eq!(Some(Seats::Unlimited), request.count);
+    }
+
+    #[test]
+    fn deserialize_license_request() {
+        let input = format!(
+            "{{\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"licenseKey\":\"{LICENSE_KEY}\"}}"
+        );
+        let request: LicenseRequest = from_str(&input).unwrap();
+
+        assert_eq!(CUSTOMER_ID, request.customer_id);
+        assert_eq!(VESSEL_ID, request.vessel_id);
+        assert_eq!(LICENSE_KEY, request.license_key);
+        assert!(request.as_of.is_none());
+    }
+
+    #[test]
+    fn deserialize_license_request_as_of() {
+        let input = format!(
+            "{{\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"licenseKey\":\"{LICENSE_KEY}\",\"asOf\":\"2023-03-01T12:00:00+02:00\"}}"
+        );
+        let request: LicenseRequest = from_str(&input).unwrap();
+
+        assert_eq!(
+            Some("2023-03-01T12:00:00+02:00".to_string()),
+            request.as_of.map(|as_of| as_of.to_rfc3339())
+        );
+    }
+
+    #[test]
+    fn deserialize_list_request() {
+        let input =
+            format!("{{\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"pageToken\":\"{PAGE_TOKEN}\"}}");
+        let request: ListLicensesRequest = from_str(&input).unwrap();
+
+        assert_eq!(CUSTOMER_ID, request.customer_id);
+        assert_eq!(VESSEL_ID, request.vessel_id);
+        assert_eq!(Some(PAGE_TOKEN.to_string()), request.page_token);
+        assert!(request.limit.is_none());
+        assert!(request.license_key_prefix.is_none());
+        assert!(!request.expired_only);
+        assert!(!request.active_only);
+        assert!(!request.descending);
+    }
+
+    #[test]
+    fn deserialize_list_request_filters() {
+        let input = format!(
+            "{{\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"limit\":{LIMIT},\"licenseKeyPrefix\":\"{PREFIX}\",\"activeOnly\":true,\"descending\":true}}"
+        );
+        let request: ListLicensesRequest = from_str(&input).unwrap();
+
+        assert_eq!(Some(LIMIT), request.limit);
+        assert_eq!(Some(PREFIX.to_string()), request.license_key_prefix);
+        assert!(request.active_only);
+        assert!(!request.expired_only);
+        assert!(request.descending);
+    }
+
+    #[test]
+    fn serialize_list_request() {
+        let value = to_value(ListLicensesRequest {
+            customer_id: CUSTOMER_ID,
+            vessel_id: VESSEL_ID,
+            page_token: None,
+            limit: Some(LIMIT),
+            license_key_prefix: None,
+            expired_only: false,
+            active_only: true,
+            descending: false,
+            as_of: None,
+        })
+        .unwrap();
+
+        assert_eq!(LIMIT, value["limit"]);
+        assert_eq!(true, value["activeOnly"]);
+        assert!(value.get("licenseKeyPrefix").is_none());
+        assert!(value.get("asOf").is_none());
+    }
+
+    #[test]
+    fn deserialize_summary_request_defaults() {
+        let request: SummaryRequest = from_str(&format!("{{\"customerId\":\"{CUSTOMER_ID}\"}}")).unwrap();
+
+        assert_eq!(CUSTOMER_ID, request.customer_id);
+        assert!(request.vessel_id.is_none());
+        assert_eq!(DEFAULT_EXPIRING_WITHIN_DAYS, request.expiring_within_days);
+    }
+}
@@ -0,0 +1,222 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::entitlements::Entitlements;
+use crate::seats::Seats;
+use crate::trials::Trial;
+use chrono::{DateTime, FixedOffset};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "License details."]
+pub struct LicenseResponse {
+    #[doc = "License entry key."]
+    pub license_key: String,
+    #[doc = "Number of license activations or `\"unlimited\"`."]
+    pub count: Option<Seats>,
+    #[doc = "Date when license ends."]
+    pub expires_at: Option<DateTime<FixedOffset>>,
+    #[doc = "Features and limits granted by license."]
+    #[serde(default, skip_serializing_if = "Entitlements::is_empty")]
+    pub entitlements: Entitlements,
+    #[doc = "Trial details - absent for paid licenses."]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trial: Option<Trial>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Page of licenses."]
+pub struct ListLicensesResponse {
+    #[doc = "Licenses in the page."]
+    pub licenses: Vec<LicenseResponse>,
+    #[doc = "Token of the next page, absent on the last page."]
+    pub page_token: Option<String>,
+    #[doc = "Whether some licenses were left out of the page, because their entries are corrupted."]
+    #[serde(default)]
+    pub partial: bool,
+    #[doc = "Licenses left out of the page."]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<SkippedLicense>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "License that couldn't be read."]
+pub struct SkippedLicense {
+    #[doc = "License entry key."]
+    pub license_key: String,
+    #[doc = "Why the license was skipped."]
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Licenses counts by state."]
+pub struct SummaryResponse {
+    #[doc = "Number of all licenses."]
+    pub total: usize,
+    #[doc = "Number of licenses that did not end yet."]
+    pub active: usize,
+    #[doc = "Number of licenses that already ended."]
+    pub expired: usize,
+    #[doc = "Number of active licenses that end within requested horizon."]
+    pub expiring_soon: usize,
+    #[doc = "Total number of activations of active licenses with limited seats."]
+    pub seats: u64,
+    #[doc = "Whether any active license grants unlimited activations."]
+    #[serde(default)]
+    pub unlimited_seats: bool,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "What the vessel is allowed to do right now - combination of all its active licenses."]
+pub struct EntitlementsResponse {
+    #[doc = "Keys of all active licenses, ordered."]
+    pub license_keys: Vec<String>,
+    #[doc = "Earliest end date of active licenses - absent if all of them are perpetual."]
+    pub expires_at: Option<DateTime<FixedOffset>>,
+    #[doc = "Total number of activations of active licenses with limited seats."]
+    pub seats: u64,
+    #[doc = "Whether any active license grants unlimited activations."]
+    #[serde(default)]
+    pub unlimited_seats: bool,
+    #[doc = "Merged features and limits of active licenses."]
+    pub entitlements: Entitlements,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Entitlement, Entitlements, EntitlementsResponse, LicenseResponse, ListLicensesResponse, Seats, SkippedLicense,
+        SummaryResponse,
+    };
+    use serde_json::{from_str, to_string};
+
+    const LICENSE_KEY: &str = "tides.2023";
+    const COUNT: Seats = Seats::Limited(6);
+    const PAGE_TOKEN: &str = "abc";
+
+    fn license() -> LicenseResponse {
+        LicenseResponse {
+            license_key: LICENSE_KEY.to_string(),
+            count: Some(COUNT),
+            expires_at: None,
+            entitlements: Entitlements::from([("routing".to_string(), Entitlement::Flag(true))]),
+            trial: None,
+        }
+    }
+
+    #[test]
+    fn serialize_license_response() {
+        let output = to_string(&license()).unwrap();
+
+        assert!(output.contains(&format!("\"{LICENSE_KEY}\"")));
+        assert!(output.contains(&format!("{COUNT}")));
+        assert!(output.contains("\"entitlements\":{\"routing\":true}"));
+        assert!(!output.contains("\"trial\""));
+    }
+
+    #[test]
+    fn serialize_unlimited_license_response() {
+        let output = to_string(&LicenseResponse {
+            count: Some(Seats::Unlimited),
+            ..license()
+        })
+        .unwrap();
+
+        assert!(output.contains("\"count\":\"unlimited\""));
+    }
+
+    #[test]
+    fn serialize_list_response() {
+        let output = to_string(&ListLicensesResponse {
+            licenses: vec![license()],
+            page_token: Some(PAGE_TOKEN.to_string()),
+            partial: false,
+            warnings: vec![],
+        })
+        .unwrap();
+
+        assert!(output.contains(&format!("{COUNT}")));
+        assert!(output.contains(&format!("\"pageToken\":\"{PAGE_TOKEN}\"")));
+        assert!(output.contains("\"partial\":false"));
+        assert!(!output.contains("\"warnings\""));
+    }
+
+    #[test]
+    fn serialize_list_response_partial() {
+        let output = to_string(&ListLicensesResponse {
+            licenses: vec![license()],
+            page_token: None,
+            partial: true,
+            warnings: vec![SkippedLicense {
+                license_key: "broken".to_string(),
+                reason: "invalid date".to_string(),
+            }],
+        })
+        .unwrap();
+
+        assert!(output.contains("\"partial\":true"));
+        assert!(output.contains("\"warnings\":[{\"licenseKey\":\"broken\",\"reason\":\"invalid date\"}]"));
+    }
+
+    #[test]
+    fn deserialize_list_response_legacy() {
+        let response: ListLicensesResponse = from_str("{\"licenses\":[],\"pageToken\":null}").unwrap();
+
+        assert!(!response.partial);
+        assert!(response.warnings.is_empty());
+    }
+
+    #[test]
+    fn serialize_list_response_no_page() {
+        let output = to_string(&ListLicensesResponse {
+            licenses: vec![license()],
+            page_token: None,
+            partial: false,
+            warnings: vec![],
+        })
+        .unwrap();
+
+        assert!(!output.contains("\"page_token\":"));
+    }
+
+    #[test]
+    fn serialize_summary_response() {
+        let output = to_string(&SummaryResponse {
+            total: 12,
+            active: 9,
+            expired: 3,
+            expiring_soon: 2,
+            seats: 20,
+            unlimited_seats: false,
+        })
+        .unwrap();
+
+        assert!(output.contains("\"total\":12"));
+        assert!(output.contains("\"expiringSoon\":2"));
+    }
+
+    #[test]
+    fn serialize_entitlements_response() {
+        let output = to_string(&EntitlementsResponse {
+            license_keys: vec![LICENSE_KEY.to_string()],
+            expires_at: None,
+            seats: 3,
+            unlimited_seats: true,
+            entitlements: Entitlements::from([("maxRoutes".to_string(), Entitlement::Limit(20))]),
+        })
+        .unwrap();
+
+        assert!(output.contains(&format!("\"licenseKeys\":[\"{LICENSE_KEY}\"]")));
+        assert!(output.contains("\"unlimitedSeats\":true"));
+        assert!(output.contains("\"entitlements\":{\"maxRoutes\":20}"));
+    }
+}
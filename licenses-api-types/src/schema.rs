@@ -0,0 +1,238 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::envelope::{ApiRequest, ApiResponse};
+use crate::error::{ErrorResponse, InvocationError};
+use crate::http::{CreateLicenseBody, UpdateLicenseBody};
+use crate::pools::{PoolsRequest, PoolsResponse};
+use crate::products::{ProductsRequest, ProductsResponse};
+use crate::requests::{CreateLicenseRequest, EntitlementsRequest, LicenseRequest, ListLicensesRequest, SummaryRequest};
+use crate::responses::{EntitlementsResponse, LicenseResponse, ListLicensesResponse, SummaryResponse};
+use crate::trials::{TrialsRequest, TrialsResponse};
+use schemars::gen::SchemaSettings;
+use schemars::schema::{RootSchema, Schema};
+use schemars::schema_for;
+use serde_json::{json, Value};
+
+/**
+JSON Schemas of all Lambda contracts, keyed by type name.
+ */
+pub fn json_schemas() -> Vec<(&'static str, RootSchema)> {
+    vec![
+        ("CreateLicenseRequest", schema_for!(CreateLicenseRequest)),
+        ("LicenseRequest", schema_for!(LicenseRequest)),
+        ("ListLicensesRequest", schema_for!(ListLicensesRequest)),
+        ("SummaryRequest", schema_for!(SummaryRequest)),
+        ("EntitlementsRequest", schema_for!(EntitlementsRequest)),
+        ("LicenseResponse", schema_for!(LicenseResponse)),
+        ("ListLicensesResponse", schema_for!(ListLicensesResponse)),
+        ("SummaryResponse", schema_for!(SummaryResponse)),
+        ("EntitlementsResponse", schema_for!(EntitlementsResponse)),
+        ("ApiRequest", schema_for!(ApiRequest)),
+        ("ApiResponse", schema_for!(ApiResponse)),
+        ("ProductsRequest", schema_for!(ProductsRequest)),
+        ("ProductsResponse", schema_for!(ProductsResponse)),
+        ("PoolsRequest", schema_for!(PoolsRequest)),
+        ("PoolsResponse", schema_for!(PoolsResponse)),
+        ("TrialsRequest", schema_for!(TrialsRequest)),
+        ("TrialsResponse", schema_for!(TrialsResponse)),
+        ("ErrorResponse", schema_for!(ErrorResponse)),
+        ("InvocationError", schema_for!(InvocationError)),
+    ]
+}
+
+fn content(schema: Schema) -> Value {
+    json!({
+        "application/json": {
+            "schema": schema,
+        },
+    })
+}
+
+fn path_parameter(name: &str, description: &str, format: Option<&str>) -> Value {
+    let mut schema = json!({ "type": "string" });
+    if let Some(format) = format {
+        schema["format"] = json!(format);
+    }
+
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "description": description,
+        "schema": schema,
+    })
+}
+
+fn query_parameter(name: &str, description: &str, schema_type: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "query",
+        "required": false,
+        "description": description,
+        "schema": {
+            "type": schema_type,
+        },
+    })
+}
+
+/**
+OpenAPI document of HTTP API.
+ */
+pub fn openapi() -> Value {
+    let mut generator = SchemaSettings::openapi3().into_generator();
+
+    let create_body = content(generator.subschema_for::<CreateLicenseBody>());
+    let update_body = content(generator.subschema_for::<UpdateLicenseBody>());
+    let license = content(generator.subschema_for::<LicenseResponse>());
+    let list = content(generator.subschema_for::<ListLicensesResponse>());
+    let key = content(generator.subschema_for::<String>());
+    let error = |description: &str, generator: &mut schemars::gen::SchemaGenerator| {
+        json!({
+            "description": description,
+            "content": content(generator.subschema_for::<ErrorResponse>()),
+        })
+    };
+    let bad_request = error("Invalid request.", &mut generator);
+    let not_found = error("License not found.", &mut generator);
+    let conflict = error("License already exists.", &mut generator);
+    let unexpected = error("Service failure - retry if flagged as retryable.", &mut generator);
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "IVMS Licenses service",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": {
+            "/customers/{customerId}/vessels/{vesselId}/licenses": {
+                "parameters": [
+                    path_parameter("customerId", "Owner ID.", Some("uuid")),
+                    path_parameter("vesselId", "Vessel ID.", Some("uuid")),
+                ],
+                "get": {
+                    "operationId": "listLicenses",
+                    "parameters": [
+                        query_parameter("pageToken", "Token of the page to fetch.", "string"),
+                        query_parameter("limit", "Maximum number of licenses in page.", "integer"),
+                        query_parameter("licenseKeyPrefix", "Prefix of returned license keys.", "string"),
+                        query_parameter("expiredOnly", "Return only licenses that already ended.", "boolean"),
+                        query_parameter("activeOnly", "Return only licenses that did not end yet.", "boolean"),
+                        query_parameter("descending", "List licenses in reversed key order.", "boolean"),
+                        query_parameter("asOf", "Moment (RFC 3339) at which licenses are reconstructed.", "string"),
+                    ],
+                    "responses": {
+                        "200": {"description": "Page of licenses.", "content": list},
+                        "400": bad_request,
+                        "default": unexpected,
+                    },
+                },
+                "post": {
+                    "operationId": "createLicense",
+                    "requestBody": {"required": true, "content": create_body},
+                    "responses": {
+                        "201": {"description": "Created license key.", "content": key},
+                        "400": bad_request,
+                        "default": unexpected,
+                        "409": conflict,
+                    },
+                },
+            },
+            "/customers/{customerId}/vessels/{vesselId}/licenses/{licenseKey}": {
+                "parameters": [
+                    path_parameter("customerId", "Owner ID.", Some("uuid")),
+                    path_parameter("vesselId", "Vessel ID.", Some("uuid")),
+                    path_parameter("licenseKey", "License entry key.", None),
+                ],
+                "get": {
+                    "operationId": "getLicense",
+                    "parameters": [
+                        query_parameter("asOf", "Moment (RFC 3339) at which license is reconstructed.", "string"),
+                    ],
+                    "responses": {
+                        "200": {"description": "License details.", "content": license},
+                        "400": bad_request,
+                        "default": unexpected,
+                        "404": not_found,
+                    },
+                },
+                "put": {
+                    "operationId": "putLicense",
+                    "requestBody": {"required": true, "content": update_body},
+                    "responses": {
+                        "200": {"description": "Stored license key.", "content": key},
+                        "400": bad_request,
+                        "default": unexpected,
+                    },
+                },
+                "delete": {
+                    "operationId": "deleteLicense",
+                    "responses": {
+                        "204": {"description": "License deleted."},
+                        "400": bad_request,
+                        "default": unexpected,
+                    },
+                },
+            },
+        },
+        "components": {
+            "schemas": generator.take_definitions(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{json_schemas, openapi};
+    use serde_json::Value;
+    use std::collections::HashSet;
+
+    fn collect_refs(value: &Value, refs: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(reference)) = map.get("$ref") {
+                    refs.push(reference.clone());
+                }
+                map.values().for_each(|value| collect_refs(value, refs));
+            }
+            Value::Array(items) => items.iter().for_each(|value| collect_refs(value, refs)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn unique_schema_names() {
+        let schemas = json_schemas();
+        let names = schemas.iter().map(|(name, _)| *name).collect::<HashSet<_>>();
+
+        assert_eq!(schemas.len(), names.len());
+    }
+
+    #[test]
+    fn openapi_paths() {
+        let document = openapi();
+        let paths = document["paths"].as_object().unwrap();
+
+        assert!(paths["/customers/{customerId}/vessels/{vesselId}/licenses"]["post"].is_object());
+        assert!(paths["/customers/{customerId}/vessels/{vesselId}/licenses/{licenseKey}"]["delete"].is_object());
+    }
+
+    #[test]
+    fn openapi_references_resolve() {
+        let document = openapi();
+        let mut refs = vec![];
+        collect_refs(&document, &mut refs);
+
+        assert!(!refs.is_empty());
+        for reference in refs {
+            let name = reference.strip_prefix("#/components/schemas/").unwrap();
+            assert!(
+                document["components"]["schemas"].get(name).is_some(),
+                "Unresolved reference {reference}."
+            );
+        }
+    }
+}
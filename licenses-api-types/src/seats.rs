@@ -0,0 +1,173 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Metadata, Schema, SchemaObject, SubschemaValidation};
+use schemars::JsonSchema;
+use serde::de::{Error, Unexpected, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter, Result as FormatResult};
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+const UNLIMITED: &str = "unlimited";
+
+/**
+Activations limit of the license.
+
+Serialized as plain number (same as legacy `u8` count, so stored items and existing clients keep working) or as
+`"unlimited"` string.
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Seats {
+    #[doc = "Fixed number of activations."]
+    Limited(u32),
+    #[doc = "No activations limit."]
+    Unlimited,
+}
+
+impl Seats {
+    /**
+    Number of activations, `None` for unlimited seats.
+     */
+    pub fn limit(&self) -> Option<u32> {
+        match self {
+            Self::Limited(count) => Some(*count),
+            Self::Unlimited => None,
+        }
+    }
+}
+
+impl Display for Seats {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> FormatResult {
+        match self {
+            Self::Limited(count) => write!(formatter, "{count}"),
+            Self::Unlimited => write!(formatter, "{UNLIMITED}"),
+        }
+    }
+}
+
+impl FromStr for Seats {
+    type Err = ParseIntError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            UNLIMITED => Ok(Self::Unlimited),
+            count => count.parse().map(Self::Limited),
+        }
+    }
+}
+
+impl Serialize for Seats {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Limited(count) => serializer.serialize_u32(*count),
+            Self::Unlimited => serializer.serialize_str(UNLIMITED),
+        }
+    }
+}
+
+struct SeatsVisitor;
+
+impl<'de> Visitor<'de> for SeatsVisitor {
+    type Value = Seats;
+
+    fn expecting(&self, formatter: &mut Formatter) -> FormatResult {
+        write!(formatter, "non-negative number or \"{UNLIMITED}\"")
+    }
+
+    fn visit_u64<E: Error>(self, value: u64) -> Result<Self::Value, E> {
+        u32::try_from(value)
+            .map(Seats::Limited)
+            .map_err(|_| E::invalid_value(Unexpected::Unsigned(value), &self))
+    }
+
+    fn visit_i64<E: Error>(self, value: i64) -> Result<Self::Value, E> {
+        u32::try_from(value)
+            .map(Seats::Limited)
+            .map_err(|_| E::invalid_value(Unexpected::Signed(value), &self))
+    }
+
+    fn visit_str<E: Error>(self, value: &str) -> Result<Self::Value, E> {
+        match value {
+            UNLIMITED => Ok(Seats::Unlimited),
+            _ => Err(E::invalid_value(Unexpected::Str(value), &self)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Seats {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(SeatsVisitor)
+    }
+}
+
+impl JsonSchema for Seats {
+    fn schema_name() -> String {
+        "Seats".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                description: Some("Number of activations or \"unlimited\".".into()),
+                ..Default::default()
+            })),
+            subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(vec![
+                    generator.subschema_for::<u32>(),
+                    SchemaObject {
+                        instance_type: Some(InstanceType::String.into()),
+                        enum_values: Some(vec![UNLIMITED.into()]),
+                        ..Default::default()
+                    }
+                    .into(),
+                ]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Seats;
+    use serde_json::{from_str, json, to_value};
+
+    #[test]
+    fn deserialize_legacy_count() {
+        assert_eq!(Seats::Limited(3), from_str::<Seats>("3").unwrap());
+        assert_eq!(Seats::Limited(1000), from_str::<Seats>("1000").unwrap());
+    }
+
+    #[test]
+    fn deserialize_unlimited() {
+        assert_eq!(Seats::Unlimited, from_str::<Seats>("\"unlimited\"").unwrap());
+    }
+
+    #[test]
+    fn deserialize_invalid() {
+        assert!(from_str::<Seats>("-1").is_err());
+        assert!(from_str::<Seats>("\"many\"").is_err());
+        assert!(from_str::<Seats>("5000000000").is_err());
+    }
+
+    #[test]
+    fn serialize_seats() {
+        assert_eq!(json!(300), to_value(Seats::Limited(300)).unwrap());
+        assert_eq!(json!("unlimited"), to_value(Seats::Unlimited).unwrap());
+    }
+
+    #[test]
+    fn parse_seats() {
+        assert_eq!(Ok(Seats::Limited(12)), "12".parse());
+        assert_eq!(Ok(Seats::Unlimited), "unlimited".parse());
+        assert!("".parse::<Seats>().is_err());
+        assert_eq!("unlimited", Seats::Unlimited.to_string());
+    }
+}
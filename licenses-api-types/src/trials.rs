@@ -0,0 +1,126 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::responses::LicenseResponse;
+use chrono::{DateTime, FixedOffset};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Marks license as a trial."]
+pub struct Trial {
+    #[doc = "Source of the trial, eg. sales campaign or signup channel."]
+    pub origin: String,
+    #[doc = "Date when trial started."]
+    pub started_at: DateTime<FixedOffset>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Starts trial of the product on the vessel - only one trial of each product is allowed per vessel."]
+pub struct StartTrialRequest {
+    #[doc = "Owner ID."]
+    pub customer_id: Uuid,
+    #[doc = "Vessel ID."]
+    pub vessel_id: Uuid,
+    #[doc = "Product key - license key of trial license."]
+    pub license_key: String,
+    #[doc = "Source of the trial, eg. sales campaign or signup channel."]
+    pub origin: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Turns trial into paid license, keeping its activations and entitlements."]
+pub struct ConvertTrialRequest {
+    #[doc = "Owner ID."]
+    pub customer_id: Uuid,
+    #[doc = "Vessel ID."]
+    pub vessel_id: Uuid,
+    #[doc = "Trial license key."]
+    pub license_key: String,
+    #[doc = "Date when paid license ends."]
+    pub expires_at: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "operation", rename_all = "camelCase")]
+#[doc = "Operation envelope accepted by trials Lambda."]
+pub enum TrialsRequest {
+    Start(StartTrialRequest),
+    Convert(ConvertTrialRequest),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+#[doc = "Result of operation executed by trials Lambda - license in its new state."]
+pub enum TrialsResponse {
+    Start(LicenseResponse),
+    Convert(LicenseResponse),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Trial, TrialsRequest};
+    use chrono::{FixedOffset, TimeZone};
+    use serde_json::{from_str, json, to_value};
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000000");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+
+    #[test]
+    fn deserialize_start() {
+        let request: TrialsRequest = from_str(&format!(
+            "{{\"operation\":\"start\",\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"licenseKey\":\"tides\",\"origin\":\"web\"}}"
+        ))
+        .unwrap();
+
+        match request {
+            TrialsRequest::Start(request) => {
+                assert_eq!(CUSTOMER_ID, request.customer_id);
+                assert_eq!(VESSEL_ID, request.vessel_id);
+                assert_eq!("tides", request.license_key);
+                assert_eq!("web", request.origin);
+            }
+            _ => panic!("Start request expected."),
+        }
+    }
+
+    #[test]
+    fn deserialize_convert() {
+        let request: TrialsRequest = from_str(&format!(
+            "{{\"operation\":\"convert\",\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"licenseKey\":\"tides\"}}"
+        ))
+        .unwrap();
+
+        match request {
+            TrialsRequest::Convert(request) => {
+                assert_eq!("tides", request.license_key);
+                assert!(request.expires_at.is_none());
+            }
+            _ => panic!("Convert request expected."),
+        }
+    }
+
+    #[test]
+    fn serialize_trial() {
+        let trial = Trial {
+            origin: "web".to_string(),
+            started_at: FixedOffset::east_opt(7200)
+                .unwrap()
+                .with_ymd_and_hms(2023, 7, 1, 12, 0, 0)
+                .unwrap(),
+        };
+
+        assert_eq!(
+            json!({"origin": "web", "startedAt": "2023-07-01T12:00:00+02:00"}),
+            to_value(trial).unwrap()
+        );
+    }
+}
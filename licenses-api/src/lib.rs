@@ -0,0 +1,170 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use licenses_api_types::{ApiRequest, ApiResponse};
+use licenses_core::{
+    ApiError, HistoryRepository, LicenseRepository, PageTokenCodec, ProductRepository, ValidationRules,
+};
+
+pub async fn handler<R: LicenseRepository + HistoryRepository, P: ProductRepository>(
+    repository: &R,
+    products: &P,
+    codec: &PageTokenCodec,
+    rules: &ValidationRules,
+    request: ApiRequest,
+) -> Result<ApiResponse, ApiError> {
+    Ok(match request {
+        ApiRequest::Create(request) => {
+            ApiResponse::Create(licenses_creator::handler(repository, products, rules, request).await?)
+        }
+        ApiRequest::Delete(request) => {
+            ApiResponse::Delete(licenses_deleter::handler(repository, rules, request).await?)
+        }
+        ApiRequest::Get(request) => ApiResponse::Get(licenses_fetcher::handler(repository, rules, request).await?),
+        ApiRequest::List(request) => {
+            ApiResponse::List(licenses_lister::handler(repository, codec, rules, request).await?)
+        }
+        ApiRequest::Summary(request) => {
+            ApiResponse::Summary(licenses_summary::handler(repository, rules, request).await?)
+        }
+        ApiRequest::Entitlements(request) => {
+            ApiResponse::Entitlements(licenses_entitlements::handler(repository, rules, request).await?)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handler;
+    use licenses_api_types::{ApiRequest, ApiResponse, Seats};
+    use licenses_core::{
+        ApiError, InMemoryLicenseRepository, InMemoryProductRepository, LicenseRepository, PageTokenCodec, Product,
+        ValidationRules,
+    };
+    use serde_json::{from_str, json, to_value};
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    const LICENSE_KEY: &str = "tides.2023";
+
+    fn codec() -> PageTokenCodec {
+        PageTokenCodec::new(b"secret").unwrap()
+    }
+
+    fn request(operation: &str) -> ApiRequest {
+        from_str(&format!(
+            "{{\"operation\":\"{operation}\",\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"licenseKey\":\"{LICENSE_KEY}\",\"count\":3}}"
+        ))
+        .unwrap()
+    }
+
+    #[tokio_test]
+    async fn dispatch_operations() {
+        let repository = InMemoryLicenseRepository::new();
+        let products = InMemoryProductRepository::from([Product {
+            product_key: LICENSE_KEY.to_string(),
+            display_name: "Tides 2023".to_string(),
+            default_count: None,
+            default_duration_days: None,
+        }]);
+
+        let response = handler(
+            &repository,
+            &products,
+            &codec(),
+            &ValidationRules::default(),
+            request("create"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(json!(LICENSE_KEY), to_value(response).unwrap());
+        assert!(repository
+            .get_license(CUSTOMER_ID, VESSEL_ID, LICENSE_KEY.to_string())
+            .await
+            .unwrap()
+            .is_some());
+
+        match handler(
+            &repository,
+            &products,
+            &codec(),
+            &ValidationRules::default(),
+            request("get"),
+        )
+        .await
+        .unwrap()
+        {
+            ApiResponse::Get(response) => assert_eq!(Some(Seats::Limited(3)), response.count),
+            _ => panic!("Get response expected."),
+        }
+
+        match handler(
+            &repository,
+            &products,
+            &codec(),
+            &ValidationRules::default(),
+            request("list"),
+        )
+        .await
+        .unwrap()
+        {
+            ApiResponse::List(response) => assert_eq!(1, response.licenses.len()),
+            _ => panic!("List response expected."),
+        }
+
+        match handler(
+            &repository,
+            &products,
+            &codec(),
+            &ValidationRules::default(),
+            request("summary"),
+        )
+        .await
+        .unwrap()
+        {
+            ApiResponse::Summary(response) => assert_eq!(3, response.seats),
+            _ => panic!("Summary response expected."),
+        }
+
+        match handler(
+            &repository,
+            &products,
+            &codec(),
+            &ValidationRules::default(),
+            request("entitlements"),
+        )
+        .await
+        .unwrap()
+        {
+            ApiResponse::Entitlements(response) => assert_eq!(vec![LICENSE_KEY], response.license_keys),
+            _ => panic!("Entitlements response expected."),
+        }
+
+        handler(
+            &repository,
+            &products,
+            &codec(),
+            &ValidationRules::default(),
+            request("delete"),
+        )
+        .await
+        .unwrap();
+        match handler(
+            &repository,
+            &products,
+            &codec(),
+            &ValidationRules::default(),
+            request("get"),
+        )
+        .await
+        {
+            Err(ApiError::LicenseNotFound(key)) => assert_eq!(LICENSE_KEY, key),
+            _ => panic!("License not found error expected."),
+        }
+    }
+}
@@ -0,0 +1,23 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+#![feature(future_join)]
+
+use lambda_runtime::{Error, LambdaEvent};
+use licenses_api::handler;
+use licenses_api_types::ApiRequest;
+use licenses_core::{run_lambda, LicenseDao, PageTokenCodec, ProductDao, ValidationRules};
+use tokio::main as tokio_main;
+
+#[tokio_main]
+async fn main() -> Result<(), Error> {
+    let dao = &LicenseDao::load_from_env().await?;
+    let products = &ProductDao::load_from_env().await?;
+    let codec = &PageTokenCodec::load_from_env()?;
+    let rules = &ValidationRules::load_from_env()?;
+
+    run_lambda!(move |event: LambdaEvent<ApiRequest>| handler(dao, products, codec, rules, event.payload))
+}
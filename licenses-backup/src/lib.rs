@@ -0,0 +1,1002 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use aws_sdk_dynamodb::operation::delete_item::DeleteItemError;
+use aws_sdk_dynamodb::operation::put_item::PutItemError;
+use aws_sdk_dynamodb::types::AttributeValue::S;
+use aws_sdk_dynamodb::types::{AttributeValue, Delete, Put, TransactWriteItem};
+use aws_sdk_dynamodb::Client;
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
+use hex::encode;
+use licenses_core::{
+    failed_conditions, history_entry, key_of, License, RuntimeError, CUSTOMER_INDEX, NOT_POOLED_CONDITION,
+    POOLED_ATTRIBUTE,
+};
+use serde::{Deserialize, Serialize};
+use serde_dynamo::{from_item, to_item};
+use serde_json::{to_vec, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::path::PathBuf;
+use thiserror::Error;
+use uuid::Uuid;
+
+const PARTITION_KEY: &str = "customerAndVesselId";
+const SORT_KEY: &str = "licenseKey";
+const CUSTOMER_ID: &str = "customerId";
+
+pub const ARCHIVE_VERSION: u32 = 1;
+
+#[derive(Parser)]
+#[command(
+    name = "licenses-backup",
+    about = "Backs up and restores licenses of a single customer."
+)]
+pub struct Cli {
+    #[doc = "DynamoDB endpoint override, eg. http://localhost:8000 for DynamoDB Local."]
+    #[arg(long, env = "DYNAMODB_ENDPOINT_URL")]
+    pub endpoint_url: Option<String>,
+    #[doc = "Licenses table name."]
+    #[arg(long, env = "LICENSES_TABLE")]
+    pub table: String,
+    #[doc = "License history table name - restored changes are recorded there if specified."]
+    #[arg(long, env = "LICENSE_HISTORY_TABLE")]
+    pub history_table: Option<String>,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[doc = "Dumps licenses of the customer (or single vessel) into archive file."]
+    Backup {
+        #[doc = "Customer ID."]
+        customer_id: Uuid,
+        #[doc = "Limits backup to licenses of given vessel."]
+        #[arg(long)]
+        vessel_id: Option<Uuid>,
+        #[doc = "Archive file path."]
+        #[arg(long)]
+        output: PathBuf,
+    },
+    #[doc = "Restores licenses from archive file."]
+    Restore {
+        #[doc = "Archive file path."]
+        #[arg(long)]
+        input: PathBuf,
+        #[doc = "How archived licenses are combined with the current ones."]
+        #[arg(long, value_enum)]
+        mode: RestoreMode,
+        #[doc = "Only reports changes, without writing anything."]
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+#[doc = "Restoration strategy."]
+pub enum RestoreMode {
+    #[doc = "Brings back exact archived state - conflicting licenses are overwritten, licenses created since the \
+    backup are deleted."]
+    Replace,
+    #[doc = "Only brings back missing licenses - current ones are kept untouched."]
+    Merge,
+}
+
+#[doc = "Raw DynamoDB item."]
+pub type Item = HashMap<String, AttributeValue>;
+
+#[derive(Error, Debug)]
+pub enum BackupError {
+    #[error("Unsupported archive version {0}.")]
+    UnsupportedVersion(u32),
+    #[error("Checksum of {0} doesn't match its content.")]
+    ChecksumMismatch(String),
+    #[error("Entry {0} doesn't belong to the archived customer or vessel.")]
+    ForeignEntry(String),
+    #[error("{0}")]
+    RuntimeError(#[from] RuntimeError),
+    #[error("{0}")]
+    JsonError(#[from] serde_json::Error),
+}
+
+// pooled licenses are owned by their pools - restore never overwrites nor deletes them
+fn is_pooled(item: &Value) -> bool {
+    item.get(POOLED_ATTRIBUTE).and_then(Value::as_bool) == Some(true)
+}
+
+fn checksum_of<T: Serialize>(value: &T) -> Result<String, BackupError> {
+    Ok(encode(Sha256::digest(to_vec(value)?)))
+}
+
+fn string_of(value: &Value, attribute: &str) -> String {
+    value
+        .get(attribute)
+        .and_then(Value::as_str)
+        .map(String::from)
+        .unwrap_or_default()
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Key of licenses table item."]
+pub struct EntryKey {
+    #[doc = "Partition key of the item."]
+    pub customer_and_vessel_id: String,
+    #[doc = "Sort key of the item."]
+    pub license_key: String,
+}
+
+impl EntryKey {
+    fn of(item: &Value) -> Self {
+        Self {
+            customer_and_vessel_id: string_of(item, PARTITION_KEY),
+            license_key: string_of(item, SORT_KEY),
+        }
+    }
+}
+
+impl Display for EntryKey {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        write!(formatter, "{}/{}", self.customer_and_vessel_id, self.license_key)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[doc = "Single archived item."]
+pub struct ArchiveEntry {
+    #[doc = "Item attributes, as plain JSON."]
+    pub item: Value,
+    #[doc = "SHA-256 of the serialized item."]
+    pub checksum: String,
+}
+
+impl ArchiveEntry {
+    fn of(item: Item) -> Result<Self, BackupError> {
+        let item = from_item::<_, Value>(item).map_err(RuntimeError::from)?;
+
+        Ok(Self {
+            checksum: checksum_of(&item)?,
+            item,
+        })
+    }
+
+    pub fn key(&self) -> EntryKey {
+        EntryKey::of(&self.item)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Versioned snapshot of customer licenses."]
+pub struct Archive {
+    #[doc = "Archive format version."]
+    pub version: u32,
+    #[doc = "Backup creation time."]
+    pub created_at: DateTime<Utc>,
+    #[doc = "Customer ID."]
+    pub customer_id: Uuid,
+    #[doc = "Vessel ID - present if the backup covers single vessel."]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vessel_id: Option<Uuid>,
+    #[doc = "Archived items, ordered by key."]
+    pub entries: Vec<ArchiveEntry>,
+    #[doc = "SHA-256 of archive header and checksums of all entries."]
+    pub checksum: String,
+}
+
+impl Archive {
+    pub fn new(customer_id: Uuid, vessel_id: Option<Uuid>, items: Vec<Item>) -> Result<Self, BackupError> {
+        let mut entries = items.into_iter().map(ArchiveEntry::of).collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by_key(ArchiveEntry::key);
+
+        let mut archive = Self {
+            version: ARCHIVE_VERSION,
+            created_at: Utc::now(),
+            customer_id,
+            vessel_id,
+            entries,
+            checksum: String::new(),
+        };
+        archive.checksum = archive.content_checksum()?;
+
+        Ok(archive)
+    }
+
+    fn content_checksum(&self) -> Result<String, BackupError> {
+        checksum_of(&(
+            self.version,
+            self.created_at,
+            self.customer_id,
+            self.vessel_id,
+            self.entries
+                .iter()
+                .map(|entry| entry.checksum.as_str())
+                .collect::<Vec<_>>(),
+        ))
+    }
+
+    /**
+    Scope is the same as the one used for dumping - vessel partition or customer index.
+     */
+    fn covers(&self, entry: &ArchiveEntry) -> bool {
+        match self.vessel_id {
+            Some(vessel_id) => string_of(&entry.item, PARTITION_KEY) == key_of(&self.customer_id, &vessel_id),
+            None => string_of(&entry.item, CUSTOMER_ID) == self.customer_id.to_string(),
+        }
+    }
+
+    /**
+    Makes sure archive was neither modified nor truncated and that it only contains licenses of its own scope.
+     */
+    pub fn verify(&self) -> Result<(), BackupError> {
+        if self.version != ARCHIVE_VERSION {
+            return Err(BackupError::UnsupportedVersion(self.version));
+        }
+
+        for entry in &self.entries {
+            if checksum_of(&entry.item)? != entry.checksum {
+                return Err(BackupError::ChecksumMismatch(entry.key().to_string()));
+            }
+            if !self.covers(entry) {
+                return Err(BackupError::ForeignEntry(entry.key().to_string()));
+            }
+        }
+
+        if self.content_checksum()? != self.checksum {
+            return Err(BackupError::ChecksumMismatch("archive".into()));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+#[doc = "How the conflict was handled."]
+pub enum Resolution {
+    #[doc = "Current license was replaced by the archived one."]
+    Overwritten,
+    #[doc = "Current license was left untouched."]
+    Kept,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+#[doc = "License that differs from its archived state."]
+pub struct Conflict {
+    #[doc = "Item key."]
+    #[serde(flatten)]
+    pub key: EntryKey,
+    #[doc = "Applied resolution."]
+    pub resolution: Resolution,
+    #[doc = "Item state before restoration."]
+    pub current: Value,
+    #[doc = "Archived item state."]
+    pub archived: Value,
+}
+
+#[derive(Serialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Restoration results."]
+pub struct RestoreReport {
+    #[doc = "Archived licenses that were missing."]
+    pub restored: Vec<EntryKey>,
+    #[doc = "Licenses created after the backup, deleted by replacing restore."]
+    pub deleted: Vec<EntryKey>,
+    #[doc = "Pooled licenses left untouched - only their pool can allocate or release them. Archived pooled licenses \
+    are never restored, current ones are never overwritten nor deleted."]
+    pub pooled: Vec<EntryKey>,
+    #[doc = "Number of licenses identical to the archived ones."]
+    pub unchanged: usize,
+    #[doc = "Licenses that differ from the archived ones."]
+    pub conflicts: Vec<Conflict>,
+    #[doc = "Whether changes were only reported."]
+    pub dry_run: bool,
+}
+
+impl RestoreReport {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts
+            .iter()
+            .all(|conflict| conflict.resolution == Resolution::Overwritten)
+    }
+}
+
+/**
+Dumps and restores licenses of a single customer (or vessel).
+ */
+pub struct Archiver {
+    client: Client,
+    table_name: String,
+    history_table_name: Option<String>,
+    dry_run: bool,
+}
+
+impl Archiver {
+    pub fn new(client: Client, table_name: String) -> Self {
+        Self {
+            client,
+            table_name,
+            history_table_name: None,
+            dry_run: false,
+        }
+    }
+
+    /**
+    Records restored changes in license history, so that they are visible in point-in-time queries.
+     */
+    pub fn with_history(self, history_table_name: String) -> Self {
+        Self {
+            history_table_name: Some(history_table_name),
+            ..self
+        }
+    }
+
+    pub fn with_dry_run(self, dry_run: bool) -> Self {
+        Self { dry_run, ..self }
+    }
+
+    pub async fn dump(&self, customer_id: Uuid, vessel_id: Option<Uuid>) -> Result<Archive, BackupError> {
+        Archive::new(customer_id, vessel_id, self.load(customer_id, vessel_id).await?)
+    }
+
+    pub async fn restore(&self, archive: &Archive, mode: RestoreMode) -> Result<RestoreReport, BackupError> {
+        archive.verify()?;
+
+        let mut current = self
+            .load(archive.customer_id, archive.vessel_id)
+            .await?
+            .into_iter()
+            .map(|item| {
+                let item = from_item::<_, Value>(item)?;
+                Ok((EntryKey::of(&item), item))
+            })
+            .collect::<Result<BTreeMap<_, _>, serde_dynamo::Error>>()
+            .map_err(RuntimeError::from)?;
+
+        let mut report = RestoreReport {
+            dry_run: self.dry_run,
+            ..RestoreReport::default()
+        };
+
+        for entry in &archive.entries {
+            let key = entry.key();
+            match current.remove(&key) {
+                Some(item) if item == entry.item => report.unchanged += 1,
+                Some(item) => {
+                    let resolution = match mode {
+                        RestoreMode::Replace if !is_pooled(&item) && !is_pooled(&entry.item) => {
+                            if self.put(&entry.item).await? {
+                                Resolution::Overwritten
+                            } else {
+                                // allocated from pool in the meantime
+                                report.pooled.push(key.clone());
+                                Resolution::Kept
+                            }
+                        }
+                        _ => Resolution::Kept,
+                    };
+                    report.conflicts.push(Conflict {
+                        key,
+                        resolution,
+                        current: item,
+                        archived: entry.item.clone(),
+                    });
+                }
+                // seat of the pool is not restored, so neither can be the license
+                None if is_pooled(&entry.item) => report.pooled.push(key),
+                None => {
+                    if self.put(&entry.item).await? {
+                        report.restored.push(key);
+                    } else {
+                        report.pooled.push(key);
+                    }
+                }
+            }
+        }
+
+        // whatever is left was created after the backup
+        if mode == RestoreMode::Replace {
+            for (key, item) in current {
+                if !is_pooled(&item) && self.delete(&key, &item).await? {
+                    report.deleted.push(key);
+                } else {
+                    report.pooled.push(key);
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    async fn load(&self, customer_id: Uuid, vessel_id: Option<Uuid>) -> Result<Vec<Item>, RuntimeError> {
+        let (index_name, key_condition, key) = match vessel_id {
+            Some(vessel_id) => (None, "customerAndVesselId = :key", key_of(&customer_id, &vessel_id)),
+            None => (
+                Some(CUSTOMER_INDEX.to_string()),
+                "customerId = :key",
+                customer_id.to_string(),
+            ),
+        };
+
+        let mut items = vec![];
+        let mut page_key = None;
+
+        loop {
+            let results = self
+                .client
+                .query()
+                .table_name(self.table_name.as_str())
+                .set_index_name(index_name.clone())
+                .key_condition_expression(key_condition)
+                .expression_attribute_values(":key", S(key.clone()))
+                .set_exclusive_start_key(page_key)
+                .send()
+                .await?;
+
+            items.extend(results.items.unwrap_or_default());
+
+            page_key = results.last_evaluated_key;
+            if page_key.is_none() {
+                return Ok(items);
+            }
+        }
+    }
+
+    /**
+    Writes never touch pooled licenses - `false` if the license turned out to be allocated from pool.
+     */
+    async fn put(&self, item: &Value) -> Result<bool, RuntimeError> {
+        if self.dry_run {
+            return Ok(true);
+        }
+
+        let item = to_item::<_, Item>(item)?;
+        match &self.history_table_name {
+            Some(history_table_name) => {
+                let license = from_item::<_, License>(item.clone())?;
+                let entry = history_entry(
+                    history_table_name,
+                    &license.customer_id,
+                    &license.vessel_id,
+                    &license.license_key,
+                    Some(license.clone()),
+                )?;
+
+                let result = self
+                    .client
+                    .transact_write_items()
+                    .transact_items(
+                        TransactWriteItem::builder()
+                            .put(
+                                Put::builder()
+                                    .table_name(self.table_name.as_str())
+                                    .set_item(Some(item))
+                                    .condition_expression(NOT_POOLED_CONDITION)
+                                    .expression_attribute_names("#pooled", POOLED_ATTRIBUTE)
+                                    .build(),
+                            )
+                            .build(),
+                    )
+                    .transact_items(entry)
+                    .send()
+                    .await;
+                match result {
+                    Ok(_) => Ok(true),
+                    Err(error) if failed_conditions(&error).map_or(false, |failed| failed.contains(&true)) => Ok(false),
+                    Err(error) => Err(error.into()),
+                }
+            }
+            None => {
+                let result = self
+                    .client
+                    .put_item()
+                    .table_name(self.table_name.as_str())
+                    .set_item(Some(item))
+                    .condition_expression(NOT_POOLED_CONDITION)
+                    .expression_attribute_names("#pooled", POOLED_ATTRIBUTE)
+                    .send()
+                    .await;
+                match result {
+                    Ok(_) => Ok(true),
+                    Err(error)
+                        if error
+                            .as_service_error()
+                            .map_or(false, PutItemError::is_conditional_check_failed_exception) =>
+                    {
+                        Ok(false)
+                    }
+                    Err(error) => Err(error.into()),
+                }
+            }
+        }
+    }
+
+    /**
+    Same as writes, deletes never touch pooled licenses - `false` if the license turned out to be allocated from pool.
+     */
+    async fn delete(&self, key: &EntryKey, item: &Value) -> Result<bool, RuntimeError> {
+        if self.dry_run {
+            return Ok(true);
+        }
+
+        let item_key = Item::from([
+            (PARTITION_KEY.into(), S(key.customer_and_vessel_id.clone())),
+            (SORT_KEY.into(), S(key.license_key.clone())),
+        ]);
+        match &self.history_table_name {
+            Some(history_table_name) => {
+                // tombstone marks the moment license disappeared
+                let license = from_item::<_, License>(to_item::<_, Item>(item)?)?;
+                let entry = history_entry(
+                    history_table_name,
+                    &license.customer_id,
+                    &license.vessel_id,
+                    &license.license_key,
+                    None,
+                )?;
+
+                let result = self
+                    .client
+                    .transact_write_items()
+                    .transact_items(
+                        TransactWriteItem::builder()
+                            .delete(
+                                Delete::builder()
+                                    .table_name(self.table_name.as_str())
+                                    .set_key(Some(item_key))
+                                    .condition_expression(NOT_POOLED_CONDITION)
+                                    .expression_attribute_names("#pooled", POOLED_ATTRIBUTE)
+                                    .build(),
+                            )
+                            .build(),
+                    )
+                    .transact_items(entry)
+                    .send()
+                    .await;
+                match result {
+                    Ok(_) => Ok(true),
+                    Err(error) if failed_conditions(&error).map_or(false, |failed| failed.contains(&true)) => Ok(false),
+                    Err(error) => Err(error.into()),
+                }
+            }
+            None => {
+                let result = self
+                    .client
+                    .delete_item()
+                    .table_name(self.table_name.as_str())
+                    .set_key(Some(item_key))
+                    .condition_expression(NOT_POOLED_CONDITION)
+                    .expression_attribute_names("#pooled", POOLED_ATTRIBUTE)
+                    .send()
+                    .await;
+                match result {
+                    Ok(_) => Ok(true),
+                    Err(error)
+                        if error
+                            .as_service_error()
+                            .map_or(false, DeleteItemError::is_conditional_check_failed_exception) =>
+                    {
+                        Ok(false)
+                    }
+                    Err(error) => Err(error.into()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Archive, Archiver, BackupError, Item, Resolution, RestoreMode};
+    use async_trait::async_trait;
+    use aws_config::load_from_env;
+    use aws_sdk_dynamodb::config::Builder;
+    use aws_sdk_dynamodb::types::AttributeValue::{Bool, N, S};
+    use aws_sdk_dynamodb::types::{
+        AttributeDefinition, BillingMode, GlobalSecondaryIndex, KeySchemaElement, KeyType, Projection, ProjectionType,
+        ScalarAttributeType,
+    };
+    use aws_sdk_dynamodb::Client;
+    use licenses_core::CUSTOMER_INDEX;
+    use serde_json::{from_str, json, to_string};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use test_context::{test_context, AsyncTestContext};
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000000");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    const OTHER_VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    const OTHER_CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000003");
+
+    static NUMBER: AtomicUsize = AtomicUsize::new(0);
+
+    fn license_of(customer_id: Uuid, vessel_id: Uuid, license_key: &str, count: u32) -> Item {
+        Item::from([
+            ("customerAndVesselId".into(), S(format!("{customer_id}:{vessel_id}"))),
+            ("licenseKey".into(), S(license_key.into())),
+            ("customerId".into(), S(customer_id.to_string())),
+            ("vesselId".into(), S(vessel_id.to_string())),
+            ("count".into(), N(count.to_string())),
+        ])
+    }
+
+    fn archive() -> Archive {
+        Archive::new(
+            CUSTOMER_ID,
+            None,
+            vec![
+                license_of(CUSTOMER_ID, OTHER_VESSEL_ID, "tides.2023", 1),
+                license_of(CUSTOMER_ID, VESSEL_ID, "weather.2023", 2),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn verify_archive() {
+        let archive = archive();
+
+        assert!(archive.verify().is_ok());
+        assert_eq!(
+            format!("{CUSTOMER_ID}:{VESSEL_ID}"),
+            archive.entries[0].key().customer_and_vessel_id
+        );
+
+        // survives serialization round trip
+        let restored: Archive = from_str(&to_string(&archive).unwrap()).unwrap();
+        assert_eq!(archive, restored);
+        assert!(restored.verify().is_ok());
+    }
+
+    #[test]
+    fn verify_modified_entry() {
+        let mut archive = archive();
+        archive.entries[0].item["count"] = json!(10);
+
+        assert!(
+            matches!(archive.verify(), Err(BackupError::ChecksumMismatch(key)) if key == format!("{CUSTOMER_ID}:{VESSEL_ID}/weather.2023"))
+        );
+    }
+
+    #[test]
+    fn verify_truncated_archive() {
+        let mut archive = archive();
+        archive.entries.pop();
+
+        assert!(matches!(archive.verify(), Err(BackupError::ChecksumMismatch(key)) if key == "archive"));
+    }
+
+    #[test]
+    fn verify_unsupported_version() {
+        let mut archive = archive();
+        archive.version = 2;
+
+        assert!(matches!(archive.verify(), Err(BackupError::UnsupportedVersion(2))));
+    }
+
+    #[test]
+    fn verify_foreign_entry() {
+        let archive = Archive::new(
+            CUSTOMER_ID,
+            Some(VESSEL_ID),
+            vec![license_of(CUSTOMER_ID, OTHER_VESSEL_ID, "tides.2023", 1)],
+        )
+        .unwrap();
+
+        assert!(matches!(archive.verify(), Err(BackupError::ForeignEntry(_))));
+    }
+
+    struct DynamoDbTestContext {
+        client: Client,
+        table_name: String,
+    }
+
+    #[async_trait]
+    impl AsyncTestContext for DynamoDbTestContext {
+        async fn setup() -> DynamoDbTestContext {
+            let table_name = format!("Backup{}", NUMBER.fetch_add(1, Ordering::SeqCst));
+            let config = load_from_env().await;
+            let client = Client::from_conf(Builder::from(&config).endpoint_url("http://localhost:8000").build());
+
+            client
+                .create_table()
+                .table_name(table_name.as_str())
+                .attribute_definitions(
+                    AttributeDefinition::builder()
+                        .attribute_name("customerAndVesselId")
+                        .attribute_type(ScalarAttributeType::S)
+                        .build(),
+                )
+                .attribute_definitions(
+                    AttributeDefinition::builder()
+                        .attribute_name("licenseKey")
+                        .attribute_type(ScalarAttributeType::S)
+                        .build(),
+                )
+                .attribute_definitions(
+                    AttributeDefinition::builder()
+                        .attribute_name("customerId")
+                        .attribute_type(ScalarAttributeType::S)
+                        .build(),
+                )
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("customerAndVesselId")
+                        .key_type(KeyType::Hash)
+                        .build(),
+                )
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("licenseKey")
+                        .key_type(KeyType::Range)
+                        .build(),
+                )
+                .global_secondary_indexes(
+                    GlobalSecondaryIndex::builder()
+                        .index_name(CUSTOMER_INDEX)
+                        .key_schema(
+                            KeySchemaElement::builder()
+                                .attribute_name("customerId")
+                                .key_type(KeyType::Hash)
+                                .build(),
+                        )
+                        .key_schema(
+                            KeySchemaElement::builder()
+                                .attribute_name("licenseKey")
+                                .key_type(KeyType::Range)
+                                .build(),
+                        )
+                        .projection(Projection::builder().projection_type(ProjectionType::All).build())
+                        .build(),
+                )
+                .billing_mode(BillingMode::PayPerRequest)
+                .send()
+                .await
+                .unwrap();
+
+            let context = DynamoDbTestContext { client, table_name };
+            context.put(license_of(CUSTOMER_ID, VESSEL_ID, "tides.2023", 1)).await;
+            context.put(license_of(CUSTOMER_ID, VESSEL_ID, "weather.2023", 2)).await;
+            context
+                .put(license_of(CUSTOMER_ID, OTHER_VESSEL_ID, "tides.2023", 3))
+                .await;
+            context
+                .put(license_of(OTHER_CUSTOMER_ID, VESSEL_ID, "tides.2023", 4))
+                .await;
+
+            context
+        }
+
+        async fn teardown(self) {
+            self.client
+                .delete_table()
+                .table_name(self.table_name)
+                .send()
+                .await
+                .unwrap();
+        }
+    }
+
+    impl DynamoDbTestContext {
+        async fn put(&self, item: Item) {
+            self.client
+                .put_item()
+                .table_name(self.table_name.as_str())
+                .set_item(Some(item))
+                .send()
+                .await
+                .unwrap();
+        }
+
+        async fn delete(&self, vessel_id: Uuid, license_key: &str) {
+            self.client
+                .delete_item()
+                .table_name(self.table_name.as_str())
+                .key("customerAndVesselId", S(format!("{CUSTOMER_ID}:{vessel_id}")))
+                .key("licenseKey", S(license_key.into()))
+                .send()
+                .await
+                .unwrap();
+        }
+
+        async fn get(&self, vessel_id: Uuid, license_key: &str) -> Option<Item> {
+            self.client
+                .get_item()
+                .table_name(self.table_name.as_str())
+                .key("customerAndVesselId", S(format!("{CUSTOMER_ID}:{vessel_id}")))
+                .key("licenseKey", S(license_key.into()))
+                .send()
+                .await
+                .unwrap()
+                .item
+        }
+
+        /**
+        Deletes one license, modifies another one and creates a new one.
+         */
+        async fn change(&self) {
+            self.delete(VESSEL_ID, "tides.2023").await;
+            self.put(license_of(CUSTOMER_ID, VESSEL_ID, "weather.2023", 5)).await;
+            self.put(license_of(CUSTOMER_ID, VESSEL_ID, "routing.2023", 6)).await;
+        }
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn dump_customer(ctx: &DynamoDbTestContext) -> Result<(), BackupError> {
+        let archive = Archiver::new(ctx.client.clone(), ctx.table_name.clone())
+            .dump(CUSTOMER_ID, None)
+            .await?;
+
+        assert_eq!(3, archive.entries.len());
+        assert!(archive.verify().is_ok());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn dump_vessel(ctx: &DynamoDbTestContext) -> Result<(), BackupError> {
+        let archive = Archiver::new(ctx.client.clone(), ctx.table_name.clone())
+            .dump(CUSTOMER_ID, Some(VESSEL_ID))
+            .await?;
+
+        assert_eq!(2, archive.entries.len());
+        assert_eq!("tides.2023", archive.entries[0].key().license_key);
+        assert_eq!("weather.2023", archive.entries[1].key().license_key);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn restore_replace(ctx: &DynamoDbTestContext) -> Result<(), BackupError> {
+        let archiver = Archiver::new(ctx.client.clone(), ctx.table_name.clone());
+        let archive = archiver.dump(CUSTOMER_ID, None).await?;
+        ctx.change().await;
+
+        let report = archiver.restore(&archive, RestoreMode::Replace).await?;
+        assert_eq!(1, report.restored.len());
+        assert_eq!("tides.2023", report.restored[0].license_key);
+        assert_eq!(1, report.deleted.len());
+        assert_eq!("routing.2023", report.deleted[0].license_key);
+        assert_eq!(1, report.unchanged);
+        assert_eq!(1, report.conflicts.len());
+        assert_eq!(Resolution::Overwritten, report.conflicts[0].resolution);
+        assert_eq!(json!(5), report.conflicts[0].current["count"]);
+        assert_eq!(json!(2), report.conflicts[0].archived["count"]);
+        assert!(report.is_clean());
+
+        assert!(ctx.get(VESSEL_ID, "tides.2023").await.is_some());
+        assert!(ctx.get(VESSEL_ID, "routing.2023").await.is_none());
+        assert_eq!(
+            Some(&N("2".into())),
+            ctx.get(VESSEL_ID, "weather.2023").await.unwrap().get("count")
+        );
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn restore_replace_pooled(ctx: &DynamoDbTestContext) -> Result<(), BackupError> {
+        let archiver = Archiver::new(ctx.client.clone(), ctx.table_name.clone());
+        let archive = archiver.dump(CUSTOMER_ID, Some(VESSEL_ID)).await?;
+        for (license_key, count) in [("weather.2023", 5), ("routing.2023", 6)] {
+            let mut item = license_of(CUSTOMER_ID, VESSEL_ID, license_key, count);
+            item.insert("pooled".into(), Bool(true));
+            ctx.put(item).await;
+        }
+
+        let report = archiver.restore(&archive, RestoreMode::Replace).await?;
+        assert!(report.deleted.is_empty());
+        assert_eq!(1, report.pooled.len());
+        assert_eq!("routing.2023", report.pooled[0].license_key);
+        assert_eq!(1, report.conflicts.len());
+        assert_eq!(Resolution::Kept, report.conflicts[0].resolution);
+
+        assert!(ctx.get(VESSEL_ID, "routing.2023").await.is_some());
+        assert_eq!(
+            Some(&N("5".into())),
+            ctx.get(VESSEL_ID, "weather.2023").await.unwrap().get("count")
+        );
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn restore_archived_pooled(ctx: &DynamoDbTestContext) -> Result<(), BackupError> {
+        let archiver = Archiver::new(ctx.client.clone(), ctx.table_name.clone());
+        let mut item = license_of(CUSTOMER_ID, VESSEL_ID, "routing.2023", 6);
+        item.insert("pooled".into(), Bool(true));
+        ctx.put(item).await;
+        let archive = archiver.dump(CUSTOMER_ID, Some(VESSEL_ID)).await?;
+
+        // seat released since the backup
+        ctx.delete(VESSEL_ID, "routing.2023").await;
+
+        let report = archiver.restore(&archive, RestoreMode::Replace).await?;
+        assert!(report.restored.is_empty());
+        assert_eq!(1, report.pooled.len());
+        assert_eq!("routing.2023", report.pooled[0].license_key);
+
+        assert!(ctx.get(VESSEL_ID, "routing.2023").await.is_none());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn restore_allocated_concurrently(ctx: &DynamoDbTestContext) -> Result<(), BackupError> {
+        let archiver = Archiver::new(ctx.client.clone(), ctx.table_name.clone());
+        let archive = archiver.dump(CUSTOMER_ID, Some(VESSEL_ID)).await?;
+
+        // seat allocated after the current state was loaded
+        let mut item = license_of(CUSTOMER_ID, VESSEL_ID, "weather.2023", 5);
+        item.insert("pooled".into(), Bool(true));
+        ctx.put(item).await;
+
+        assert!(!archiver.put(&archive.entries[1].item).await?);
+        assert_eq!(
+            Some(&N("5".into())),
+            ctx.get(VESSEL_ID, "weather.2023").await.unwrap().get("count")
+        );
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn restore_merge(ctx: &DynamoDbTestContext) -> Result<(), BackupError> {
+        let archiver = Archiver::new(ctx.client.clone(), ctx.table_name.clone());
+        let archive = archiver.dump(CUSTOMER_ID, None).await?;
+        ctx.change().await;
+
+        let report = archiver.restore(&archive, RestoreMode::Merge).await?;
+        assert_eq!(1, report.restored.len());
+        assert!(report.deleted.is_empty());
+        assert_eq!(1, report.conflicts.len());
+        assert_eq!(Resolution::Kept, report.conflicts[0].resolution);
+        assert!(!report.is_clean());
+
+        assert!(ctx.get(VESSEL_ID, "tides.2023").await.is_some());
+        assert!(ctx.get(VESSEL_ID, "routing.2023").await.is_some());
+        assert_eq!(
+            Some(&N("5".into())),
+            ctx.get(VESSEL_ID, "weather.2023").await.unwrap().get("count")
+        );
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn restore_dry_run(ctx: &DynamoDbTestContext) -> Result<(), BackupError> {
+        let archive = Archiver::new(ctx.client.clone(), ctx.table_name.clone())
+            .dump(CUSTOMER_ID, Some(VESSEL_ID))
+            .await?;
+        ctx.change().await;
+
+        let report = Archiver::new(ctx.client.clone(), ctx.table_name.clone())
+            .with_dry_run(true)
+            .restore(&archive, RestoreMode::Replace)
+            .await?;
+        assert!(report.dry_run);
+        assert_eq!(1, report.restored.len());
+        assert_eq!(1, report.deleted.len());
+
+        assert!(ctx.get(VESSEL_ID, "tides.2023").await.is_none());
+        assert!(ctx.get(VESSEL_ID, "routing.2023").await.is_some());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use clap::Parser;
+use licenses_backup::{Archive, Archiver, Cli, Command};
+use licenses_core::load_client;
+use serde_json::{from_slice, to_string_pretty, to_vec_pretty};
+use std::error::Error;
+use std::fs::{read, write};
+use std::process::exit;
+use tokio::main as tokio_main;
+
+#[tokio_main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    let client = load_client(cli.endpoint_url).await;
+    let archiver = Archiver::new(client, cli.table);
+    let archiver = match cli.history_table {
+        Some(history_table) => archiver.with_history(history_table),
+        None => archiver,
+    };
+
+    match cli.command {
+        Command::Backup {
+            customer_id,
+            vessel_id,
+            output,
+        } => {
+            let archive = archiver.dump(customer_id, vessel_id).await?;
+            write(&output, to_vec_pretty(&archive)?)?;
+
+            println!("Archived {} licenses into {}.", archive.entries.len(), output.display());
+        }
+        Command::Restore { input, mode, dry_run } => {
+            let archive: Archive = from_slice(&read(input)?)?;
+            let report = archiver.with_dry_run(dry_run).restore(&archive, mode).await?;
+
+            println!("{}", to_string_pretty(&report)?);
+
+            // conflicts kept by merging need manual decision
+            if !report.is_clean() {
+                exit(1);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,784 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use aws_sdk_dynamodb::types::AttributeValue::S;
+use aws_sdk_dynamodb::types::{AttributeValue, Put, TransactWriteItem};
+use aws_sdk_dynamodb::Client;
+use chrono::{DateTime, FixedOffset};
+use clap::Parser;
+use licenses_core::{
+    failed_conditions, history_entry, item_state_condition, key_of, License, RuntimeError, SCHEMA_PARTITION,
+};
+use serde::Serialize;
+use serde_dynamo::from_item;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const PARTITION_KEY: &str = "customerAndVesselId";
+const SORT_KEY: &str = "licenseKey";
+const CUSTOMER_ID: &str = "customerId";
+const VESSEL_ID: &str = "vesselId";
+const EXPIRES_AT: &str = "expiresAt";
+
+#[derive(Parser)]
+#[command(name = "licenses-checker", about = "Verifies integrity of licenses table items.")]
+pub struct Cli {
+    #[doc = "DynamoDB endpoint override, eg. http://localhost:8000 for DynamoDB Local."]
+    #[arg(long, env = "DYNAMODB_ENDPOINT_URL")]
+    pub endpoint_url: Option<String>,
+    #[doc = "Licenses table name."]
+    #[arg(long, env = "LICENSES_TABLE")]
+    pub table: String,
+    #[doc = "Fixes repairable issues (identifiers not matching item key)."]
+    #[arg(long)]
+    pub repair: bool,
+    #[doc = "License history table name - repairs are recorded there if specified."]
+    #[arg(long, env = "LICENSE_HISTORY_TABLE")]
+    pub history_table: Option<String>,
+}
+
+#[doc = "Raw DynamoDB item."]
+pub type Item = HashMap<String, AttributeValue>;
+
+#[derive(Serialize, PartialEq, Debug)]
+#[serde(tag = "issue", rename_all = "camelCase")]
+#[doc = "Detected item problem."]
+pub enum Issue {
+    #[doc = "Partition key is not in canonical `customerId:vesselId` form."]
+    MalformedKey,
+    #[doc = "Identifier attribute differs from the one in partition key."]
+    KeyMismatch { attribute: String, value: String },
+    #[doc = "Required attribute is absent."]
+    MissingField { attribute: String },
+    #[doc = "Date attribute is not a valid RFC 3339 date."]
+    MalformedDate { attribute: String, value: String },
+    #[doc = "Item can't be read as license for any other reason."]
+    InvalidItem { message: String },
+    #[doc = "Another item (with given partition key) is a license of the same vessel with the same key."]
+    DuplicateKey { original: String },
+}
+
+impl Issue {
+    /**
+    Identifiers can be restored from partition key - everything else needs manual decision.
+     */
+    pub fn is_repairable(&self) -> bool {
+        matches!(self, Self::KeyMismatch { .. } | Self::MissingField { .. })
+    }
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Issue of single item."]
+pub struct Finding {
+    #[doc = "Partition key of the item."]
+    pub customer_and_vessel_id: String,
+    #[doc = "Sort key of the item."]
+    pub license_key: String,
+    #[doc = "Detected problem."]
+    #[serde(flatten)]
+    pub issue: Issue,
+    #[doc = "Whether the item was fixed."]
+    pub repaired: bool,
+}
+
+#[derive(Serialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Table check results."]
+pub struct Report {
+    #[doc = "Number of checked items."]
+    pub scanned: usize,
+    #[doc = "All detected issues."]
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    pub fn is_clean(&self) -> bool {
+        self.findings.iter().all(|finding| finding.repaired)
+    }
+}
+
+fn string_of(value: &AttributeValue) -> String {
+    value.as_s().map_or_else(|value| format!("{value:?}"), String::clone)
+}
+
+fn ids_of(key: &str) -> Option<(Uuid, Uuid)> {
+    let (customer_id, vessel_id) = key.split_once(':')?;
+    let (customer_id, vessel_id) = (Uuid::parse_str(customer_id).ok()?, Uuid::parse_str(vessel_id).ok()?);
+
+    // items under any other spelling of the identifiers are not reachable by the DAO
+    (key_of(&customer_id, &vessel_id) == key).then_some((customer_id, vessel_id))
+}
+
+fn identity_of(item: &Item) -> Option<(Uuid, Uuid, String)> {
+    let id_of = |attribute: &str| {
+        item.get(attribute)
+            .and_then(|value| value.as_s().ok())
+            .and_then(|value| Uuid::parse_str(value).ok())
+    };
+
+    Some((
+        id_of(CUSTOMER_ID)?,
+        id_of(VESSEL_ID)?,
+        item.get(SORT_KEY).map(string_of)?,
+    ))
+}
+
+/**
+Validates single licenses table item - returns detected issues and, if any of them could be fixed, repaired item.
+ */
+pub fn check_item(item: &Item) -> (Vec<Issue>, Option<Item>) {
+    let ids = ids_of(&item.get(PARTITION_KEY).map(string_of).unwrap_or_default());
+
+    let mut issues = vec![];
+    if ids.is_none() {
+        issues.push(Issue::MalformedKey);
+    }
+
+    let mut repaired = item.clone();
+    for (attribute, expected) in [
+        (CUSTOMER_ID, ids.map(|(customer_id, _)| customer_id)),
+        (VESSEL_ID, ids.map(|(_, vessel_id)| vessel_id)),
+    ] {
+        let issue = match item.get(attribute) {
+            None => Issue::MissingField {
+                attribute: attribute.into(),
+            },
+            Some(value) if expected.map_or(false, |expected| value.as_s().ok() != Some(&expected.to_string())) => {
+                Issue::KeyMismatch {
+                    attribute: attribute.into(),
+                    value: string_of(value),
+                }
+            }
+            Some(_) => continue,
+        };
+
+        if let Some(expected) = expected {
+            repaired.insert(attribute.into(), S(expected.to_string()));
+        }
+        issues.push(issue);
+    }
+
+    let malformed_date = item.get(EXPIRES_AT).filter(|value| {
+        value
+            .as_s()
+            .ok()
+            .map_or(true, |date| date.parse::<DateTime<FixedOffset>>().is_err())
+    });
+    if let Some(value) = malformed_date {
+        issues.push(Issue::MalformedDate {
+            attribute: EXPIRES_AT.into(),
+            value: string_of(value),
+        });
+    }
+
+    // anything else that would break reading the item, like invalid seats or entitlements
+    if malformed_date.is_none() && repaired.contains_key(CUSTOMER_ID) && repaired.contains_key(VESSEL_ID) {
+        if let Err(error) = from_item::<_, License>(repaired.clone()) {
+            issues.push(Issue::InvalidItem {
+                message: error.to_string(),
+            });
+        }
+    }
+
+    (issues, Some(repaired).filter(|repaired| repaired != item))
+}
+
+/**
+Scans whole licenses table looking for items that can't be read by the DAO or that are not where they should be.
+ */
+pub struct Checker {
+    client: Client,
+    table_name: String,
+    history_table_name: Option<String>,
+    repair: bool,
+}
+
+impl Checker {
+    pub fn new(client: Client, table_name: String) -> Self {
+        Self {
+            client,
+            table_name,
+            history_table_name: None,
+            repair: false,
+        }
+    }
+
+    /**
+    Records repaired licenses in license history, the same way as changes made by Lambdas.
+     */
+    pub fn with_history(self, history_table_name: String) -> Self {
+        Self {
+            history_table_name: Some(history_table_name),
+            ..self
+        }
+    }
+
+    /**
+    With repair enabled, repairable items are overwritten in place - item key never changes.
+     */
+    pub fn with_repair(self, repair: bool) -> Self {
+        Self { repair, ..self }
+    }
+
+    pub async fn run(&self) -> Result<Report, RuntimeError> {
+        let mut report = Report::default();
+        let mut identities = HashMap::new();
+        let mut page_key = None;
+
+        loop {
+            let results = self
+                .client
+                .scan()
+                .table_name(self.table_name.as_str())
+                .set_exclusive_start_key(page_key)
+                .send()
+                .await?;
+
+            for item in results.items.unwrap_or_default() {
+                let key = item.get(PARTITION_KEY).map(string_of).unwrap_or_default();
+                if key == SCHEMA_PARTITION {
+                    continue;
+                }
+
+                report.scanned += 1;
+                let license_key = item.get(SORT_KEY).map(string_of).unwrap_or_default();
+
+                let (mut issues, repaired) = check_item(&item);
+                if let Some(identity) = identity_of(repaired.as_ref().unwrap_or(&item)) {
+                    match identities.entry(identity) {
+                        Entry::Occupied(entry) => issues.push(Issue::DuplicateKey {
+                            original: entry.get().clone(),
+                        }),
+                        Entry::Vacant(entry) => {
+                            entry.insert(key.clone());
+                        }
+                    }
+                }
+
+                let fixed = match repaired {
+                    Some(repaired) if self.repair => self.save(&item, repaired).await?,
+                    _ => false,
+                };
+                report.findings.extend(issues.into_iter().map(|issue| Finding {
+                    customer_and_vessel_id: key.clone(),
+                    license_key: license_key.clone(),
+                    repaired: fixed && issue.is_repairable(),
+                    issue,
+                }));
+            }
+
+            page_key = results.last_evaluated_key;
+            if page_key.is_none() {
+                return Ok(report);
+            }
+        }
+    }
+
+    /**
+    Repair is conditioned on the scanned state of the item - item changed or deleted (and possibly re-created) in the
+    meantime is left untouched. With history enabled, item that still isn't a valid license can't be recorded and is
+    left for manual fix.
+     */
+    async fn save(&self, item: &Item, repaired: Item) -> Result<bool, RuntimeError> {
+        let added = repaired
+            .keys()
+            .filter(|name| !item.contains_key(*name))
+            .collect::<Vec<_>>();
+        let (condition, names, values) = item_state_condition(item, &added);
+
+        let license = from_item::<_, License>(repaired.clone());
+        let mut writes = vec![TransactWriteItem::builder()
+            .put(
+                Put::builder()
+                    .table_name(self.table_name.as_str())
+                    .set_item(Some(repaired))
+                    .condition_expression(condition)
+                    .set_expression_attribute_names(Some(names))
+                    .set_expression_attribute_values(Some(values))
+                    .build(),
+            )
+            .build()];
+        if let Some(history_table_name) = &self.history_table_name {
+            let Ok(license) = license else {
+                return Ok(false);
+            };
+            writes.push(history_entry(
+                history_table_name,
+                &license.customer_id,
+                &license.vessel_id,
+                &license.license_key,
+                Some(license.clone()),
+            )?);
+        }
+
+        match self
+            .client
+            .transact_write_items()
+            .set_transact_items(Some(writes))
+            .send()
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(error) if failed_conditions(&error).map_or(false, |failed| failed.contains(&true)) => Ok(false),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{check_item, Checker, Finding, Issue, Item};
+    use async_trait::async_trait;
+    use aws_config::load_from_env;
+    use aws_sdk_dynamodb::config::Builder;
+    use aws_sdk_dynamodb::types::AttributeValue::{N, S};
+    use aws_sdk_dynamodb::types::{
+        AttributeDefinition, AttributeValue, BillingMode, KeySchemaElement, KeyType, ScalarAttributeType,
+    };
+    use aws_sdk_dynamodb::Client;
+    use licenses_core::RuntimeError;
+    use serde_json::{json, to_value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use test_context::{test_context, AsyncTestContext};
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000000");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    const OTHER_VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+
+    static NUMBER: AtomicUsize = AtomicUsize::new(0);
+
+    fn item_of(key: &str, attributes: Vec<(&str, AttributeValue)>) -> Item {
+        let mut item = Item::from([
+            ("customerAndVesselId".into(), S(key.into())),
+            ("licenseKey".into(), S("tides.2023".into())),
+        ]);
+        item.extend(attributes.into_iter().map(|(name, value)| (name.into(), value)));
+        item
+    }
+
+    fn license_of(key: &str, customer_id: Uuid, vessel_id: Uuid) -> Item {
+        item_of(
+            key,
+            vec![
+                ("customerId", S(customer_id.to_string())),
+                ("vesselId", S(vessel_id.to_string())),
+                ("expiresAt", S("2023-07-01T02:00:00+02:00".into())),
+            ],
+        )
+    }
+
+    #[test]
+    fn check_valid_item() {
+        let item = license_of(&format!("{CUSTOMER_ID}:{VESSEL_ID}"), CUSTOMER_ID, VESSEL_ID);
+
+        assert_eq!((vec![], None), check_item(&item));
+    }
+
+    #[test]
+    fn check_key_mismatch() {
+        let key = format!("{CUSTOMER_ID}:{VESSEL_ID}");
+        let (issues, repaired) = check_item(&license_of(&key, CUSTOMER_ID, OTHER_VESSEL_ID));
+
+        assert_eq!(
+            vec![Issue::KeyMismatch {
+                attribute: "vesselId".into(),
+                value: OTHER_VESSEL_ID.to_string(),
+            }],
+            issues
+        );
+        assert_eq!(Some(license_of(&key, CUSTOMER_ID, VESSEL_ID)), repaired);
+    }
+
+    #[test]
+    fn check_missing_field() {
+        let key = format!("{CUSTOMER_ID}:{VESSEL_ID}");
+        let mut item = license_of(&key, CUSTOMER_ID, VESSEL_ID);
+        item.remove("customerId");
+
+        let (issues, repaired) = check_item(&item);
+        assert_eq!(
+            vec![Issue::MissingField {
+                attribute: "customerId".into(),
+            }],
+            issues
+        );
+        assert_eq!(Some(license_of(&key, CUSTOMER_ID, VESSEL_ID)), repaired);
+    }
+
+    #[test]
+    fn check_malformed_key() {
+        let item = license_of(&format!("{}:{VESSEL_ID}", CUSTOMER_ID.simple()), CUSTOMER_ID, VESSEL_ID);
+
+        assert_eq!((vec![Issue::MalformedKey], None), check_item(&item));
+    }
+
+    #[test]
+    fn check_malformed_key_missing_field() {
+        let mut item = license_of("broken", CUSTOMER_ID, VESSEL_ID);
+        item.remove("vesselId");
+
+        assert_eq!(
+            (
+                vec![
+                    Issue::MalformedKey,
+                    Issue::MissingField {
+                        attribute: "vesselId".into(),
+                    },
+                ],
+                None
+            ),
+            check_item(&item)
+        );
+    }
+
+    #[test]
+    fn check_malformed_date() {
+        let mut item = license_of(&format!("{CUSTOMER_ID}:{VESSEL_ID}"), CUSTOMER_ID, VESSEL_ID);
+        item.insert("expiresAt".into(), S("01.07.2023".into()));
+
+        assert_eq!(
+            (
+                vec![Issue::MalformedDate {
+                    attribute: "expiresAt".into(),
+                    value: "01.07.2023".into(),
+                }],
+                None
+            ),
+            check_item(&item)
+        );
+    }
+
+    #[test]
+    fn check_invalid_item() {
+        let mut item = license_of(&format!("{CUSTOMER_ID}:{VESSEL_ID}"), CUSTOMER_ID, VESSEL_ID);
+        item.insert("count".into(), S("many".into()));
+
+        let (issues, repaired) = check_item(&item);
+        assert!(matches!(issues.as_slice(), [Issue::InvalidItem { .. }]));
+        assert!(repaired.is_none());
+    }
+
+    #[test]
+    fn serialize_finding() {
+        let value = to_value(Finding {
+            customer_and_vessel_id: format!("{CUSTOMER_ID}:{VESSEL_ID}"),
+            license_key: "tides.2023".into(),
+            issue: Issue::KeyMismatch {
+                attribute: "vesselId".into(),
+                value: OTHER_VESSEL_ID.to_string(),
+            },
+            repaired: true,
+        })
+        .unwrap();
+
+        assert_eq!(
+            json!({
+                "customerAndVesselId": format!("{CUSTOMER_ID}:{VESSEL_ID}"),
+                "licenseKey": "tides.2023",
+                "issue": "keyMismatch",
+                "attribute": "vesselId",
+                "value": OTHER_VESSEL_ID.to_string(),
+                "repaired": true,
+            }),
+            value
+        );
+    }
+
+    struct DynamoDbTestContext {
+        client: Client,
+        table_name: String,
+        history_table_name: String,
+    }
+
+    #[async_trait]
+    impl AsyncTestContext for DynamoDbTestContext {
+        async fn setup() -> DynamoDbTestContext {
+            let number = NUMBER.fetch_add(1, Ordering::SeqCst);
+            let table_name = format!("Checker{number}");
+            let history_table_name = format!("CheckerHistory{number}");
+            let config = load_from_env().await;
+            let client = Client::from_conf(Builder::from(&config).endpoint_url("http://localhost:8000").build());
+
+            client
+                .create_table()
+                .table_name(table_name.as_str())
+                .attribute_definitions(
+                    AttributeDefinition::builder()
+                        .attribute_name("customerAndVesselId")
+                        .attribute_type(ScalarAttributeType::S)
+                        .build(),
+                )
+                .attribute_definitions(
+                    AttributeDefinition::builder()
+                        .attribute_name("licenseKey")
+                        .attribute_type(ScalarAttributeType::S)
+                        .build(),
+                )
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("customerAndVesselId")
+                        .key_type(KeyType::Hash)
+                        .build(),
+                )
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("licenseKey")
+                        .key_type(KeyType::Range)
+                        .build(),
+                )
+                .billing_mode(BillingMode::PayPerRequest)
+                .send()
+                .await
+                .unwrap();
+
+            client
+                .create_table()
+                .table_name(history_table_name.as_str())
+                .attribute_definitions(
+                    AttributeDefinition::builder()
+                        .attribute_name("customerAndVesselId")
+                        .attribute_type(ScalarAttributeType::S)
+                        .build(),
+                )
+                .attribute_definitions(
+                    AttributeDefinition::builder()
+                        .attribute_name("licenseVersion")
+                        .attribute_type(ScalarAttributeType::S)
+                        .build(),
+                )
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("customerAndVesselId")
+                        .key_type(KeyType::Hash)
+                        .build(),
+                )
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("licenseVersion")
+                        .key_type(KeyType::Range)
+                        .build(),
+                )
+                .billing_mode(BillingMode::PayPerRequest)
+                .send()
+                .await
+                .unwrap();
+
+            let context = DynamoDbTestContext {
+                client,
+                table_name,
+                history_table_name,
+            };
+            // valid license, mismatched one and migrator bookkeeping entry
+            context
+                .put(license_of(
+                    &format!("{CUSTOMER_ID}:{VESSEL_ID}"),
+                    CUSTOMER_ID,
+                    VESSEL_ID,
+                ))
+                .await;
+            context
+                .put(license_of(
+                    &format!("{CUSTOMER_ID}:{OTHER_VESSEL_ID}"),
+                    CUSTOMER_ID,
+                    VESSEL_ID,
+                ))
+                .await;
+            context
+                .put(Item::from([
+                    ("customerAndVesselId".into(), S("#schema".into())),
+                    ("licenseKey".into(), S("version".into())),
+                    ("version".into(), N("1".into())),
+                ]))
+                .await;
+
+            context
+        }
+
+        async fn teardown(self) {
+            self.client
+                .delete_table()
+                .table_name(self.table_name)
+                .send()
+                .await
+                .unwrap();
+            self.client
+                .delete_table()
+                .table_name(self.history_table_name)
+                .send()
+                .await
+                .unwrap();
+        }
+    }
+
+    impl DynamoDbTestContext {
+        async fn put(&self, item: Item) {
+            self.client
+                .put_item()
+                .table_name(self.table_name.as_str())
+                .set_item(Some(item))
+                .send()
+                .await
+                .unwrap();
+        }
+
+        async fn get(&self, key: &str) -> Item {
+            self.client
+                .get_item()
+                .table_name(self.table_name.as_str())
+                .key("customerAndVesselId", S(key.into()))
+                .key("licenseKey", S("tides.2023".into()))
+                .send()
+                .await
+                .unwrap()
+                .item
+                .unwrap_or_default()
+        }
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn check_table(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let report = Checker::new(ctx.client.clone(), ctx.table_name.clone()).run().await?;
+
+        assert_eq!(2, report.scanned);
+        assert_eq!(1, report.findings.len());
+        assert_eq!(
+            format!("{CUSTOMER_ID}:{OTHER_VESSEL_ID}"),
+            report.findings[0].customer_and_vessel_id
+        );
+        assert_eq!(
+            Issue::KeyMismatch {
+                attribute: "vesselId".into(),
+                value: VESSEL_ID.to_string(),
+            },
+            report.findings[0].issue
+        );
+        assert!(!report.findings[0].repaired);
+        assert!(!report.is_clean());
+
+        // nothing is written without repair
+        let item = ctx.get(&format!("{CUSTOMER_ID}:{OTHER_VESSEL_ID}")).await;
+        assert_eq!(Some(&S(VESSEL_ID.to_string())), item.get("vesselId"));
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn check_table_repair(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let checker = Checker::new(ctx.client.clone(), ctx.table_name.clone()).with_repair(true);
+
+        let report = checker.run().await?;
+        assert!(report.findings[0].repaired);
+        assert!(report.is_clean());
+
+        let item = ctx.get(&format!("{CUSTOMER_ID}:{OTHER_VESSEL_ID}")).await;
+        assert_eq!(Some(&S(OTHER_VESSEL_ID.to_string())), item.get("vesselId"));
+
+        assert!(checker.run().await?.findings.is_empty());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn check_table_repair_history(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let checker = Checker::new(ctx.client.clone(), ctx.table_name.clone())
+            .with_repair(true)
+            .with_history(ctx.history_table_name.clone());
+
+        assert!(checker.run().await?.is_clean());
+
+        let history = ctx
+            .client
+            .query()
+            .table_name(ctx.history_table_name.as_str())
+            .key_condition_expression("customerAndVesselId = :key")
+            .expression_attribute_values(":key", S(format!("{CUSTOMER_ID}:{OTHER_VESSEL_ID}")))
+            .send()
+            .await
+            .unwrap()
+            .items
+            .unwrap_or_default();
+        assert_eq!(1, history.len());
+        assert_eq!(Some(&S(OTHER_VESSEL_ID.to_string())), history[0].get("vesselId"));
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn check_table_repair_changed(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let checker = Checker::new(ctx.client.clone(), ctx.table_name.clone()).with_repair(true);
+        let key = format!("{CUSTOMER_ID}:{OTHER_VESSEL_ID}");
+        let scanned = ctx.get(&key).await;
+        let (_, repaired) = check_item(&scanned);
+
+        // license changed in the meantime
+        let mut changed = scanned.clone();
+        changed.insert("expiresAt".into(), S("2024-07-01T02:00:00+02:00".into()));
+        ctx.put(changed.clone()).await;
+
+        assert!(!checker.save(&scanned, repaired.unwrap()).await?);
+        assert_eq!(changed, ctx.get(&key).await);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn check_table_repair_deleted(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let checker = Checker::new(ctx.client.clone(), ctx.table_name.clone()).with_repair(true);
+        let key = format!("{CUSTOMER_ID}:{OTHER_VESSEL_ID}");
+        let scanned = ctx.get(&key).await;
+        let (_, repaired) = check_item(&scanned);
+
+        // license deleted in the meantime
+        ctx.client
+            .delete_item()
+            .table_name(ctx.table_name.as_str())
+            .key("customerAndVesselId", S(key.clone()))
+            .key("licenseKey", S("tides.2023".into()))
+            .send()
+            .await
+            .unwrap();
+
+        assert!(!checker.save(&scanned, repaired.unwrap()).await?);
+        assert!(ctx.get(&key).await.is_empty());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn check_table_duplicate(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        // same vessel stored under non-canonical key
+        ctx.put(license_of(
+            &format!("{CUSTOMER_ID}:{}", VESSEL_ID.simple()),
+            CUSTOMER_ID,
+            VESSEL_ID,
+        ))
+        .await;
+
+        let report = Checker::new(ctx.client.clone(), ctx.table_name.clone()).run().await?;
+
+        assert_eq!(3, report.scanned);
+        assert!(report
+            .findings
+            .iter()
+            .any(|finding| finding.issue == Issue::MalformedKey));
+        assert!(report
+            .findings
+            .iter()
+            .any(|finding| matches!(finding.issue, Issue::DuplicateKey { .. })));
+
+        Ok(())
+    }
+}
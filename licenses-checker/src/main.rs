@@ -0,0 +1,35 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use clap::Parser;
+use licenses_checker::{Checker, Cli};
+use licenses_core::load_client;
+use serde_json::to_string_pretty;
+use std::error::Error;
+use std::process::exit;
+use tokio::main as tokio_main;
+
+#[tokio_main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    let client = load_client(cli.endpoint_url).await;
+    let checker = Checker::new(client, cli.table).with_repair(cli.repair);
+    let checker = match cli.history_table {
+        Some(history_table) => checker.with_history(history_table),
+        None => checker,
+    };
+    let report = checker.run().await?;
+
+    println!("{}", to_string_pretty(&report)?);
+
+    // lets scheduled checks fail when manual intervention is needed
+    if !report.is_clean() {
+        exit(1);
+    }
+
+    Ok(())
+}
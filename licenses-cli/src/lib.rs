@@ -0,0 +1,556 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use clap::{value_parser, Parser, Subcommand, ValueEnum};
+use licenses_api_types::{Entitlement, Entitlements, Seats};
+use licenses_core::{
+    list_all, ApiError, License, LicenseRepository, LicensesFilter, RuntimeError, DEFAULT_RETENTION_DAYS,
+};
+use serde_json::to_string_pretty;
+use std::slice::from_ref;
+use uuid::Uuid;
+
+#[derive(Parser)]
+#[command(
+    name = "licenses-cli",
+    about = "Manages vessels licenses directly in DynamoDB table."
+)]
+pub struct Cli {
+    #[doc = "DynamoDB endpoint override, eg. http://localhost:8000 for DynamoDB Local."]
+    #[arg(long, env = "DYNAMODB_ENDPOINT_URL")]
+    pub endpoint_url: Option<String>,
+    #[doc = "Licenses table name."]
+    #[arg(long, env = "LICENSES_TABLE")]
+    pub table: String,
+    #[doc = "Days after expiration when license is removed."]
+    #[arg(long, env = "LICENSE_RETENTION_DAYS", default_value_t = DEFAULT_RETENTION_DAYS)]
+    pub retention_days: i64,
+    #[doc = "License history table name - changes are recorded there if specified."]
+    #[arg(long, env = "LICENSE_HISTORY_TABLE")]
+    pub history_table: Option<String>,
+    #[doc = "Output format."]
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[doc = "Creates (or overwrites) license."]
+    Create {
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        license_key: String,
+        #[doc = "Number of license activations or \"unlimited\"."]
+        #[arg(long)]
+        count: Option<Seats>,
+        #[doc = "License end date (RFC 3339)."]
+        #[arg(long)]
+        expires_at: Option<DateTime<FixedOffset>>,
+        #[doc = "Granted entitlement as NAME=VALUE (boolean, integer or text), may be repeated."]
+        #[arg(long = "entitlement", value_parser = parse_entitlement)]
+        entitlements: Vec<(String, Entitlement)>,
+    },
+    #[doc = "Displays single license."]
+    Get {
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        license_key: String,
+    },
+    #[doc = "Lists all licenses of the vessel."]
+    List {
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        #[doc = "Prefix of listed license keys."]
+        #[arg(long)]
+        license_key_prefix: Option<String>,
+        #[doc = "List only licenses that already ended."]
+        #[arg(long)]
+        expired_only: bool,
+        #[doc = "List only licenses that did not end yet."]
+        #[arg(long, conflicts_with = "expired_only")]
+        active_only: bool,
+        #[doc = "List licenses in reversed key order."]
+        #[arg(long)]
+        descending: bool,
+        #[doc = "Number of licenses fetched with single query."]
+        #[arg(long, value_parser = value_parser!(u16).range(1..))]
+        page_size: Option<u16>,
+    },
+    #[doc = "Deletes license."]
+    Delete {
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        license_key: String,
+    },
+    #[doc = "Prolongs license - already expired license is extended from now."]
+    Extend {
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        license_key: String,
+        #[doc = "Number of days to extend license by."]
+        #[arg(long)]
+        days: u16,
+    },
+    #[doc = "Dumps all licenses of the vessel as JSON."]
+    Export { customer_id: Uuid, vessel_id: Uuid },
+}
+
+pub async fn execute<R: LicenseRepository>(
+    repository: &R,
+    command: Command,
+    output: OutputFormat,
+) -> Result<String, ApiError> {
+    match command {
+        Command::Create {
+            customer_id,
+            vessel_id,
+            license_key,
+            count,
+            expires_at,
+            entitlements,
+        } => {
+            let license = License {
+                customer_id,
+                vessel_id,
+                license_key,
+                count,
+                expires_at,
+                entitlements: entitlements.into_iter().collect(),
+                trial: None,
+            };
+            save(repository, &license).await?;
+            format(&license, output)
+        }
+        Command::Get {
+            customer_id,
+            vessel_id,
+            license_key,
+        } => {
+            let license = fetch(repository, customer_id, vessel_id, license_key).await?;
+            format(&license, output)
+        }
+        Command::List {
+            customer_id,
+            vessel_id,
+            license_key_prefix,
+            expired_only,
+            active_only,
+            descending,
+            page_size,
+        } => {
+            let filter = LicensesFilter {
+                license_key_prefix,
+                expired_only,
+                active_only,
+                descending,
+                as_of: None,
+            };
+            let licenses = list_all(repository, customer_id, vessel_id, &filter, page_size).await?;
+            format_list(&licenses, output)
+        }
+        Command::Delete {
+            customer_id,
+            vessel_id,
+            license_key,
+        } => {
+            let license = fetch(repository, customer_id, vessel_id, license_key).await?;
+            if !repository
+                .delete_license(customer_id, vessel_id, license.license_key.clone())
+                .await?
+            {
+                return Err(ApiError::PooledLicense(license.license_key));
+            }
+            format(&license, output)
+        }
+        Command::Extend {
+            customer_id,
+            vessel_id,
+            license_key,
+            days,
+        } => {
+            let mut license = fetch(repository, customer_id, vessel_id, license_key).await?;
+            license.expires_at = Some(extended(&license, Duration::days(days.into()), &Utc::now())?);
+            save(repository, &license).await?;
+            format(&license, output)
+        }
+        Command::Export { customer_id, vessel_id } => {
+            let licenses = list_all(repository, customer_id, vessel_id, &LicensesFilter::default(), None).await?;
+            Ok(to_string_pretty(&licenses).map_err(RuntimeError::from)?)
+        }
+    }
+}
+
+fn parse_entitlement(input: &str) -> Result<(String, Entitlement), String> {
+    let (name, value) = input
+        .split_once('=')
+        .ok_or_else(|| format!("invalid entitlement \"{input}\", expected NAME=VALUE"))?;
+
+    let entitlement = if let Ok(flag) = value.parse() {
+        Entitlement::Flag(flag)
+    } else if let Ok(limit) = value.parse() {
+        Entitlement::Limit(limit)
+    } else {
+        Entitlement::Value(value.into())
+    };
+
+    Ok((name.into(), entitlement))
+}
+
+async fn fetch<R: LicenseRepository>(
+    repository: &R,
+    customer_id: Uuid,
+    vessel_id: Uuid,
+    license_key: String,
+) -> Result<License, ApiError> {
+    repository
+        .get_license(customer_id, vessel_id, license_key.clone())
+        .await?
+        .ok_or(ApiError::LicenseNotFound(license_key))
+}
+
+async fn save<R: LicenseRepository>(repository: &R, license: &License) -> Result<(), ApiError> {
+    if repository.create_license(license.clone()).await? {
+        Ok(())
+    } else {
+        Err(ApiError::PooledLicense(license.license_key.clone()))
+    }
+}
+
+fn extended(license: &License, period: Duration, now: &DateTime<Utc>) -> Result<DateTime<FixedOffset>, ApiError> {
+    let expires_at = license
+        .expires_at
+        .ok_or_else(|| ApiError::InvalidRequest(format!("license \"{}\" does not expire", license.license_key)))?;
+
+    Ok(if expires_at > *now {
+        expires_at
+    } else {
+        now.with_timezone(&expires_at.timezone())
+    } + period)
+}
+
+fn format(license: &License, output: OutputFormat) -> Result<String, ApiError> {
+    match output {
+        OutputFormat::Json => Ok(to_string_pretty(license).map_err(RuntimeError::from)?),
+        OutputFormat::Table => Ok(table(from_ref(license))),
+    }
+}
+
+// listing is always an array, regardless of how many licenses matched
+fn format_list(licenses: &[License], output: OutputFormat) -> Result<String, ApiError> {
+    match output {
+        OutputFormat::Json => Ok(to_string_pretty(licenses).map_err(RuntimeError::from)?),
+        OutputFormat::Table => Ok(table(licenses)),
+    }
+}
+
+fn table(licenses: &[License]) -> String {
+    let header = ["LICENSE KEY", "COUNT", "EXPIRES AT"].map(String::from);
+    let rows = licenses
+        .iter()
+        .map(|license| {
+            [
+                license.license_key.clone(),
+                license.count.map_or_else(|| "-".into(), |count| count.to_string()),
+                license
+                    .expires_at
+                    .map_or_else(|| "-".into(), |expires_at| expires_at.to_rfc3339()),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let widths = (0..header.len())
+        .map(|column| {
+            rows.iter()
+                .chain([&header])
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+
+    [&header]
+        .into_iter()
+        .chain(rows.iter())
+        .map(|row| {
+            row.iter()
+                .zip(widths.iter())
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{execute, extended, parse_entitlement, table, Cli, Command, OutputFormat};
+    use chrono::{DateTime, Duration, FixedOffset, TimeZone, Utc};
+    use clap::Parser;
+    use licenses_api_types::{Entitlement, Entitlements, Seats};
+    use licenses_core::{ApiError, InMemoryLicenseRepository, License, LicenseRepository};
+    use serde_json::{from_str, Value};
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000000");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    const LICENSE_KEY: &str = "tides.2023";
+
+    fn license(license_key: &str, expires_at: Option<&str>) -> License {
+        License {
+            customer_id: CUSTOMER_ID,
+            vessel_id: VESSEL_ID,
+            license_key: license_key.into(),
+            count: Some(Seats::Limited(2)),
+            expires_at: expires_at.map(|expires_at| DateTime::parse_from_rfc3339(expires_at).unwrap()),
+            entitlements: Entitlements::new(),
+            trial: None,
+        }
+    }
+
+    #[test]
+    fn table_output() {
+        let output = table(&[
+            license(LICENSE_KEY, Some("2023-06-30T12:00:00+02:00")),
+            license("weather", None),
+        ]);
+
+        assert_eq!(
+            "LICENSE KEY  COUNT  EXPIRES AT\n\
+             tides.2023   2      2023-06-30T12:00:00+02:00\n\
+             weather      2      -",
+            output
+        );
+    }
+
+    #[test]
+    fn extend_active() {
+        let now = Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap();
+        let extended = extended(
+            &license(LICENSE_KEY, Some("2023-06-30T12:00:00+02:00")),
+            Duration::days(10),
+            &now,
+        )
+        .unwrap();
+
+        assert_eq!("2023-07-10T12:00:00+02:00", extended.to_rfc3339());
+    }
+
+    #[test]
+    fn extend_expired() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let extended = extended(
+            &license(LICENSE_KEY, Some("2023-06-30T12:00:00+02:00")),
+            Duration::days(10),
+            &now,
+        )
+        .unwrap();
+
+        assert_eq!(FixedOffset::east_opt(7200).unwrap(), extended.timezone());
+        assert_eq!(now + Duration::days(10), extended);
+    }
+
+    #[test]
+    fn extend_perpetual() {
+        match extended(&license(LICENSE_KEY, None), Duration::days(10), &Utc::now()) {
+            Err(ApiError::InvalidRequest(_)) => {}
+            _ => panic!("Perpetual license extended."),
+        }
+    }
+
+    #[test]
+    fn parse_entitlements() {
+        assert_eq!(
+            ("routing".to_string(), Entitlement::Flag(true)),
+            parse_entitlement("routing=true").unwrap()
+        );
+        assert_eq!(
+            ("maxRoutes".to_string(), Entitlement::Limit(20)),
+            parse_entitlement("maxRoutes=20").unwrap()
+        );
+        assert_eq!(
+            ("forecastModel".to_string(), Entitlement::Value("gfs=1".to_string())),
+            parse_entitlement("forecastModel=gfs=1").unwrap()
+        );
+        assert!(parse_entitlement("routing").is_err());
+    }
+
+    #[test]
+    fn invalid_list_arguments() {
+        let (customer_id, vessel_id) = (CUSTOMER_ID.to_string(), VESSEL_ID.to_string());
+        let args = |extra: &[&'static str]| {
+            [
+                vec![
+                    "licenses-cli",
+                    "--table",
+                    "Licenses",
+                    "list",
+                    customer_id.as_str(),
+                    vessel_id.as_str(),
+                ],
+                extra.to_vec(),
+            ]
+            .concat()
+        };
+
+        assert!(Cli::try_parse_from(args(&["--page-size", "10"])).is_ok());
+        assert!(Cli::try_parse_from(args(&["--page-size", "0"])).is_err());
+        assert!(Cli::try_parse_from(args(&["--expired-only", "--active-only"])).is_err());
+    }
+
+    #[tokio_test]
+    async fn create_with_entitlements() {
+        let repository = InMemoryLicenseRepository::new();
+
+        execute(
+            &repository,
+            Command::Create {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+                license_key: LICENSE_KEY.into(),
+                count: None,
+                expires_at: None,
+                entitlements: vec![("maxRoutes".to_string(), Entitlement::Limit(20))],
+            },
+            OutputFormat::Json,
+        )
+        .await
+        .unwrap();
+
+        let license = repository
+            .get_license(CUSTOMER_ID, VESSEL_ID, LICENSE_KEY.into())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            Entitlements::from([("maxRoutes".to_string(), Entitlement::Limit(20))]),
+            license.entitlements
+        );
+    }
+
+    #[tokio_test]
+    async fn get_unexisting() {
+        let result = execute(
+            &InMemoryLicenseRepository::new(),
+            Command::Get {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+                license_key: LICENSE_KEY.into(),
+            },
+            OutputFormat::Table,
+        )
+        .await;
+
+        match result {
+            Err(ApiError::LicenseNotFound(key)) => assert_eq!(LICENSE_KEY, key),
+            _ => panic!("License not found error expected."),
+        }
+    }
+
+    #[tokio_test]
+    async fn list_single_license() {
+        let repository = InMemoryLicenseRepository::new();
+        repository.create_license(license(LICENSE_KEY, None)).await.unwrap();
+
+        let output = execute(
+            &repository,
+            Command::List {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+                license_key_prefix: None,
+                expired_only: false,
+                active_only: false,
+                descending: false,
+                page_size: None,
+            },
+            OutputFormat::Json,
+        )
+        .await
+        .unwrap();
+
+        let listed: Vec<License> = from_str(&output).unwrap();
+        assert_eq!(1, listed.len());
+        assert_eq!(LICENSE_KEY, listed[0].license_key);
+    }
+
+    #[tokio_test]
+    async fn get_license_object() {
+        let repository = InMemoryLicenseRepository::new();
+        repository.create_license(license(LICENSE_KEY, None)).await.unwrap();
+
+        let output = execute(
+            &repository,
+            Command::Get {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+                license_key: LICENSE_KEY.into(),
+            },
+            OutputFormat::Json,
+        )
+        .await
+        .unwrap();
+
+        let fetched: License = from_str(&output).unwrap();
+        assert_eq!(LICENSE_KEY, fetched.license_key);
+    }
+
+    #[tokio_test]
+    async fn delete_license() {
+        let repository = InMemoryLicenseRepository::new();
+        repository.create_license(license(LICENSE_KEY, None)).await.unwrap();
+
+        execute(
+            &repository,
+            Command::Delete {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+                license_key: LICENSE_KEY.into(),
+            },
+            OutputFormat::Table,
+        )
+        .await
+        .unwrap();
+
+        assert!(repository
+            .get_license(CUSTOMER_ID, VESSEL_ID, LICENSE_KEY.into())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio_test]
+    async fn export_licenses() {
+        let repository = InMemoryLicenseRepository::new();
+        repository.create_license(license(LICENSE_KEY, None)).await.unwrap();
+
+        let output = execute(
+            &repository,
+            Command::Export {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+            },
+            OutputFormat::Table,
+        )
+        .await
+        .unwrap();
+
+        let exported: Vec<License> = from_str(&output).unwrap();
+        assert_eq!(1, exported.len());
+        assert_eq!(VESSEL_ID, exported[0].vessel_id);
+    }
+}
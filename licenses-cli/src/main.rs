@@ -0,0 +1,31 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use chrono::Duration;
+use clap::Parser;
+use licenses_cli::{execute, Cli};
+use licenses_core::{load_client, LicenseDao};
+use std::error::Error;
+use tokio::main as tokio_main;
+
+#[tokio_main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    let client = load_client(cli.endpoint_url).await;
+    // corrupted entries need to be fixed, not silently left out of exports
+    let dao = LicenseDao::new(client, cli.table)
+        .with_retention(Duration::days(cli.retention_days))
+        .with_lenient(false);
+    let dao = match cli.history_table {
+        Some(history_table) => dao.with_history(history_table),
+        None => dao,
+    };
+
+    println!("{}", execute(&dao, cli.command, cli.output).await?);
+
+    Ok(())
+}
@@ -0,0 +1,336 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use aws_config::load_from_env;
+use aws_sdk_lambda::config::Builder;
+use aws_sdk_lambda::error::SdkError;
+use aws_sdk_lambda::operation::invoke::InvokeError;
+use aws_sdk_lambda::Client;
+use aws_smithy_types::Blob;
+use chrono::{DateTime, FixedOffset};
+use futures::stream::{iter, try_unfold};
+use futures::{Stream, TryStreamExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{from_slice, from_str, to_vec};
+use std::collections::BTreeMap;
+use std::env::{var, VarError};
+use thiserror::Error;
+use uuid::Uuid;
+
+pub use licenses_api_types::{
+    CreateLicenseRequest, ErrorCode, ErrorResponse, InvocationError, LicenseRequest, LicenseResponse,
+    ListLicensesRequest, ListLicensesResponse, Seats, SkippedLicense,
+};
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("License not found.")]
+    LicenseNotFound,
+    #[error("License already exists.")]
+    LicenseAlreadyExists,
+    #[error("Invalid page token.")]
+    InvalidPageToken,
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("Validation failed: {0:?}")]
+    ValidationFailed(BTreeMap<String, String>),
+    #[error("{}", .0.message)]
+    ServiceError(ErrorResponse),
+    #[error("{error_type}: {message}")]
+    InvocationFailed { error_type: String, message: String },
+    #[error("Missing response payload.")]
+    EmptyResponse,
+    #[error(transparent)]
+    InvokeError(#[from] SdkError<InvokeError>),
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+    #[error(transparent)]
+    ConfigError(#[from] VarError),
+}
+
+impl ClientError {
+    #[doc = "Whether the same call may succeed when repeated later."]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::ServiceError(error) => error.retryable,
+            _ => false,
+        }
+    }
+}
+
+impl From<ErrorResponse> for ClientError {
+    fn from(error: ErrorResponse) -> Self {
+        match error.code {
+            ErrorCode::LicenseNotFound => Self::LicenseNotFound,
+            ErrorCode::LicenseAlreadyExists => Self::LicenseAlreadyExists,
+            ErrorCode::InvalidPageToken => Self::InvalidPageToken,
+            ErrorCode::InvalidRequest => Self::InvalidRequest(
+                error
+                    .message
+                    .strip_prefix("Invalid request: ")
+                    .unwrap_or(&error.message)
+                    .into(),
+            ),
+            ErrorCode::ValidationFailed => Self::ValidationFailed(error.details),
+            _ => Self::ServiceError(error),
+        }
+    }
+}
+
+impl From<InvocationError> for ClientError {
+    fn from(error: InvocationError) -> Self {
+        // failures not produced by handler (eg. timeouts, malformed payloads) carry plain message
+        match from_str::<ErrorResponse>(&error.error_message) {
+            Ok(response) => response.into(),
+            Err(_) => Self::InvocationFailed {
+                error_type: error.error_type,
+                message: error.error_message,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[doc = "Names (or ARNs) of licenses Lambdas."]
+pub struct LicensesFunctions {
+    pub creator: String,
+    pub deleter: String,
+    pub fetcher: String,
+    pub lister: String,
+}
+
+pub struct LicensesClient {
+    lambda: Client,
+    functions: LicensesFunctions,
+}
+
+/**
+Required environment variables:
+<dl>
+    <dt><code>CREATOR_LAMBDA</code>, <code>DELETER_LAMBDA</code>, <code>FETCHER_LAMBDA</code>,
+        <code>LISTER_LAMBDA</code></dt>
+    <dd>Names (or ARNs) of licenses Lambdas.</dd>
+</dl>
+
+Optional environment variables:
+<dl>
+    <dt><code>LAMBDA_ENDPOINT_URL</code></dt>
+    <dd>Overrides Lambda endpoint, eg. to point to <code>licenses-local</code> server.</dd>
+</dl>
+ */
+impl LicensesClient {
+    pub async fn load_from_env() -> Result<Self, ClientError> {
+        let config = &load_from_env().await;
+        let functions = LicensesFunctions {
+            creator: var("CREATOR_LAMBDA")?,
+            deleter: var("DELETER_LAMBDA")?,
+            fetcher: var("FETCHER_LAMBDA")?,
+            lister: var("LISTER_LAMBDA")?,
+        };
+
+        Ok(Self::new(
+            match var("LAMBDA_ENDPOINT_URL") {
+                Ok(endpoint_url) => Client::from_conf(Builder::from(config).endpoint_url(endpoint_url).build()),
+                Err(_) => Client::new(config),
+            },
+            functions,
+        ))
+    }
+
+    pub fn new(lambda: Client, functions: LicensesFunctions) -> Self {
+        Self { lambda, functions }
+    }
+
+    pub async fn create(&self, request: &CreateLicenseRequest) -> Result<String, ClientError> {
+        self.invoke(&self.functions.creator, request).await
+    }
+
+    pub async fn get(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        license_key: String,
+    ) -> Result<LicenseResponse, ClientError> {
+        self.invoke(
+            &self.functions.fetcher,
+            &LicenseRequest {
+                customer_id,
+                vessel_id,
+                license_key,
+                as_of: None,
+            },
+        )
+        .await
+    }
+
+    /**
+    Fetches state of the license at given moment - fails with `LicenseNotFound` if it did not exist back then.
+     */
+    pub async fn get_as_of(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        license_key: String,
+        as_of: DateTime<FixedOffset>,
+    ) -> Result<LicenseResponse, ClientError> {
+        self.invoke(
+            &self.functions.fetcher,
+            &LicenseRequest {
+                customer_id,
+                vessel_id,
+                license_key,
+                as_of: Some(as_of),
+            },
+        )
+        .await
+    }
+
+    pub async fn list(&self, request: &ListLicensesRequest) -> Result<ListLicensesResponse, ClientError> {
+        self.invoke(&self.functions.lister, request).await
+    }
+
+    /**
+    Streams all licenses matching the request, following page tokens.
+     */
+    pub fn list_all(
+        &self,
+        request: ListLicensesRequest,
+    ) -> impl Stream<Item = Result<LicenseResponse, ClientError>> + '_ {
+        try_unfold(Some(request), move |request| async move {
+            Ok(match request {
+                Some(request) => {
+                    let page = self.list(&request).await?;
+                    let next = page.page_token.map(|page_token| ListLicensesRequest {
+                        page_token: Some(page_token),
+                        ..request
+                    });
+
+                    Some((iter(page.licenses.into_iter().map(Ok)), next))
+                }
+                None => None,
+            })
+        })
+        .try_flatten()
+    }
+
+    pub async fn delete(&self, customer_id: Uuid, vessel_id: Uuid, license_key: String) -> Result<(), ClientError> {
+        self.invoke(
+            &self.functions.deleter,
+            &LicenseRequest {
+                customer_id,
+                vessel_id,
+                license_key,
+                as_of: None,
+            },
+        )
+        .await
+    }
+
+    async fn invoke<RequestType: Serialize, ResponseType: DeserializeOwned>(
+        &self,
+        function_name: &str,
+        request: &RequestType,
+    ) -> Result<ResponseType, ClientError> {
+        let output = self
+            .lambda
+            .invoke()
+            .function_name(function_name)
+            .payload(Blob::new(to_vec(request)?))
+            .send()
+            .await?;
+
+        let payload = output.payload().ok_or(ClientError::EmptyResponse)?.as_ref();
+        parse_payload(payload, output.function_error().is_some())
+    }
+}
+
+fn parse_payload<ResponseType: DeserializeOwned>(payload: &[u8], failed: bool) -> Result<ResponseType, ClientError> {
+    if failed {
+        Err(from_slice::<InvocationError>(payload)?.into())
+    } else {
+        Ok(from_slice(payload)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_payload, ClientError, ErrorCode, ErrorResponse, InvocationError, LicenseResponse, Seats};
+    use serde_json::to_vec;
+
+    fn failure(error_message: String) -> ClientError {
+        parse_payload::<()>(
+            &to_vec(&InvocationError {
+                error_type: "licenses_core::lambda::LambdaFailure".into(),
+                error_message,
+            })
+            .unwrap(),
+            true,
+        )
+        .unwrap_err()
+    }
+
+    fn error(code: ErrorCode, message: &str) -> ClientError {
+        failure(serde_json::to_string(&ErrorResponse::new(code, message)).unwrap())
+    }
+
+    #[test]
+    fn parse_response() {
+        let license: LicenseResponse = parse_payload(b"{\"licenseKey\":\"tides\",\"count\":2}", false).unwrap();
+
+        assert_eq!("tides", license.license_key);
+        assert_eq!(Some(Seats::Limited(2)), license.count);
+        assert!(license.expires_at.is_none());
+    }
+
+    #[test]
+    fn parse_unit_response() {
+        parse_payload::<()>(b"null", false).unwrap();
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(matches!(
+            error(ErrorCode::LicenseNotFound, "License not found."),
+            ClientError::LicenseNotFound
+        ));
+        assert!(matches!(
+            error(ErrorCode::LicenseAlreadyExists, "License already exists."),
+            ClientError::LicenseAlreadyExists
+        ));
+        assert!(matches!(
+            error(ErrorCode::InvalidPageToken, "Invalid page token."),
+            ClientError::InvalidPageToken
+        ));
+        assert!(matches!(
+            error(ErrorCode::InvalidRequest, "Invalid request: bad key"),
+            ClientError::InvalidRequest(reason) if reason == "bad key"
+        ));
+        assert!(matches!(
+            failure(r#"{"code":"VALIDATION_FAILED","message":"Validation failed.","details":{"count":"must be positive"},"retryable":false}"#.into()),
+            ClientError::ValidationFailed(fields) if fields["count"] == "must be positive"
+        ));
+    }
+
+    #[test]
+    fn parse_service_errors() {
+        let throttled = error(ErrorCode::Throttled, "Too many requests, try again later.");
+        assert!(matches!(&throttled, ClientError::ServiceError(error) if error.code == ErrorCode::Throttled));
+        assert!(throttled.is_retryable());
+
+        let internal = error(ErrorCode::InternalError, "Internal service error.");
+        assert!(matches!(&internal, ClientError::ServiceError(error) if error.code == ErrorCode::InternalError));
+        assert!(!internal.is_retryable());
+    }
+
+    #[test]
+    fn parse_invocation_failure() {
+        assert!(matches!(
+            failure("Task timed out after 3.00 seconds".into()),
+            ClientError::InvocationFailed { message, .. } if message == "Task timed out after 3.00 seconds"
+        ));
+    }
+}
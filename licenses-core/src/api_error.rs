@@ -4,15 +4,38 @@
  * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
  */
 
-use crate::RuntimeError;
+use crate::{FieldError, RuntimeError};
+use licenses_api_types::{ErrorCode, ErrorResponse};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum ApiError {
-    #[error(transparent)]
-    RuntimeError(Box<RuntimeError>),
+    #[error("Internal service error.")]
+    RuntimeError(#[source] Box<RuntimeError>),
     #[error("License not found.")]
     LicenseNotFound(String),
+    #[error("License already exists.")]
+    LicenseAlreadyExists(String),
+    #[error("Product not found.")]
+    ProductNotFound(String),
+    #[error("Pool not found.")]
+    PoolNotFound(String),
+    #[error("Pool already exists.")]
+    PoolAlreadyExists(String),
+    #[error("Not enough seats available in pool.")]
+    InsufficientSeats(String),
+    #[error("Trial already used.")]
+    TrialAlreadyUsed(String),
+    #[error("License is not a trial.")]
+    NotTrial(String),
+    #[error("License is allocated from pool.")]
+    PooledLicense(String),
+    #[error("Invalid page token.")]
+    InvalidPageToken,
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("Validation failed.")]
+    ValidationFailed(Vec<FieldError>),
 }
 
 impl From<RuntimeError> for ApiError {
@@ -21,9 +44,67 @@ impl From<RuntimeError> for ApiError {
     }
 }
 
+impl ApiError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::RuntimeError(error) => error.code(),
+            Self::LicenseNotFound(_) => ErrorCode::LicenseNotFound,
+            Self::LicenseAlreadyExists(_) => ErrorCode::LicenseAlreadyExists,
+            Self::ProductNotFound(_) => ErrorCode::ProductNotFound,
+            Self::PoolNotFound(_) => ErrorCode::PoolNotFound,
+            Self::PoolAlreadyExists(_) => ErrorCode::PoolAlreadyExists,
+            Self::InsufficientSeats(_) => ErrorCode::InsufficientSeats,
+            Self::TrialAlreadyUsed(_) => ErrorCode::TrialAlreadyUsed,
+            Self::NotTrial(_) => ErrorCode::NotTrial,
+            Self::PooledLicense(_) => ErrorCode::PooledLicense,
+            Self::InvalidPageToken => ErrorCode::InvalidPageToken,
+            Self::InvalidRequest(_) => ErrorCode::InvalidRequest,
+            Self::ValidationFailed(_) => ErrorCode::ValidationFailed,
+        }
+    }
+}
+
+impl From<&ApiError> for ErrorResponse {
+    fn from(error: &ApiError) -> Self {
+        let code = error.code();
+        let response = match code {
+            ErrorCode::Conflict => Self::new(code, "Request conflicts with concurrent change."),
+            ErrorCode::Throttled => Self::new(code, "Too many requests, try again later."),
+            ErrorCode::ServiceUnavailable => Self::new(code, "Service temporarily unavailable, try again later."),
+            _ => Self::new(code, error.to_string()),
+        };
+
+        match error {
+            ApiError::LicenseNotFound(license_key)
+            | ApiError::LicenseAlreadyExists(license_key)
+            | ApiError::TrialAlreadyUsed(license_key)
+            | ApiError::NotTrial(license_key)
+            | ApiError::PooledLicense(license_key) => response.with_detail("licenseKey", license_key),
+            ApiError::ProductNotFound(product_key)
+            | ApiError::PoolNotFound(product_key)
+            | ApiError::PoolAlreadyExists(product_key)
+            | ApiError::InsufficientSeats(product_key) => response.with_detail("productKey", product_key),
+            ApiError::ValidationFailed(errors) => errors.iter().fold(response, |mut response, error| {
+                response
+                    .details
+                    .entry(error.field.clone())
+                    .and_modify(|message| {
+                        message.push_str("; ");
+                        message.push_str(&error.message);
+                    })
+                    .or_insert_with(|| error.message.clone());
+                response
+            }),
+            _ => response,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{ApiError, RuntimeError};
+    use crate::{ApiError, FieldError, RuntimeError};
+    use licenses_api_types::{ErrorCode, ErrorResponse};
+    use serde_json::from_str;
     use std::env::VarError;
 
     #[test]
@@ -35,4 +116,67 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn runtime_error_response() {
+        let error = ApiError::from(RuntimeError::ClientConfigLoadingError(VarError::NotPresent));
+        let response = ErrorResponse::from(&error);
+
+        assert_eq!(ErrorCode::InternalError, response.code);
+        assert_eq!("Internal service error.", response.message);
+        assert!(!response.message.contains("ClientConfigLoadingError"));
+        assert!(response.details.is_empty());
+        assert!(!response.retryable);
+    }
+
+    #[test]
+    fn serialization_error_response() {
+        let error = ApiError::from(RuntimeError::from(from_str::<u8>("x").unwrap_err()));
+
+        assert_eq!(ErrorCode::InternalError, ErrorResponse::from(&error).code);
+    }
+
+    #[test]
+    fn not_found_response() {
+        let response = ErrorResponse::from(&ApiError::LicenseNotFound("tides".into()));
+
+        assert_eq!(ErrorCode::LicenseNotFound, response.code);
+        assert_eq!("License not found.", response.message);
+        assert_eq!("tides", response.details["licenseKey"]);
+        assert!(!response.retryable);
+    }
+
+    #[test]
+    fn insufficient_seats_response() {
+        let response = ErrorResponse::from(&ApiError::InsufficientSeats("tides".into()));
+
+        assert_eq!(ErrorCode::InsufficientSeats, response.code);
+        assert_eq!("Not enough seats available in pool.", response.message);
+        assert_eq!("tides", response.details["productKey"]);
+    }
+
+    #[test]
+    fn invalid_page_token_response() {
+        let response = ErrorResponse::from(&ApiError::InvalidPageToken);
+
+        assert_eq!(ErrorCode::InvalidPageToken, response.code);
+        assert_eq!("Invalid page token.", response.message);
+    }
+
+    #[test]
+    fn validation_failed_response() {
+        let response = ErrorResponse::from(&ApiError::ValidationFailed(vec![
+            FieldError::new("licenseKey", "must be at most 64 characters long"),
+            FieldError::new("licenseKey", "is not a registered product"),
+            FieldError::new("count", "must be positive"),
+        ]));
+
+        assert_eq!(ErrorCode::ValidationFailed, response.code);
+        assert_eq!("Validation failed.", response.message);
+        assert_eq!(
+            "must be at most 64 characters long; is not a registered product",
+            response.details["licenseKey"]
+        );
+        assert_eq!("must be positive", response.details["count"]);
+    }
 }
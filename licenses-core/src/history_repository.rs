@@ -0,0 +1,61 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::model::{License, LicenseVersion};
+use crate::runtime_error::RuntimeError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[async_trait]
+pub trait HistoryRepository: Send + Sync {
+    /**
+    Lists all recorded versions of the vessel licenses (or only of the given license), ordered by license key and
+    time.
+     */
+    async fn license_history(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        license_key: Option<String>,
+    ) -> Result<Vec<LicenseVersion>, RuntimeError>;
+
+    /**
+    Reconstructs license state at given moment - `None` if the license did not exist back then.
+     */
+    async fn license_as_of(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        license_key: String,
+        as_of: DateTime<Utc>,
+    ) -> Result<Option<License>, RuntimeError> {
+        Ok(self
+            .license_history(customer_id, vessel_id, Some(license_key))
+            .await?
+            .into_iter()
+            .find(|version| version.is_valid_at(&as_of))
+            .and_then(|version| version.license))
+    }
+
+    /**
+    Reconstructs state of all vessel licenses at given moment, ordered by license key.
+     */
+    async fn licenses_as_of(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        as_of: DateTime<Utc>,
+    ) -> Result<Vec<License>, RuntimeError> {
+        Ok(self
+            .license_history(customer_id, vessel_id, None)
+            .await?
+            .into_iter()
+            .filter(|version| version.is_valid_at(&as_of))
+            .filter_map(|version| version.license)
+            .collect())
+    }
+}
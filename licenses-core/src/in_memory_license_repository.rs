@@ -0,0 +1,401 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::history_repository::HistoryRepository;
+use crate::license_repository::LicenseRepository;
+use crate::model::{page_of, DynamoResultsPage, License, LicenseVersion, LicensesFilter, LicensesSummary, PageKey};
+use crate::runtime_error::RuntimeError;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use uuid::Uuid;
+
+type Licenses = BTreeMap<(Uuid, Uuid), BTreeMap<String, License>>;
+// (customer, vessel) -> (license key, time) -> state
+type History = BTreeMap<(Uuid, Uuid), BTreeMap<(String, DateTime<Utc>), Option<License>>>;
+
+#[derive(Default)]
+#[doc = "Thread-safe licenses storage that keeps all data (including history of changes) in process memory."]
+pub struct InMemoryLicenseRepository {
+    licenses: RwLock<Licenses>,
+    history: RwLock<History>,
+    // (customer, vessel, license key) of licenses allocated from pools
+    pooled: RwLock<BTreeSet<(Uuid, Uuid, String)>>,
+}
+
+impl InMemoryLicenseRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Licenses> {
+        // every write is a single map operation, so data behind poisoned lock is still consistent
+        self.licenses.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn write(&self) -> RwLockWriteGuard<'_, Licenses> {
+        self.licenses.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /**
+    Stores new state of the license in history - `None` marks deletion.
+     */
+    pub(crate) fn record(&self, customer_id: Uuid, vessel_id: Uuid, license_key: String, license: Option<License>) {
+        self.history
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry((customer_id, vessel_id))
+            .or_default()
+            .insert((license_key, Utc::now()), license);
+    }
+
+    /**
+    Marks license as allocated from a pool (or releases the mark).
+     */
+    pub(crate) fn set_pooled(&self, customer_id: Uuid, vessel_id: Uuid, license_key: String, pooled: bool) {
+        let mut licenses = self.pooled.write().unwrap_or_else(PoisonError::into_inner);
+        if pooled {
+            licenses.insert((customer_id, vessel_id, license_key));
+        } else {
+            licenses.remove(&(customer_id, vessel_id, license_key));
+        }
+    }
+
+    fn is_pooled(&self, customer_id: Uuid, vessel_id: Uuid, license_key: String) -> bool {
+        self.pooled
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(&(customer_id, vessel_id, license_key))
+    }
+}
+
+#[async_trait]
+impl LicenseRepository for InMemoryLicenseRepository {
+    async fn create_license(&self, license: License) -> Result<bool, RuntimeError> {
+        if self.is_pooled(license.customer_id, license.vessel_id, license.license_key.clone()) {
+            return Ok(false);
+        }
+
+        self.record(
+            license.customer_id,
+            license.vessel_id,
+            license.license_key.clone(),
+            Some(license.clone()),
+        );
+        self.write()
+            .entry((license.customer_id, license.vessel_id))
+            .or_default()
+            .insert(license.license_key.clone(), license);
+        Ok(true)
+    }
+
+    async fn insert_license(&self, license: License) -> Result<bool, RuntimeError> {
+        Ok(
+            match self
+                .write()
+                .entry((license.customer_id, license.vessel_id))
+                .or_default()
+                .entry(license.license_key.clone())
+            {
+                Entry::Occupied(_) => false,
+                Entry::Vacant(entry) => {
+                    self.record(
+                        license.customer_id,
+                        license.vessel_id,
+                        license.license_key.clone(),
+                        Some(license.clone()),
+                    );
+                    entry.insert(license);
+                    true
+                }
+            },
+        )
+    }
+
+    async fn list_licenses(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        filter: &LicensesFilter,
+        limit: Option<u16>,
+        page_key: Option<PageKey>,
+    ) -> Result<DynamoResultsPage<License, PageKey>, RuntimeError> {
+        let licenses = self
+            .read()
+            .get(&(customer_id, vessel_id))
+            .map(|vessel| vessel.values().cloned().collect())
+            .unwrap_or_default();
+
+        Ok(page_of(licenses, filter, limit, page_key, &Utc::now()))
+    }
+
+    async fn get_license(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        license_key: String,
+    ) -> Result<Option<License>, RuntimeError> {
+        Ok(self
+            .read()
+            .get(&(customer_id, vessel_id))
+            .and_then(|vessel| vessel.get(&license_key))
+            .cloned())
+    }
+
+    async fn delete_license(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        license_key: String,
+    ) -> Result<bool, RuntimeError> {
+        if self.is_pooled(customer_id, vessel_id, license_key.clone()) {
+            return Ok(false);
+        }
+
+        let removed = self
+            .write()
+            .get_mut(&(customer_id, vessel_id))
+            .and_then(|vessel| vessel.remove(&license_key));
+        if removed.is_some() {
+            self.record(customer_id, vessel_id, license_key, None);
+        }
+        Ok(true)
+    }
+
+    async fn summarize(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Option<Uuid>,
+        expiring_within: Duration,
+    ) -> Result<LicensesSummary, RuntimeError> {
+        let now = Utc::now();
+        let horizon = now + expiring_within;
+
+        let mut summary = LicensesSummary::default();
+        self.read()
+            .iter()
+            .filter(|((customer, vessel), _)| {
+                *customer == customer_id && vessel_id.map_or(true, |vessel_id| *vessel == vessel_id)
+            })
+            .flat_map(|(_, licenses)| licenses.values())
+            .for_each(|license| summary.add(license.count, license.expires_at, &now, &horizon));
+
+        Ok(summary)
+    }
+}
+
+#[async_trait]
+impl HistoryRepository for InMemoryLicenseRepository {
+    async fn license_history(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        license_key: Option<String>,
+    ) -> Result<Vec<LicenseVersion>, RuntimeError> {
+        Ok(LicenseVersion::from_states(
+            self.history
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(&(customer_id, vessel_id))
+                .map(|vessel| {
+                    vessel
+                        .iter()
+                        .filter(|((key, _), _)| license_key.as_ref().map_or(true, |license_key| key == license_key))
+                        .map(|((key, at), license)| (key.clone(), *at, license.clone()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        HistoryRepository, InMemoryLicenseRepository, License, LicenseRepository, LicensesFilter, RuntimeError,
+    };
+    use chrono::{Duration, FixedOffset, TimeZone, Utc};
+    use licenses_api_types::{Entitlements, Seats};
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    // customers
+    static ID_0: Uuid = uuid!("00000000-0000-0000-0000-000000000000");
+    // vessels
+    static ID_1: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    static ID_2: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    // licenses
+    static LICENSE_KEY_0: &str = "tides.2023";
+    static LICENSE_KEY_1: &str = "weather.2022";
+    static LICENSE_KEY_2: &str = "weather.2023";
+
+    async fn repository() -> InMemoryLicenseRepository {
+        let repository = InMemoryLicenseRepository::new();
+        let expired = Utc
+            .with_ymd_and_hms(2011, 1, 30, 13, 58, 0)
+            .unwrap()
+            .with_timezone(&FixedOffset::east_opt(3600).unwrap());
+
+        for (vessel_id, license_key, count, expires_at) in [
+            (ID_1, LICENSE_KEY_0, Some(Seats::Limited(2)), None),
+            (ID_1, LICENSE_KEY_1, None, Some(expired)),
+            (ID_1, LICENSE_KEY_2, Some(Seats::Limited(1)), None),
+            (ID_2, LICENSE_KEY_0, Some(Seats::Limited(2)), None),
+        ] {
+            repository
+                .create_license(License {
+                    customer_id: ID_0,
+                    vessel_id,
+                    license_key: license_key.into(),
+                    count,
+                    expires_at,
+                    entitlements: Entitlements::new(),
+                    trial: None,
+                })
+                .await
+                .unwrap();
+        }
+
+        repository
+    }
+
+    #[tokio_test]
+    async fn list_licenses_pages() -> Result<(), RuntimeError> {
+        let repository = repository().await;
+        let filter = LicensesFilter::default();
+
+        let first = repository.list_licenses(ID_0, ID_1, &filter, Some(2), None).await?;
+        assert_eq!(2, first.items.len());
+        assert_eq!(LICENSE_KEY_0, first.items[0].license_key);
+        assert_eq!(LICENSE_KEY_1, first.items[1].license_key);
+
+        let second = repository
+            .list_licenses(ID_0, ID_1, &filter, Some(2), first.last_evaluated_key)
+            .await?;
+        assert_eq!(1, second.items.len());
+        assert_eq!(LICENSE_KEY_2, second.items[0].license_key);
+        assert!(second.last_evaluated_key.is_none());
+
+        Ok(())
+    }
+
+    #[tokio_test]
+    async fn list_licenses_descending_filtered() -> Result<(), RuntimeError> {
+        let repository = repository().await;
+        let filter = LicensesFilter {
+            license_key_prefix: Some("weather.".into()),
+            active_only: true,
+            descending: true,
+            ..LicensesFilter::default()
+        };
+
+        let page = repository.list_licenses(ID_0, ID_1, &filter, Some(1), None).await?;
+        assert_eq!(1, page.items.len());
+        assert_eq!(LICENSE_KEY_2, page.items[0].license_key);
+
+        let page = repository
+            .list_licenses(ID_0, ID_1, &filter, Some(1), page.last_evaluated_key)
+            .await?;
+        assert!(page.items.is_empty());
+        assert!(page.last_evaluated_key.is_none());
+
+        Ok(())
+    }
+
+    #[tokio_test]
+    async fn delete_license() -> Result<(), RuntimeError> {
+        let repository = repository().await;
+
+        repository.delete_license(ID_0, ID_1, LICENSE_KEY_0.into()).await?;
+        assert!(repository
+            .get_license(ID_0, ID_1, LICENSE_KEY_0.into())
+            .await?
+            .is_none());
+        assert!(repository
+            .get_license(ID_0, ID_2, LICENSE_KEY_0.into())
+            .await?
+            .is_some());
+
+        Ok(())
+    }
+
+    #[tokio_test]
+    async fn license_history() -> Result<(), RuntimeError> {
+        let repository = InMemoryLicenseRepository::new();
+        for count in [2, 3] {
+            repository
+                .create_license(License {
+                    customer_id: ID_0,
+                    vessel_id: ID_1,
+                    license_key: LICENSE_KEY_0.into(),
+                    count: Some(Seats::Limited(count)),
+                    expires_at: None,
+                    entitlements: Entitlements::new(),
+                    trial: None,
+                })
+                .await?;
+        }
+        repository.delete_license(ID_0, ID_1, LICENSE_KEY_0.into()).await?;
+        // deleting unexisting license changes nothing
+        repository.delete_license(ID_0, ID_1, LICENSE_KEY_0.into()).await?;
+
+        let versions = repository
+            .license_history(ID_0, ID_1, Some(LICENSE_KEY_0.into()))
+            .await?;
+        assert_eq!(3, versions.len());
+        assert_eq!(Some(versions[1].valid_from), versions[0].valid_to);
+        assert_eq!(Some(versions[2].valid_from), versions[1].valid_to);
+        assert!(versions[2].valid_to.is_none());
+        assert!(versions[2].license.is_none());
+
+        let first = repository
+            .license_as_of(ID_0, ID_1, LICENSE_KEY_0.into(), versions[0].valid_from)
+            .await?;
+        assert_eq!(Some(Seats::Limited(2)), first.unwrap().count);
+        let second = repository.licenses_as_of(ID_0, ID_1, versions[1].valid_from).await?;
+        assert_eq!(1, second.len());
+        assert_eq!(Some(Seats::Limited(3)), second[0].count);
+        assert!(repository
+            .license_as_of(ID_0, ID_1, LICENSE_KEY_0.into(), versions[2].valid_from)
+            .await?
+            .is_none());
+        assert!(repository
+            .license_as_of(
+                ID_0,
+                ID_1,
+                LICENSE_KEY_0.into(),
+                versions[0].valid_from - Duration::seconds(1)
+            )
+            .await?
+            .is_none());
+
+        Ok(())
+    }
+
+    #[tokio_test]
+    async fn summarize() -> Result<(), RuntimeError> {
+        let repository = repository().await;
+
+        let vessel = repository.summarize(ID_0, Some(ID_1), Duration::days(30)).await?;
+        assert_eq!(3, vessel.total);
+        assert_eq!(1, vessel.expired);
+        assert_eq!(3, vessel.seats);
+
+        let customer = repository.summarize(ID_0, None, Duration::days(30)).await?;
+        assert_eq!(4, customer.total);
+        assert_eq!(5, customer.seats);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio_test]
+    async fn conformance() -> Result<(), RuntimeError> {
+        crate::testing::check_all(&InMemoryLicenseRepository::new()).await
+    }
+}
@@ -0,0 +1,312 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::in_memory_license_repository::InMemoryLicenseRepository;
+use crate::model::{Pool, SeatsAllocation};
+use crate::pool_repository::PoolRepository;
+use crate::runtime_error::RuntimeError;
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use uuid::Uuid;
+
+#[derive(Default)]
+struct Pools {
+    pools: BTreeMap<(Uuid, String), Pool>,
+    // (customer, vessel, product) -> seats
+    allocations: BTreeMap<(Uuid, Uuid, String), u32>,
+}
+
+#[doc = "Thread-safe pools storage that keeps all data in process memory, allocating licenses in given repository."]
+pub struct InMemoryPoolRepository {
+    licenses: Arc<InMemoryLicenseRepository>,
+    pools: RwLock<Pools>,
+}
+
+impl InMemoryPoolRepository {
+    pub fn new(licenses: Arc<InMemoryLicenseRepository>) -> Self {
+        Self {
+            licenses,
+            pools: RwLock::default(),
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Pools> {
+        self.pools.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // pools lock is held for the whole allocation, which makes pool and license changes atomic
+    fn write(&self) -> RwLockWriteGuard<'_, Pools> {
+        self.pools.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[async_trait]
+impl PoolRepository for InMemoryPoolRepository {
+    async fn insert_pool(&self, pool: Pool) -> Result<bool, RuntimeError> {
+        Ok(
+            match self.write().pools.entry((pool.customer_id, pool.product_key.clone())) {
+                Entry::Occupied(_) => false,
+                Entry::Vacant(entry) => {
+                    entry.insert(pool);
+                    true
+                }
+            },
+        )
+    }
+
+    async fn get_pool(&self, customer_id: Uuid, product_key: String) -> Result<Option<Pool>, RuntimeError> {
+        Ok(self.read().pools.get(&(customer_id, product_key)).cloned())
+    }
+
+    async fn list_pools(&self, customer_id: Uuid) -> Result<Vec<Pool>, RuntimeError> {
+        Ok(self
+            .read()
+            .pools
+            .values()
+            .filter(|pool| pool.customer_id == customer_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn allocate_seats(&self, pool: &Pool, vessel_id: Uuid, seats: u32) -> Result<SeatsAllocation, RuntimeError> {
+        let now = Utc::now();
+        let mut state = self.write();
+
+        let Some(current) = state
+            .pools
+            .get_mut(&(pool.customer_id, pool.product_key.clone()))
+            .filter(|current| current.is_active(&now) && current.available_seats >= seats)
+        else {
+            return Ok(SeatsAllocation::InsufficientSeats);
+        };
+
+        let license = current.license_of(vessel_id, seats);
+        match self
+            .licenses
+            .write()
+            .entry((license.customer_id, license.vessel_id))
+            .or_default()
+            .entry(license.license_key.clone())
+        {
+            Entry::Occupied(_) => return Ok(SeatsAllocation::AlreadyAllocated),
+            Entry::Vacant(entry) => {
+                self.licenses.record(
+                    license.customer_id,
+                    license.vessel_id,
+                    license.license_key.clone(),
+                    Some(license.clone()),
+                );
+                self.licenses.set_pooled(
+                    license.customer_id,
+                    license.vessel_id,
+                    license.license_key.clone(),
+                    true,
+                );
+                entry.insert(license);
+            }
+        }
+
+        current.available_seats -= seats;
+        state
+            .allocations
+            .insert((pool.customer_id, vessel_id, pool.product_key.clone()), seats);
+
+        Ok(SeatsAllocation::Allocated)
+    }
+
+    async fn release_seats(
+        &self,
+        customer_id: Uuid,
+        product_key: String,
+        vessel_id: Uuid,
+    ) -> Result<bool, RuntimeError> {
+        let mut state = self.write();
+
+        let Some(seats) = state.allocations.remove(&(customer_id, vessel_id, product_key.clone())) else {
+            return Ok(false);
+        };
+
+        if let Some(vessel) = self.licenses.write().get_mut(&(customer_id, vessel_id)) {
+            vessel.remove(&product_key);
+        }
+        self.licenses.record(customer_id, vessel_id, product_key.clone(), None);
+        self.licenses
+            .set_pooled(customer_id, vessel_id, product_key.clone(), false);
+
+        if let Some(pool) = state.pools.get_mut(&(customer_id, product_key)) {
+            pool.available_seats += seats;
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        InMemoryLicenseRepository, InMemoryPoolRepository, License, LicenseRepository, Pool, PoolRepository,
+        RuntimeError, SeatsAllocation,
+    };
+    use chrono::{Duration, Utc};
+    use licenses_api_types::{Entitlements, Seats};
+    use std::sync::Arc;
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    // customers
+    static ID_0: Uuid = uuid!("00000000-0000-0000-0000-000000000000");
+    // vessels
+    static ID_1: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    static ID_2: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    // products
+    static PRODUCT_KEY_0: &str = "tides.2023";
+    static PRODUCT_KEY_1: &str = "weather.2023";
+
+    fn pool(product_key: &str, total_seats: u32) -> Pool {
+        Pool {
+            customer_id: ID_0,
+            product_key: product_key.into(),
+            total_seats,
+            available_seats: total_seats,
+            expires_at: Some((Utc::now() + Duration::days(30)).into()),
+        }
+    }
+
+    async fn repositories() -> (Arc<InMemoryLicenseRepository>, InMemoryPoolRepository) {
+        let licenses = Arc::new(InMemoryLicenseRepository::new());
+        let pools = InMemoryPoolRepository::new(licenses.clone());
+
+        pools.insert_pool(pool(PRODUCT_KEY_1, 5)).await.unwrap();
+        pools.insert_pool(pool(PRODUCT_KEY_0, 3)).await.unwrap();
+
+        (licenses, pools)
+    }
+
+    #[tokio_test]
+    async fn insert_and_list() -> Result<(), RuntimeError> {
+        let (_, pools) = repositories().await;
+
+        assert!(!pools.insert_pool(pool(PRODUCT_KEY_0, 10)).await?);
+
+        let list = pools.list_pools(ID_0).await?;
+        assert_eq!(2, list.len());
+        assert_eq!(PRODUCT_KEY_0, list[0].product_key);
+        assert_eq!(3, list[0].total_seats);
+        assert!(pools.list_pools(ID_1).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio_test]
+    async fn allocate_and_release() -> Result<(), RuntimeError> {
+        let (licenses, pools) = repositories().await;
+        let current = pools.get_pool(ID_0, PRODUCT_KEY_0.into()).await?.unwrap();
+
+        assert_eq!(
+            SeatsAllocation::Allocated,
+            pools.allocate_seats(&current, ID_1, 2).await?
+        );
+        assert_eq!(
+            SeatsAllocation::InsufficientSeats,
+            pools.allocate_seats(&current, ID_2, 2).await?
+        );
+        assert_eq!(
+            SeatsAllocation::AlreadyAllocated,
+            pools.allocate_seats(&current, ID_1, 1).await?
+        );
+
+        let license = licenses.get_license(ID_0, ID_1, PRODUCT_KEY_0.into()).await?.unwrap();
+        assert_eq!(Some(Seats::Limited(2)), license.count);
+        assert_eq!(current.expires_at, license.expires_at);
+        assert_eq!(
+            2,
+            pools
+                .get_pool(ID_0, PRODUCT_KEY_0.into())
+                .await?
+                .unwrap()
+                .allocated_seats()
+        );
+
+        assert!(pools.release_seats(ID_0, PRODUCT_KEY_0.into(), ID_1).await?);
+        assert!(!pools.release_seats(ID_0, PRODUCT_KEY_0.into(), ID_1).await?);
+        assert!(licenses.get_license(ID_0, ID_1, PRODUCT_KEY_0.into()).await?.is_none());
+        assert_eq!(
+            3,
+            pools
+                .get_pool(ID_0, PRODUCT_KEY_0.into())
+                .await?
+                .unwrap()
+                .available_seats
+        );
+
+        Ok(())
+    }
+
+    #[tokio_test]
+    async fn allocate_expired() -> Result<(), RuntimeError> {
+        let (_, pools) = repositories().await;
+        let mut expired = pool("expired", 10);
+        expired.expires_at = Some((Utc::now() - Duration::days(1)).into());
+        pools.insert_pool(expired.clone()).await?;
+
+        assert_eq!(
+            SeatsAllocation::InsufficientSeats,
+            pools.allocate_seats(&expired, ID_1, 1).await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio_test]
+    async fn modify_pooled() -> Result<(), RuntimeError> {
+        let (licenses, pools) = repositories().await;
+        let current = pools.get_pool(ID_0, PRODUCT_KEY_0.into()).await?.unwrap();
+        pools.allocate_seats(&current, ID_1, 2).await?;
+
+        let mut license = licenses.get_license(ID_0, ID_1, PRODUCT_KEY_0.into()).await?.unwrap();
+        license.count = Some(Seats::Unlimited);
+        assert!(!licenses.create_license(license).await?);
+        assert!(!licenses.delete_license(ID_0, ID_1, PRODUCT_KEY_0.into()).await?);
+        assert_eq!(
+            Some(Seats::Limited(2)),
+            licenses
+                .get_license(ID_0, ID_1, PRODUCT_KEY_0.into())
+                .await?
+                .unwrap()
+                .count
+        );
+
+        // released license key is no longer protected
+        assert!(pools.release_seats(ID_0, PRODUCT_KEY_0.into(), ID_1).await?);
+        assert!(licenses.delete_license(ID_0, ID_1, PRODUCT_KEY_0.into()).await?);
+
+        Ok(())
+    }
+
+    #[tokio_test]
+    async fn release_not_pooled() -> Result<(), RuntimeError> {
+        let (licenses, pools) = repositories().await;
+        licenses
+            .create_license(License {
+                customer_id: ID_0,
+                vessel_id: ID_1,
+                license_key: PRODUCT_KEY_1.into(),
+                count: Some(Seats::Limited(1)),
+                expires_at: None,
+                entitlements: Entitlements::new(),
+                trial: None,
+            })
+            .await?;
+
+        assert!(!pools.release_seats(ID_0, PRODUCT_KEY_1.into(), ID_1).await?);
+        assert!(licenses.get_license(ID_0, ID_1, PRODUCT_KEY_1.into()).await?.is_some());
+
+        Ok(())
+    }
+}
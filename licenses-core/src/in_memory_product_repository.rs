@@ -0,0 +1,125 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::model::Product;
+use crate::product_repository::ProductRepository;
+use crate::runtime_error::RuntimeError;
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::sync::{PoisonError, RwLock};
+
+#[derive(Default)]
+#[doc = "Thread-safe product catalogue that keeps all data in process memory."]
+pub struct InMemoryProductRepository {
+    products: RwLock<BTreeMap<String, Product>>,
+}
+
+impl InMemoryProductRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<const N: usize> From<[Product; N]> for InMemoryProductRepository {
+    fn from(products: [Product; N]) -> Self {
+        Self {
+            products: RwLock::new(
+                products
+                    .into_iter()
+                    .map(|product| (product.product_key.clone(), product))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl ProductRepository for InMemoryProductRepository {
+    async fn save_product(&self, product: Product) -> Result<(), RuntimeError> {
+        self.products
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(product.product_key.clone(), product);
+        Ok(())
+    }
+
+    async fn list_products(&self) -> Result<Vec<Product>, RuntimeError> {
+        Ok(self
+            .products
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    async fn get_product(&self, product_key: String) -> Result<Option<Product>, RuntimeError> {
+        Ok(self
+            .products
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&product_key)
+            .cloned())
+    }
+
+    async fn delete_product(&self, product_key: String) -> Result<(), RuntimeError> {
+        self.products
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&product_key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{InMemoryProductRepository, Product, ProductRepository};
+    use tokio::test as tokio_test;
+
+    fn product(product_key: &str) -> Product {
+        Product {
+            product_key: product_key.into(),
+            display_name: product_key.to_uppercase(),
+            default_count: None,
+            default_duration_days: None,
+        }
+    }
+
+    #[tokio_test]
+    async fn save_and_get() {
+        let repository = InMemoryProductRepository::new();
+        repository.save_product(product("tides.2023")).await.unwrap();
+
+        assert_eq!(
+            Some(product("tides.2023")),
+            repository.get_product("tides.2023".into()).await.unwrap()
+        );
+        assert!(repository.get_product("weather.2022".into()).await.unwrap().is_none());
+    }
+
+    #[tokio_test]
+    async fn list_ordered() {
+        let repository = InMemoryProductRepository::from([product("weather.2022"), product("tides.2023")]);
+
+        let keys: Vec<_> = repository
+            .list_products()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|product| product.product_key)
+            .collect();
+        assert_eq!(vec!["tides.2023", "weather.2022"], keys);
+    }
+
+    #[tokio_test]
+    async fn delete() {
+        let repository = InMemoryProductRepository::from([product("tides.2023")]);
+
+        repository.delete_product("tides.2023".into()).await.unwrap();
+        repository.delete_product("tides.2023".into()).await.unwrap();
+        assert!(repository.list_products().await.unwrap().is_empty());
+    }
+}
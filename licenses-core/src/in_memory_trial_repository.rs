@@ -0,0 +1,195 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::in_memory_license_repository::InMemoryLicenseRepository;
+use crate::model::{License, TrialRecord, TrialStart};
+use crate::runtime_error::RuntimeError;
+use crate::trial_repository::TrialRepository;
+use async_trait::async_trait;
+use chrono::Utc;
+use licenses_api_types::Trial;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::sync::{Arc, PoisonError, RwLock, RwLockWriteGuard};
+use uuid::Uuid;
+
+type Trials = BTreeMap<(Uuid, Uuid, String), TrialRecord>;
+
+#[doc = "Thread-safe trials history that keeps all data in process memory, storing licenses in given repository."]
+pub struct InMemoryTrialRepository {
+    licenses: Arc<InMemoryLicenseRepository>,
+    trials: RwLock<Trials>,
+}
+
+impl InMemoryTrialRepository {
+    pub fn new(licenses: Arc<InMemoryLicenseRepository>) -> Self {
+        Self {
+            licenses,
+            trials: RwLock::default(),
+        }
+    }
+
+    // history lock is held for the whole operation, which makes history and license changes atomic
+    fn write(&self) -> RwLockWriteGuard<'_, Trials> {
+        self.trials.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[async_trait]
+impl TrialRepository for InMemoryTrialRepository {
+    async fn start_trial(&self, mut license: License, trial: Trial) -> Result<TrialStart, RuntimeError> {
+        let record = TrialRecord::of(&license, &trial);
+        license.trial = Some(trial);
+
+        let mut trials = self.write();
+        let Entry::Vacant(history) =
+            trials.entry((license.customer_id, license.vessel_id, license.license_key.clone()))
+        else {
+            return Ok(TrialStart::AlreadyUsed);
+        };
+
+        match self
+            .licenses
+            .write()
+            .entry((license.customer_id, license.vessel_id))
+            .or_default()
+            .entry(license.license_key.clone())
+        {
+            Entry::Occupied(_) => return Ok(TrialStart::LicenseExists),
+            Entry::Vacant(entry) => {
+                self.licenses.record(
+                    license.customer_id,
+                    license.vessel_id,
+                    license.license_key.clone(),
+                    Some(license.clone()),
+                );
+                entry.insert(license);
+            }
+        }
+
+        history.insert(record);
+        Ok(TrialStart::Started)
+    }
+
+    async fn convert_trial(&self, license: License) -> Result<bool, RuntimeError> {
+        let mut trials = self.write();
+        let mut licenses = self.licenses.write();
+
+        let Some(current) = licenses
+            .get_mut(&(license.customer_id, license.vessel_id))
+            .and_then(|vessel| vessel.get_mut(&license.license_key))
+            .filter(|current| current.is_trial())
+        else {
+            return Ok(false);
+        };
+
+        if let Some(record) = trials.get_mut(&(license.customer_id, license.vessel_id, license.license_key.clone())) {
+            record.converted_at = Some(Utc::now().into());
+        }
+        self.licenses.record(
+            license.customer_id,
+            license.vessel_id,
+            license.license_key.clone(),
+            Some(license.clone()),
+        );
+        *current = license;
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        InMemoryLicenseRepository, InMemoryTrialRepository, License, LicenseRepository, RuntimeError, TrialRepository,
+        TrialStart,
+    };
+    use chrono::{Duration, Utc};
+    use licenses_api_types::{Entitlements, Seats, Trial};
+    use std::sync::Arc;
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    // customers
+    static ID_0: Uuid = uuid!("00000000-0000-0000-0000-000000000000");
+    // vessels
+    static ID_1: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    // licenses
+    static LICENSE_KEY_0: &str = "tides.2023";
+
+    fn license() -> License {
+        License {
+            customer_id: ID_0,
+            vessel_id: ID_1,
+            license_key: LICENSE_KEY_0.into(),
+            count: Some(Seats::Limited(1)),
+            expires_at: Some((Utc::now() + Duration::days(30)).into()),
+            entitlements: Entitlements::new(),
+            trial: None,
+        }
+    }
+
+    fn trial() -> Trial {
+        Trial {
+            origin: "web".into(),
+            started_at: Utc::now().into(),
+        }
+    }
+
+    #[tokio_test]
+    async fn start_once() -> Result<(), RuntimeError> {
+        let licenses = Arc::new(InMemoryLicenseRepository::new());
+        let trials = InMemoryTrialRepository::new(licenses.clone());
+
+        assert_eq!(TrialStart::Started, trials.start_trial(license(), trial()).await?);
+        assert!(licenses
+            .get_license(ID_0, ID_1, LICENSE_KEY_0.into())
+            .await?
+            .unwrap()
+            .is_trial());
+
+        licenses.delete_license(ID_0, ID_1, LICENSE_KEY_0.into()).await?;
+        assert_eq!(TrialStart::AlreadyUsed, trials.start_trial(license(), trial()).await?);
+        assert!(licenses.get_license(ID_0, ID_1, LICENSE_KEY_0.into()).await?.is_none());
+
+        Ok(())
+    }
+
+    #[tokio_test]
+    async fn start_with_existing_license() -> Result<(), RuntimeError> {
+        let licenses = Arc::new(InMemoryLicenseRepository::new());
+        let trials = InMemoryTrialRepository::new(licenses.clone());
+        licenses.create_license(license()).await?;
+
+        assert_eq!(TrialStart::LicenseExists, trials.start_trial(license(), trial()).await?);
+        // trial is still available once the paid license is gone
+        licenses.delete_license(ID_0, ID_1, LICENSE_KEY_0.into()).await?;
+        assert_eq!(TrialStart::Started, trials.start_trial(license(), trial()).await?);
+
+        Ok(())
+    }
+
+    #[tokio_test]
+    async fn convert() -> Result<(), RuntimeError> {
+        let licenses = Arc::new(InMemoryLicenseRepository::new());
+        let trials = InMemoryTrialRepository::new(licenses.clone());
+        trials.start_trial(license(), trial()).await?;
+
+        let paid = License {
+            expires_at: None,
+            ..license()
+        };
+        assert!(trials.convert_trial(paid.clone()).await?);
+        assert!(!trials.convert_trial(paid).await?);
+
+        let stored = licenses.get_license(ID_0, ID_1, LICENSE_KEY_0.into()).await?.unwrap();
+        assert!(!stored.is_trial());
+        assert!(stored.expires_at.is_none());
+        assert_eq!(TrialStart::AlreadyUsed, trials.start_trial(license(), trial()).await?);
+
+        Ok(())
+    }
+}
@@ -4,31 +4,60 @@
  * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
  */
 
+use crate::api_error::ApiError;
 use env_logger::Builder;
+use futures::TryFutureExt;
 use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use licenses_api_types::ErrorResponse;
+use log::error;
 use serde::{Deserialize, Serialize};
-use std::fmt::{Debug, Display};
+use serde_json::to_string;
+use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::future::Future;
 use tracing_core::dispatcher::set_global_default;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::Registry;
 use xray::XRaySubscriber;
 
-pub async fn run_lambda<PayloadType, HandlerType, FutureType, ReturnType, ErrorType>(
-    func: HandlerType,
-) -> Result<(), Error>
+pub fn init_lambda() -> Result<(), Error> {
+    Builder::from_default_env().format_timestamp(None).init();
+
+    set_global_default(Registry::default().with(XRaySubscriber::default()).into())?;
+
+    Ok(())
+}
+
+#[derive(Debug)]
+#[doc = "Public part of handler failure - rendered by Lambda runtime as error message."]
+struct LambdaFailure(ErrorResponse);
+
+impl Display for LambdaFailure {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> FmtResult {
+        formatter.write_str(&to_string(&self.0).map_err(|_| std::fmt::Error)?)
+    }
+}
+
+impl std::error::Error for LambdaFailure {}
+
+impl From<ApiError> for LambdaFailure {
+    fn from(error: ApiError) -> Self {
+        // raw cause stays in logs only
+        error!("Request failed: {error:?}");
+
+        Self(ErrorResponse::from(&error))
+    }
+}
+
+pub async fn run_lambda<PayloadType, HandlerType, FutureType, ReturnType>(func: HandlerType) -> Result<(), Error>
 where
     PayloadType: for<'serde> Deserialize<'serde>,
     HandlerType: Fn(LambdaEvent<PayloadType>) -> FutureType,
-    FutureType: Future<Output = Result<ReturnType, ErrorType>>,
+    FutureType: Future<Output = Result<ReturnType, ApiError>>,
     ReturnType: Serialize,
-    ErrorType: Debug + Display,
 {
-    Builder::from_default_env().format_timestamp(None).init();
-
-    set_global_default(Registry::default().with(XRaySubscriber::default()).into())?;
+    init_lambda()?;
 
-    run(service_fn(func)).await
+    run(service_fn(|event| func(event).map_err(LambdaFailure::from))).await
 }
 
 #[macro_export]
@@ -37,3 +66,24 @@ macro_rules! run_lambda {
         run_lambda($handler).await
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::lambda::LambdaFailure;
+    use crate::{ApiError, RuntimeError};
+    use licenses_api_types::{ErrorCode, ErrorResponse};
+    use serde_json::from_str;
+    use std::env::VarError;
+
+    #[test]
+    fn failure_message() {
+        let failure = LambdaFailure::from(ApiError::from(RuntimeError::ClientConfigLoadingError(
+            VarError::NotPresent,
+        )));
+        let message = failure.to_string();
+        let response: ErrorResponse = from_str(&message).unwrap();
+
+        assert!(!message.contains("ClientConfigLoadingError"));
+        assert_eq!(ErrorCode::InternalError, response.code);
+    }
+}
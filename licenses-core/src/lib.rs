@@ -7,13 +7,52 @@
 #![feature(future_join)]
 
 mod api_error;
+mod history_repository;
+mod in_memory_license_repository;
+mod in_memory_pool_repository;
+mod in_memory_product_repository;
+mod in_memory_trial_repository;
 mod lambda;
 mod license_dao;
+mod license_repository;
 mod model;
+mod page_token;
+mod pool_dao;
+mod pool_repository;
+mod product_dao;
+mod product_repository;
 mod runtime_error;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod trial_dao;
+mod trial_repository;
+mod validation;
 
 pub use crate::api_error::ApiError;
-pub use crate::lambda::run_lambda;
-pub use crate::license_dao::LicenseDao;
-pub use crate::model::{DynamoResultsPage, License};
-pub use crate::runtime_error::RuntimeError;
+pub use crate::history_repository::HistoryRepository;
+pub use crate::in_memory_license_repository::InMemoryLicenseRepository;
+pub use crate::in_memory_pool_repository::InMemoryPoolRepository;
+pub use crate::in_memory_product_repository::InMemoryProductRepository;
+pub use crate::in_memory_trial_repository::InMemoryTrialRepository;
+pub use crate::lambda::{init_lambda, run_lambda};
+pub use crate::license_dao::{
+    history_entry, item_state_condition, key_of, load_client, load_client_from_env, retention_from_env, ttl_of,
+    LicenseDao, CUSTOMER_INDEX, DEFAULT_RETENTION_DAYS, NOT_POOLED_CONDITION, POOLED_ATTRIBUTE, SCHEMA_PARTITION,
+    TTL_ATTRIBUTE,
+};
+pub use crate::license_repository::{list_all, LicenseRepository};
+pub use crate::model::{
+    page_of, resolve_entitlements, DynamoResultsPage, License, LicenseVersion, LicensesFilter, LicensesSummary,
+    PageKey, Pool, Product, SeatsAllocation, SkippedItem, TrialRecord, TrialStart, VesselEntitlements,
+};
+pub use crate::page_token::{PageScope, PageTokenCodec};
+pub use crate::pool_dao::PoolDao;
+pub use crate::pool_repository::PoolRepository;
+pub use crate::product_dao::ProductDao;
+pub use crate::product_repository::ProductRepository;
+pub use crate::runtime_error::{failed_conditions, RuntimeError};
+pub use crate::trial_dao::TrialDao;
+pub use crate::trial_repository::TrialRepository;
+pub use crate::validation::{
+    FieldError, Validate, ValidationRules, DEFAULT_LICENSE_KEY_CHARACTERS, DEFAULT_LICENSE_KEY_MAX_LENGTH,
+};
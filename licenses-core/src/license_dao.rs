@@ -4,112 +4,529 @@
  * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
  */
 
-use crate::model::{DynamoResultsPage, License};
-use crate::runtime_error::RuntimeError;
+use crate::history_repository::HistoryRepository;
+use crate::license_repository::LicenseRepository;
+use crate::model::{DynamoResultsPage, License, LicenseVersion, LicensesFilter, LicensesSummary, PageKey, SkippedItem};
+use crate::runtime_error::{failed_conditions, RuntimeError};
 use std::collections::HashMap;
 
+use async_trait::async_trait;
 use aws_config::load_from_env;
-use aws_sdk_dynamodb::types::AttributeValue::S;
+use aws_sdk_dynamodb::config::Builder;
+use aws_sdk_dynamodb::operation::delete_item::DeleteItemError;
+use aws_sdk_dynamodb::operation::put_item::PutItemError;
+use aws_sdk_dynamodb::types::AttributeValue::{Bool, N, S};
+use aws_sdk_dynamodb::types::{AttributeValue, Delete, Put, Select, TransactWriteItem};
 use aws_sdk_dynamodb::Client;
+use chrono::{DateTime, Duration, FixedOffset, SecondsFormat, Utc};
+use licenses_api_types::Seats;
+use log::warn;
+use serde::Deserialize;
 use serde_dynamo::{from_item, from_items, to_item};
 use std::env::var;
 use tracing::{Instrument, Span};
 use uuid::Uuid;
 use xray::aws_metadata;
 
+pub const CUSTOMER_INDEX: &str = "customerId-licenseKey-index";
+// projects only aggregated attributes, so summary reads cost the same regardless of licenses size
+const SUMMARY_INDEX: &str = "customerId-customerAndVesselId-index";
+pub const TTL_ATTRIBUTE: &str = "ttl";
+// marks licenses created by seats allocation - only the pool may change or remove them
+pub const POOLED_ATTRIBUTE: &str = "pooled";
+pub const NOT_POOLED_CONDITION: &str = "attribute_not_exists(#pooled)";
+// DynamoDB reaps expired items within few days, until then they need to be filtered out on reads
+const TTL_FILTER: &str = "attribute_not_exists(#ttl) OR #ttl > :now";
+
+pub const DEFAULT_RETENTION_DAYS: i64 = 365;
+
+// bookkeeping entries partition (eg. schema version) - never collides with `customerId:vesselId` keys of licenses and,
+// lacking `customerId` attribute, never appears in customer index
+pub const SCHEMA_PARTITION: &str = "#schema";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LicenseState {
+    count: Option<Seats>,
+    expires_at: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryState {
+    license_key: String,
+    valid_from: DateTime<FixedOffset>,
+    #[serde(default)]
+    deleted: bool,
+}
+
 pub struct LicenseDao {
     client: Box<Client>,
     table_name: String,
+    history_table_name: Option<String>,
+    retention: Duration,
+    lenient: bool,
 }
 
+/**
+Partition key of vessel licenses.
+ */
 #[inline(always)]
-fn key_of(customer_id: &Uuid, vessel_id: &Uuid) -> String {
+pub fn key_of(customer_id: &Uuid, vessel_id: &Uuid) -> String {
     format!("{customer_id}:{vessel_id}")
 }
 
+fn page_key_of(key: &HashMap<String, AttributeValue>) -> Result<PageKey, RuntimeError> {
+    key.iter()
+        .map(|(name, value)| {
+            value
+                .as_s()
+                .map(|value| (name.clone(), value.clone()))
+                .map_err(|value| RuntimeError::DataError(value.clone(), name.clone()))
+        })
+        .collect()
+}
+
+/**
+Epoch seconds after which DynamoDB removes expired license.
+ */
+pub fn ttl_of(expires_at: &DateTime<FixedOffset>, retention: Duration) -> i64 {
+    (*expires_at + retention).timestamp()
+}
+
+/**
+Licenses table item - licenses without expiration date are never removed, others live for retention period after
+they expire.
+ */
+pub(crate) fn license_item(
+    license: License,
+    retention: Duration,
+) -> Result<HashMap<String, AttributeValue>, RuntimeError> {
+    let key = key_of(&license.customer_id, &license.vessel_id);
+    let ttl = license.expires_at.map(|expires_at| ttl_of(&expires_at, retention));
+
+    let mut item: HashMap<String, AttributeValue> = to_item(license)?;
+    item.insert("customerAndVesselId".into(), S(key));
+    if let Some(ttl) = ttl {
+        item.insert(TTL_ATTRIBUTE.into(), N(ttl.to_string()));
+    }
+    Ok(item)
+}
+
+/**
+Retention period of expired licenses, shared by all DAOs writing licenses table.
+ */
+pub fn retention_from_env() -> Result<Duration, RuntimeError> {
+    let days = match var("LICENSE_RETENTION_DAYS") {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| RuntimeError::InvalidConfigError(format!("LICENSE_RETENTION_DAYS={value}")))?,
+        Err(_) => DEFAULT_RETENTION_DAYS,
+    };
+    Ok(Duration::days(days))
+}
+
+/**
+History entry storing new state of the license - `None` marks deletion. Entries are sorted by `licenseVersion` key,
+composed of license key and the moment of change, and are never removed.
+ */
+pub fn history_entry(
+    table_name: &str,
+    customer_id: &Uuid,
+    vessel_id: &Uuid,
+    license_key: &str,
+    license: Option<License>,
+) -> Result<TransactWriteItem, RuntimeError> {
+    let valid_from = Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true);
+
+    let mut item: HashMap<String, AttributeValue> = match license {
+        Some(license) => to_item(license)?,
+        None => HashMap::from([
+            ("customerId".into(), S(customer_id.to_string())),
+            ("vesselId".into(), S(vessel_id.to_string())),
+            ("licenseKey".into(), S(license_key.into())),
+            ("deleted".into(), Bool(true)),
+        ]),
+    };
+    item.insert("customerAndVesselId".into(), S(key_of(customer_id, vessel_id)));
+    item.insert("licenseVersion".into(), S(format!("{license_key}#{valid_from}")));
+    item.insert("validFrom".into(), S(valid_from));
+
+    Ok(TransactWriteItem::builder()
+        .put(Put::builder().table_name(table_name).set_item(Some(item)).build())
+        .build())
+}
+
+/**
+Condition that holds only while the item is still in the given (previously read) state - all its attributes keep their
+values and `absent` ones still don't exist. Protects raw rewrites of scanned items from overwriting concurrent changes.
+ */
+pub fn item_state_condition(
+    item: &HashMap<String, AttributeValue>,
+    absent: &[&String],
+) -> (String, HashMap<String, String>, HashMap<String, AttributeValue>) {
+    let mut conditions = vec![];
+    let mut names = HashMap::new();
+    let mut values = HashMap::new();
+
+    for (index, (name, value)) in item.iter().enumerate() {
+        conditions.push(format!("#s{index} = :s{index}"));
+        names.insert(format!("#s{index}"), name.clone());
+        values.insert(format!(":s{index}"), value.clone());
+    }
+    for (index, name) in absent.iter().enumerate() {
+        conditions.push(format!("attribute_not_exists(#a{index})"));
+        names.insert(format!("#a{index}"), name.to_string());
+    }
+
+    (conditions.join(" AND "), names, values)
+}
+
+fn is_reaped(item: &HashMap<String, AttributeValue>, now: i64) -> bool {
+    item.get(TTL_ATTRIBUTE)
+        .and_then(|value| value.as_n().ok())
+        .and_then(|value| value.parse::<i64>().ok())
+        .map_or(false, |ttl| ttl <= now)
+}
+
+/**
+Optional environment variables:
+<dl>
+    <dt><code>DYNAMODB_ENDPOINT_URL</code></dt>
+    <dd>Overrides DynamoDB endpoint, eg. to point to DynamoDB Local.</dd>
+</dl>
+ */
+pub async fn load_client_from_env() -> Client {
+    load_client(var("DYNAMODB_ENDPOINT_URL").ok()).await
+}
+
+/**
+Client for AWS SDK resolved configuration, optionally pointing to custom DynamoDB endpoint.
+ */
+pub async fn load_client(endpoint_url: Option<String>) -> Client {
+    let config = &load_from_env().await;
+
+    match endpoint_url {
+        Some(endpoint_url) => Client::from_conf(Builder::from(config).endpoint_url(endpoint_url).build()),
+        None => Client::new(config),
+    }
+}
+
 /**
 Required environment variables:
 <dl>
     <dt><code>LICENSES_TABLE</code></dt>
     <dd>Name of DynamoDB licenses table.</dd>
 </dl>
+
+Optional environment variables:
+<dl>
+    <dt><code>LICENSE_RETENTION_DAYS</code></dt>
+    <dd>How long expired licenses are kept before DynamoDB removes them, by default <code>365</code> days.</dd>
+    <dt><code>LICENSE_HISTORY_TABLE</code></dt>
+    <dd>Name of DynamoDB license history table - without it changes are not recorded.</dd>
+</dl>
  */
 impl LicenseDao {
     pub async fn load_from_env() -> Result<Self, RuntimeError> {
-        let config = &load_from_env().await;
+        let client = load_client_from_env().await;
+        let retention = retention_from_env()?;
 
-        var("LICENSES_TABLE")
-            .map(|table_name| {
-                let client = Client::new(config);
-                Self::new(client, table_name)
-            })
-            .map_err(RuntimeError::ClientConfigLoadingError)
+        let dao = var("LICENSES_TABLE")
+            .map(|table_name| Self::new(client, table_name).with_retention(retention))
+            .map_err(RuntimeError::ClientConfigLoadingError)?;
+
+        Ok(match var("LICENSE_HISTORY_TABLE") {
+            Ok(history_table_name) => dao.with_history(history_table_name),
+            Err(_) => dao,
+        })
     }
 
     pub fn new(client: Client, table_name: String) -> Self {
         Self {
             client: Box::new(client),
             table_name,
+            history_table_name: None,
+            retention: Duration::days(DEFAULT_RETENTION_DAYS),
+            lenient: true,
         }
     }
 
-    pub async fn create_license(&self, license: License) -> Result<(), RuntimeError> {
-        let key = key_of(&license.customer_id, &license.vessel_id);
+    pub fn with_retention(self, retention: Duration) -> Self {
+        Self { retention, ..self }
+    }
 
-        self.client
-            .put_item()
+    /**
+    In lenient mode (default) listing reads items one by one - corrupted entries are reported as skipped in the
+    results page, instead of failing the whole page, so single corrupted entry doesn't hide all the other licenses of
+    the vessel. Strict mode fails on the first corrupted entry.
+     */
+    pub fn with_lenient(self, lenient: bool) -> Self {
+        Self { lenient, ..self }
+    }
+
+    /**
+    With history table every license change is recorded there, in the same transaction as the change itself.
+     */
+    pub fn with_history(self, history_table_name: String) -> Self {
+        Self {
+            history_table_name: Some(history_table_name),
+            ..self
+        }
+    }
+
+    fn history_entry_of(
+        &self,
+        customer_id: &Uuid,
+        vessel_id: &Uuid,
+        license_key: &str,
+        license: Option<License>,
+    ) -> Result<Option<TransactWriteItem>, RuntimeError> {
+        self.history_table_name
+            .as_deref()
+            .map(|table_name| history_entry(table_name, customer_id, vessel_id, license_key, license))
+            .transpose()
+    }
+
+    /**
+    Executes license write together with its history entry - `false` if the write condition failed.
+     */
+    async fn write_with_history(
+        &self,
+        write: TransactWriteItem,
+        entry: TransactWriteItem,
+    ) -> Result<bool, RuntimeError> {
+        match self
+            .client
+            .transact_write_items()
+            .transact_items(write)
+            .transact_items(entry)
+            .send()
+            .instrument(self.instrumentation())
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(error) => match failed_conditions(&error).as_deref() {
+                Some([true, ..]) => Ok(false),
+                _ => Err(error.into()),
+            },
+        }
+    }
+
+    async fn is_pooled(&self, customer_id: &Uuid, vessel_id: &Uuid, license_key: String) -> Result<bool, RuntimeError> {
+        Ok(self
+            .client
+            .get_item()
             .table_name(self.table_name.as_str())
-            .set_item(Some(to_item(license)?))
-            .item("customerAndVesselId", S(key))
+            .key("customerAndVesselId", S(key_of(customer_id, vessel_id)))
+            .key("licenseKey", S(license_key))
+            .projection_expression("#pooled")
+            .expression_attribute_names("#pooled", POOLED_ATTRIBUTE)
+            .consistent_read(true)
             .send()
             .instrument(self.instrumentation())
-            .await?;
-        Ok(())
+            .await?
+            .item
+            .and_then(|item| {
+                item.get(POOLED_ATTRIBUTE)
+                    .and_then(|value| value.as_bool().ok())
+                    .copied()
+            })
+            .unwrap_or(false))
     }
 
-    pub async fn list_licenses(
+    fn licenses_of(
         &self,
-        customer_id: Uuid,
-        vessel_id: Uuid,
-        page_token: Option<String>,
-    ) -> Result<DynamoResultsPage<License, String>, RuntimeError> {
-        let key = key_of(&customer_id, &vessel_id);
+        items: Vec<HashMap<String, AttributeValue>>,
+        skipped: &mut Vec<SkippedItem>,
+    ) -> Result<Vec<License>, RuntimeError> {
+        if !self.lenient {
+            return Ok(from_items(items)?);
+        }
+
+        Ok(items
+            .into_iter()
+            .filter_map(|item| {
+                let license_key = item.get("licenseKey").and_then(|value| value.as_s().ok()).cloned();
+
+                from_item(item)
+                    .map_err(|error| {
+                        let license_key = license_key.unwrap_or_default();
+                        warn!("Skipping corrupted license {license_key}: {error}");
+                        skipped.push(SkippedItem {
+                            license_key,
+                            reason: error.to_string(),
+                        });
+                    })
+                    .ok()
+            })
+            .collect())
+    }
+
+    fn instrumentation(&self) -> Span {
+        aws_metadata(
+            self.client.conf().region().map(|value| value.to_string()).as_deref(),
+            Some(self.table_name.as_str()),
+        )
+    }
+}
+
+#[async_trait]
+impl LicenseRepository for LicenseDao {
+    async fn create_license(&self, license: License) -> Result<bool, RuntimeError> {
+        let entry = self.history_entry_of(
+            &license.customer_id,
+            &license.vessel_id,
+            &license.license_key,
+            Some(license.clone()),
+        )?;
+        if let Some(entry) = entry {
+            let write = Put::builder()
+                .table_name(self.table_name.as_str())
+                .set_item(Some(license_item(license, self.retention)?))
+                .condition_expression(NOT_POOLED_CONDITION)
+                .expression_attribute_names("#pooled", POOLED_ATTRIBUTE)
+                .build();
+            return self
+                .write_with_history(TransactWriteItem::builder().put(write).build(), entry)
+                .await;
+        }
 
-        let results = self
+        match self
             .client
-            .query()
+            .put_item()
             .table_name(self.table_name.as_str())
-            .key_condition_expression("customerAndVesselId = :customerAndVesselId")
-            .expression_attribute_values(":customerAndVesselId", S(key.clone()))
-            .set_exclusive_start_key(page_token.map(|license_key| {
-                HashMap::from([
-                    ("customerAndVesselId".into(), S(key)),
-                    ("licenseKey".into(), S(license_key)),
-                ])
-            }))
+            .set_item(Some(license_item(license, self.retention)?))
+            .condition_expression(NOT_POOLED_CONDITION)
+            .expression_attribute_names("#pooled", POOLED_ATTRIBUTE)
             .send()
             .instrument(self.instrumentation())
-            .await?;
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(error)
+                if error
+                    .as_service_error()
+                    .map_or(false, PutItemError::is_conditional_check_failed_exception) =>
+            {
+                Ok(false)
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
 
-        Ok(DynamoResultsPage {
-            last_evaluated_key: results
-                .last_evaluated_key()
-                .and_then(|key| key["licenseKey"].as_s().ok())
-                .cloned(),
-            items: if let Some(items) = results.items {
-                from_items(items)?
-            } else {
-                vec![]
-            },
-        })
+    async fn insert_license(&self, license: License) -> Result<bool, RuntimeError> {
+        let entry = self.history_entry_of(
+            &license.customer_id,
+            &license.vessel_id,
+            &license.license_key,
+            Some(license.clone()),
+        )?;
+        if let Some(entry) = entry {
+            let write = Put::builder()
+                .table_name(self.table_name.as_str())
+                .set_item(Some(license_item(license, self.retention)?))
+                .condition_expression("attribute_not_exists(licenseKey)")
+                .build();
+            return self
+                .write_with_history(TransactWriteItem::builder().put(write).build(), entry)
+                .await;
+        }
+
+        match self
+            .client
+            .put_item()
+            .table_name(self.table_name.as_str())
+            .set_item(Some(license_item(license, self.retention)?))
+            .condition_expression("attribute_not_exists(licenseKey)")
+            .send()
+            .instrument(self.instrumentation())
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(error)
+                if error
+                    .as_service_error()
+                    .map_or(false, PutItemError::is_conditional_check_failed_exception) =>
+            {
+                Ok(false)
+            }
+            Err(error) => Err(error.into()),
+        }
     }
 
-    pub async fn get_license(
+    /**
+    When `limit` is given, subsequent queries are issued until the page is filled.
+     */
+    async fn list_licenses(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        filter: &LicensesFilter,
+        limit: Option<u16>,
+        page_key: Option<PageKey>,
+    ) -> Result<DynamoResultsPage<License, PageKey>, RuntimeError> {
+        let now = Utc::now();
+        let mut values = HashMap::from([
+            (":customerAndVesselId".into(), S(key_of(&customer_id, &vessel_id))),
+            (":now".into(), N(now.timestamp().to_string())),
+        ]);
+        if let Some(prefix) = &filter.license_key_prefix {
+            values.insert(":licenseKeyPrefix".into(), S(prefix.clone()));
+        }
+
+        let mut page = DynamoResultsPage {
+            items: vec![],
+            last_evaluated_key: page_key,
+            skipped: vec![],
+        };
+        loop {
+            let remaining = limit.map(|limit| limit.saturating_sub(page.items.len() as u16));
+
+            let results = self
+                .client
+                .query()
+                .table_name(self.table_name.as_str())
+                .key_condition_expression(if filter.license_key_prefix.is_some() {
+                    "customerAndVesselId = :customerAndVesselId AND begins_with(licenseKey, :licenseKeyPrefix)"
+                } else {
+                    "customerAndVesselId = :customerAndVesselId"
+                })
+                .filter_expression(TTL_FILTER)
+                .expression_attribute_names("#ttl", TTL_ATTRIBUTE)
+                .set_expression_attribute_values(Some(values.clone()))
+                .scan_index_forward(!filter.descending)
+                .set_limit(remaining.map(i32::from))
+                .set_exclusive_start_key(
+                    page.last_evaluated_key
+                        .take()
+                        .map(|page_key| page_key.into_iter().map(|(name, value)| (name, S(value))).collect()),
+                )
+                .send()
+                .instrument(self.instrumentation())
+                .await?;
+
+            page.last_evaluated_key = results.last_evaluated_key().map(page_key_of).transpose()?;
+            if let Some(items) = results.items {
+                let licenses = self.licenses_of(items, &mut page.skipped)?;
+                page.items
+                    .extend(licenses.into_iter().filter(|license| filter.matches(license, &now)));
+            }
+
+            if page.last_evaluated_key.is_none() || limit.map_or(true, |limit| page.items.len() >= limit.into()) {
+                return Ok(page);
+            }
+        }
+    }
+
+    async fn get_license(
         &self,
         customer_id: Uuid,
         vessel_id: Uuid,
         license_key: String,
     ) -> Result<Option<License>, RuntimeError> {
+        let now = Utc::now().timestamp();
+
         self.client
             .get_item()
             .table_name(self.table_name.as_str())
@@ -119,52 +536,202 @@ impl LicenseDao {
             .instrument(self.instrumentation())
             .await?
             .item
+            .filter(|item| !is_reaped(item, now))
             .map(from_item::<_, License>)
             .map_or(Ok(None), |license| license.map(Some))
             .map_err(RuntimeError::from)
     }
 
-    pub async fn delete_license(
+    async fn delete_license(
         &self,
         customer_id: Uuid,
         vessel_id: Uuid,
         license_key: String,
-    ) -> Result<(), RuntimeError> {
-        self.client
+    ) -> Result<bool, RuntimeError> {
+        // tombstone is recorded only if the license actually existed
+        if let Some(entry) = self.history_entry_of(&customer_id, &vessel_id, &license_key, None)? {
+            let write = Delete::builder()
+                .table_name(self.table_name.as_str())
+                .key("customerAndVesselId", S(key_of(&customer_id, &vessel_id)))
+                .key("licenseKey", S(license_key.clone()))
+                .condition_expression(format!("attribute_exists(licenseKey) AND {NOT_POOLED_CONDITION}"))
+                .expression_attribute_names("#pooled", POOLED_ATTRIBUTE)
+                .build();
+            if self
+                .write_with_history(TransactWriteItem::builder().delete(write).build(), entry)
+                .await?
+            {
+                return Ok(true);
+            }
+
+            // failed condition doesn't tell whether the license was missing or pooled
+            return Ok(!self.is_pooled(&customer_id, &vessel_id, license_key).await?);
+        }
+
+        match self
+            .client
             .delete_item()
             .table_name(self.table_name.as_str())
             .key("customerAndVesselId", S(key_of(&customer_id, &vessel_id)))
             .key("licenseKey", S(license_key))
+            .condition_expression(NOT_POOLED_CONDITION)
+            .expression_attribute_names("#pooled", POOLED_ATTRIBUTE)
             .send()
             .instrument(self.instrumentation())
-            .await?;
-        Ok(())
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(error)
+                if error
+                    .as_service_error()
+                    .map_or(false, DeleteItemError::is_conditional_check_failed_exception) =>
+            {
+                Ok(false)
+            }
+            Err(error) => Err(error.into()),
+        }
     }
 
-    fn instrumentation(&self) -> Span {
-        aws_metadata(
-            self.client.conf().region().map(|value| value.to_string()).as_deref(),
-            Some(self.table_name.as_str()),
-        )
+    /**
+    States and seats need attribute values, so instead of counting items with `Select::Count` the queries read summary
+    index, which holds just the attributes needed for aggregation (and TTL filtering).
+     */
+    async fn summarize(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Option<Uuid>,
+        expiring_within: Duration,
+    ) -> Result<LicensesSummary, RuntimeError> {
+        let now = Utc::now();
+        let horizon = now + expiring_within;
+
+        let mut values = HashMap::from([
+            (":customerId".into(), S(customer_id.to_string())),
+            (":now".into(), N(now.timestamp().to_string())),
+        ]);
+        if let Some(vessel_id) = vessel_id {
+            values.insert(":customerAndVesselId".into(), S(key_of(&customer_id, &vessel_id)));
+        }
+
+        let mut summary = LicensesSummary::default();
+        let mut page_key = None;
+        loop {
+            let results = self
+                .client
+                .query()
+                .table_name(self.table_name.as_str())
+                .index_name(SUMMARY_INDEX)
+                .key_condition_expression(if vessel_id.is_some() {
+                    "customerId = :customerId AND customerAndVesselId = :customerAndVesselId"
+                } else {
+                    "customerId = :customerId"
+                })
+                .filter_expression(TTL_FILTER)
+                .set_expression_attribute_values(Some(values.clone()))
+                .select(Select::SpecificAttributes)
+                .projection_expression("expiresAt, #count")
+                .expression_attribute_names("#count", "count")
+                .expression_attribute_names("#ttl", TTL_ATTRIBUTE)
+                .set_exclusive_start_key(page_key)
+                .send()
+                .instrument(self.instrumentation())
+                .await?;
+
+            if let Some(items) = results.items {
+                for state in from_items::<_, LicenseState>(items)? {
+                    summary.add(state.count, state.expires_at, &now, &horizon);
+                }
+            }
+
+            page_key = results.last_evaluated_key;
+            if page_key.is_none() {
+                return Ok(summary);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl HistoryRepository for LicenseDao {
+    async fn license_history(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        license_key: Option<String>,
+    ) -> Result<Vec<LicenseVersion>, RuntimeError> {
+        let history_table_name = self
+            .history_table_name
+            .as_deref()
+            .ok_or_else(|| RuntimeError::InvalidConfigError("LICENSE_HISTORY_TABLE".into()))?;
+
+        let mut values = HashMap::from([(":customerAndVesselId".into(), S(key_of(&customer_id, &vessel_id)))]);
+        if let Some(license_key) = &license_key {
+            values.insert(":licenseKeyPrefix".into(), S(format!("{license_key}#")));
+        }
+
+        let mut states = Vec::new();
+        let mut page_key = None;
+        loop {
+            let results = self
+                .client
+                .query()
+                .table_name(history_table_name)
+                .key_condition_expression(if license_key.is_some() {
+                    "customerAndVesselId = :customerAndVesselId AND begins_with(licenseVersion, :licenseKeyPrefix)"
+                } else {
+                    "customerAndVesselId = :customerAndVesselId"
+                })
+                .set_expression_attribute_values(Some(values.clone()))
+                .set_exclusive_start_key(page_key)
+                .send()
+                .instrument(self.instrumentation())
+                .await?;
+
+            for item in results.items.unwrap_or_default() {
+                let state: HistoryState = from_item(item.clone())?;
+                let license = if state.deleted {
+                    None
+                } else {
+                    Some(from_item::<_, License>(item)?)
+                };
+                states.push((state.license_key, state.valid_from.with_timezone(&Utc), license));
+            }
+
+            page_key = results.last_evaluated_key;
+            if page_key.is_none() {
+                break;
+            }
+        }
+
+        states.sort_by(|(left_key, left_time, _), (right_key, right_time, _)| {
+            (left_key, left_time).cmp(&(right_key, right_time))
+        });
+        Ok(LicenseVersion::from_states(states))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::license_dao::key_of;
-    use crate::{License, LicenseDao, RuntimeError};
+    use crate::license_dao::{CUSTOMER_INDEX, DEFAULT_RETENTION_DAYS, SUMMARY_INDEX};
+    use crate::{
+        HistoryRepository, License, LicenseDao, LicenseRepository, LicensesFilter, LicensesSummary, PageKey,
+        RuntimeError,
+    };
     use async_trait::async_trait;
     use aws_config::load_from_env;
     use aws_sdk_dynamodb::config::Builder;
     use aws_sdk_dynamodb::operation::put_item::{PutItemError, PutItemOutput};
     use aws_sdk_dynamodb::types::{
         AttributeDefinition,
-        AttributeValue::{L, M, N, S},
-        KeySchemaElement, KeyType, ProvisionedThroughput, ScalarAttributeType,
+        AttributeValue::{Bool, L, M, N, S},
+        GlobalSecondaryIndex, KeySchemaElement, KeyType, Projection, ProjectionType, ProvisionedThroughput,
+        ScalarAttributeType,
     };
     use aws_sdk_dynamodb::Client;
     use aws_smithy_http::result::SdkError;
-    use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+    use chrono::{DateTime, Duration, FixedOffset, TimeZone, Utc};
+    use licenses_api_types::{Entitlement, Entitlements, Seats};
     use std::collections::HashMap;
     use std::future::join;
     use std::sync::atomic::{AtomicUsize, Ordering};
@@ -176,6 +743,7 @@ mod tests {
         client: Box<Client>,
         dao: Box<LicenseDao>,
         table_name: String,
+        history_table_name: String,
     }
 
     static NUMBER: AtomicUsize = AtomicUsize::new(0);
@@ -194,7 +762,9 @@ mod tests {
     #[async_trait]
     impl AsyncTestContext for DynamoDbTestContext {
         async fn setup() -> DynamoDbTestContext {
-            let table_name = format!("Licenses{}", NUMBER.fetch_add(1, Ordering::SeqCst));
+            let number = NUMBER.fetch_add(1, Ordering::SeqCst);
+            let table_name = format!("Licenses{number}");
+            let history_table_name = format!("LicenseHistory{number}");
             let config = load_from_env().await;
             let local_config = Builder::from(&config).endpoint_url("http://localhost:8000").build();
             let client = Client::from_conf(local_config);
@@ -214,6 +784,12 @@ mod tests {
                         .attribute_type(ScalarAttributeType::S)
                         .build(),
                 )
+                .attribute_definitions(
+                    AttributeDefinition::builder()
+                        .attribute_name("customerId")
+                        .attribute_type(ScalarAttributeType::S)
+                        .build(),
+                )
                 .key_schema(
                     KeySchemaElement::builder()
                         .attribute_name("customerAndVesselId")
@@ -226,6 +802,98 @@ mod tests {
                         .key_type(KeyType::Range)
                         .build(),
                 )
+                .global_secondary_indexes(
+                    GlobalSecondaryIndex::builder()
+                        .index_name(CUSTOMER_INDEX)
+                        .key_schema(
+                            KeySchemaElement::builder()
+                                .attribute_name("customerId")
+                                .key_type(KeyType::Hash)
+                                .build(),
+                        )
+                        .key_schema(
+                            KeySchemaElement::builder()
+                                .attribute_name("licenseKey")
+                                .key_type(KeyType::Range)
+                                .build(),
+                        )
+                        .projection(Projection::builder().projection_type(ProjectionType::All).build())
+                        .provisioned_throughput(
+                            ProvisionedThroughput::builder()
+                                .read_capacity_units(1000)
+                                .write_capacity_units(1000)
+                                .build(),
+                        )
+                        .build(),
+                )
+                .global_secondary_indexes(
+                    GlobalSecondaryIndex::builder()
+                        .index_name(SUMMARY_INDEX)
+                        .key_schema(
+                            KeySchemaElement::builder()
+                                .attribute_name("customerId")
+                                .key_type(KeyType::Hash)
+                                .build(),
+                        )
+                        .key_schema(
+                            KeySchemaElement::builder()
+                                .attribute_name("customerAndVesselId")
+                                .key_type(KeyType::Range)
+                                .build(),
+                        )
+                        .projection(
+                            Projection::builder()
+                                .projection_type(ProjectionType::Include)
+                                .non_key_attributes("expiresAt")
+                                .non_key_attributes("count")
+                                .non_key_attributes("ttl")
+                                .build(),
+                        )
+                        .provisioned_throughput(
+                            ProvisionedThroughput::builder()
+                                .read_capacity_units(1000)
+                                .write_capacity_units(1000)
+                                .build(),
+                        )
+                        .build(),
+                )
+                .provisioned_throughput(
+                    ProvisionedThroughput::builder()
+                        .read_capacity_units(1000)
+                        .write_capacity_units(1000)
+                        .build(),
+                )
+                .send()
+                .await
+                .unwrap();
+
+            client
+                .create_table()
+                .table_name(history_table_name.as_str())
+                .attribute_definitions(
+                    AttributeDefinition::builder()
+                        .attribute_name("customerAndVesselId")
+                        .attribute_type(ScalarAttributeType::S)
+                        .build(),
+                )
+                .attribute_definitions(
+                    AttributeDefinition::builder()
+                        .attribute_name("licenseVersion")
+                        .attribute_type(ScalarAttributeType::S)
+                        .build(),
+                )
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("customerAndVesselId")
+                        .key_type(KeyType::Hash)
+                        .build(),
+                )
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("licenseVersion")
+                        .key_type(KeyType::Range)
+                        .build(),
+                )
                 .provisioned_throughput(
                     ProvisionedThroughput::builder()
                         .read_capacity_units(1000)
@@ -238,8 +906,9 @@ mod tests {
 
             let context = DynamoDbTestContext {
                 client: Box::new(client.clone()),
-                dao: Box::new(LicenseDao::new(client, table_name.clone())),
+                dao: Box::new(LicenseDao::new(client, table_name.clone()).with_history(history_table_name.clone())),
                 table_name: table_name.clone(),
+                history_table_name,
             };
 
             let (res1, res2, res3) = join!(
@@ -263,6 +932,12 @@ mod tests {
                 .send()
                 .await
                 .unwrap();
+            self.client
+                .delete_table()
+                .table_name(self.history_table_name)
+                .send()
+                .await
+                .unwrap();
         }
     }
 
@@ -273,6 +948,11 @@ mod tests {
             .with_ymd_and_hms(2015, 7, 2, 1, 20, 0)
             .unwrap()
             .with_timezone(&FixedOffset::east_opt(7200).unwrap());
+        let entitlements = Entitlements::from([
+            ("routing".to_string(), Entitlement::Flag(true)),
+            ("maxRoutes".to_string(), Entitlement::Limit(20)),
+            ("forecastModel".to_string(), Entitlement::Value("gfs".to_string())),
+        ]);
 
         let save = ctx
             .dao
@@ -282,6 +962,8 @@ mod tests {
                 license_key: LICENSE_KEY_1.to_string(),
                 count: None,
                 expires_at: Some(expires_at),
+                entitlements: entitlements.clone(),
+                trial: None,
             })
             .await;
         assert!(save.is_ok());
@@ -295,11 +977,85 @@ mod tests {
             .send()
             .await?;
         assert!(license.item.is_some());
+        let item = license.item.unwrap();
+        assert_eq!("2015-07-02T03:20:00+02:00", item["expiresAt"].as_s().unwrap());
+        assert_eq!("20", item["entitlements"].as_m().unwrap()["maxRoutes"].as_n().unwrap());
         assert_eq!(
-            "2015-07-02T03:20:00+02:00",
-            license.item.unwrap()["expiresAt"].as_s().unwrap()
+            (expires_at + Duration::days(DEFAULT_RETENTION_DAYS))
+                .timestamp()
+                .to_string(),
+            *item["ttl"].as_n().unwrap()
         );
 
+        let stored = ctx
+            .dao
+            .get_license(ID_0, ID_2, LICENSE_KEY_1.to_string())
+            .await?
+            .unwrap();
+        assert_eq!(entitlements, stored.entitlements);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn insert_license(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let license = || License {
+            customer_id: ID_0,
+            vessel_id: ID_1,
+            license_key: LICENSE_KEY_0.to_string(),
+            count: Some(Seats::Limited(5)),
+            expires_at: None,
+            entitlements: Entitlements::new(),
+            trial: None,
+        };
+
+        assert!(!ctx.dao.insert_license(license()).await?);
+        assert!(
+            ctx.dao
+                .insert_license(License {
+                    vessel_id: ID_3,
+                    ..license()
+                })
+                .await?
+        );
+
+        let existing = ctx
+            .dao
+            .get_license(ID_0, ID_1, LICENSE_KEY_0.to_string())
+            .await?
+            .unwrap();
+        assert_eq!(Some(Seats::Limited(2)), existing.count);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn create_unlimited_license(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        ctx.dao
+            .create_license(License {
+                customer_id: ID_0,
+                vessel_id: ID_3,
+                license_key: LICENSE_KEY_0.to_string(),
+                count: Some(Seats::Unlimited),
+                expires_at: None,
+                entitlements: Entitlements::new(),
+                trial: None,
+            })
+            .await?;
+
+        let stored = ctx
+            .dao
+            .get_license(ID_0, ID_3, LICENSE_KEY_0.to_string())
+            .await?
+            .unwrap();
+        assert_eq!(Some(Seats::Unlimited), stored.count);
+
+        let summary = ctx.dao.summarize(ID_0, Some(ID_3), Duration::days(30)).await?;
+        assert_eq!(0, summary.seats);
+        assert!(summary.unlimited_seats);
+
         Ok(())
     }
 
@@ -318,6 +1074,7 @@ mod tests {
             .unwrap();
         assert!(license.count.is_none());
         assert_eq!(Some(expires_at), license.expires_at);
+        assert!(license.entitlements.is_empty());
 
         Ok(())
     }
@@ -331,6 +1088,37 @@ mod tests {
         Ok(())
     }
 
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn get_license_past_ttl(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        ctx.client
+            .put_item()
+            .table_name(ctx.table_name.as_str())
+            .item("customerAndVesselId", S(key_of(&ID_0, &ID_1)))
+            .item("customerId", S(ID_0.to_string()))
+            .item("vesselId", S(ID_1.to_string()))
+            .item("licenseKey", S(LICENSE_KEY_2.into()))
+            .item("expiresAt", S("2011-01-30T14:58:00+01:00".into()))
+            .item("ttl", N((Utc::now() - Duration::days(1)).timestamp().to_string()))
+            .send()
+            .await?;
+
+        // not reaped by DynamoDB yet, but already hidden
+        let reaped = ctx.dao.get_license(ID_0, ID_1, LICENSE_KEY_2.to_string()).await?;
+        assert!(reaped.is_none());
+
+        let results = ctx
+            .dao
+            .list_licenses(ID_0, ID_1, &LicensesFilter::default(), None, None)
+            .await?;
+        assert_eq!(2, results.items.len());
+
+        let summary = ctx.dao.summarize(ID_0, Some(ID_1), Duration::days(30)).await?;
+        assert_eq!(2, summary.total);
+
+        Ok(())
+    }
+
     #[test_context(DynamoDbTestContext)]
     #[tokio_test]
     async fn delete_license(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
@@ -359,10 +1147,136 @@ mod tests {
         Ok(())
     }
 
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn delete_license_unexisting_history(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let dao =
+            LicenseDao::new(*ctx.client.clone(), ctx.table_name.clone()).with_history(ctx.history_table_name.clone());
+
+        // missing license is not pooled, so delete stays idempotent
+        assert!(dao.delete_license(ID_0, ID_1, LICENSE_KEY_2.to_string()).await?);
+
+        let history = ctx
+            .client
+            .query()
+            .table_name(ctx.history_table_name.as_str())
+            .key_condition_expression("customerAndVesselId = :key AND begins_with(licenseVersion, :prefix)")
+            .expression_attribute_values(":key", S(key_of(&ID_0, &ID_1)))
+            .expression_attribute_values(":prefix", S(format!("{LICENSE_KEY_2}#")))
+            .send()
+            .await?;
+        assert_eq!(0, history.count);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn pooled_license(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        ctx.client
+            .put_item()
+            .table_name(ctx.table_name.as_str())
+            .item("customerAndVesselId", S(key_of(&ID_0, &ID_3)))
+            .item("customerId", S(ID_0.to_string()))
+            .item("vesselId", S(ID_3.to_string()))
+            .item("licenseKey", S(LICENSE_KEY_0.into()))
+            .item("count", N("2".into()))
+            .item("pooled", Bool(true))
+            .send()
+            .await?;
+
+        let overwritten = ctx
+            .dao
+            .create_license(License {
+                customer_id: ID_0,
+                vessel_id: ID_3,
+                license_key: LICENSE_KEY_0.into(),
+                count: Some(Seats::Limited(10)),
+                expires_at: None,
+                entitlements: Entitlements::new(),
+                trial: None,
+            })
+            .await?;
+        assert!(!overwritten);
+        assert!(!ctx.dao.delete_license(ID_0, ID_3, LICENSE_KEY_0.into()).await?);
+
+        let license = ctx.dao.get_license(ID_0, ID_3, LICENSE_KEY_0.into()).await?.unwrap();
+        assert_eq!(Some(Seats::Limited(2)), license.count);
+        assert!(ctx.dao.license_history(ID_0, ID_3, None).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn license_history(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        for count in [2, 3] {
+            ctx.dao
+                .create_license(License {
+                    customer_id: ID_0,
+                    vessel_id: ID_3,
+                    license_key: LICENSE_KEY_0.into(),
+                    count: Some(Seats::Limited(count)),
+                    expires_at: None,
+                    entitlements: Entitlements::new(),
+                    trial: None,
+                })
+                .await?;
+        }
+        // neither failed insert, nor deleting unexisting license changes anything
+        let inserted = ctx
+            .dao
+            .insert_license(License {
+                customer_id: ID_0,
+                vessel_id: ID_3,
+                license_key: LICENSE_KEY_0.into(),
+                count: Some(Seats::Limited(5)),
+                expires_at: None,
+                entitlements: Entitlements::new(),
+                trial: None,
+            })
+            .await?;
+        assert!(!inserted);
+        ctx.dao.delete_license(ID_0, ID_3, LICENSE_KEY_0.into()).await?;
+        ctx.dao.delete_license(ID_0, ID_3, LICENSE_KEY_0.into()).await?;
+
+        let versions = ctx.dao.license_history(ID_0, ID_3, None).await?;
+        assert_eq!(3, versions.len());
+        assert_eq!(Some(versions[1].valid_from), versions[0].valid_to);
+        assert_eq!(Some(versions[2].valid_from), versions[1].valid_to);
+        assert!(versions[2].valid_to.is_none());
+        assert!(versions[2].license.is_none());
+
+        let first = ctx
+            .dao
+            .license_as_of(ID_0, ID_3, LICENSE_KEY_0.into(), versions[0].valid_from)
+            .await?;
+        assert_eq!(Some(Seats::Limited(2)), first.unwrap().count);
+        let second = ctx.dao.licenses_as_of(ID_0, ID_3, versions[1].valid_from).await?;
+        assert_eq!(1, second.len());
+        assert_eq!(Some(Seats::Limited(3)), second[0].count);
+        assert!(ctx.dao.licenses_as_of(ID_0, ID_3, Utc::now()).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn license_history_disabled(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let dao = LicenseDao::new(*ctx.client.clone(), ctx.table_name.clone());
+        let result = dao.license_history(ID_0, ID_1, None).await;
+        assert!(matches!(result, Err(RuntimeError::InvalidConfigError(_))));
+
+        Ok(())
+    }
+
     #[test_context(DynamoDbTestContext)]
     #[tokio_test]
     async fn list_licenses(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
-        let unexisting = ctx.dao.list_licenses(ID_0, ID_1, None).await;
+        let unexisting = ctx
+            .dao
+            .list_licenses(ID_0, ID_1, &LicensesFilter::default(), None, None)
+            .await;
         assert!(unexisting.is_ok());
 
         let results = unexisting.unwrap();
@@ -375,7 +1289,15 @@ mod tests {
     #[test_context(DynamoDbTestContext)]
     #[tokio_test]
     async fn list_licenses_page(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
-        let unexisting = ctx.dao.list_licenses(ID_0, ID_1, Some(LICENSE_KEY_0.to_string())).await;
+        let page_key = PageKey::from([
+            ("customerAndVesselId".into(), key_of(&ID_0, &ID_1)),
+            ("licenseKey".into(), LICENSE_KEY_0.into()),
+        ]);
+
+        let unexisting = ctx
+            .dao
+            .list_licenses(ID_0, ID_1, &LicensesFilter::default(), None, Some(page_key))
+            .await;
         assert!(unexisting.is_ok());
 
         let results = unexisting.unwrap();
@@ -388,7 +1310,10 @@ mod tests {
     #[test_context(DynamoDbTestContext)]
     #[tokio_test]
     async fn list_licenses_unexisting(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
-        let unexisting = ctx.dao.list_licenses(ID_1, ID_2, None).await;
+        let unexisting = ctx
+            .dao
+            .list_licenses(ID_1, ID_2, &LicensesFilter::default(), None, None)
+            .await;
         assert!(unexisting.is_ok());
 
         let results = unexisting.unwrap();
@@ -398,6 +1323,208 @@ mod tests {
         Ok(())
     }
 
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn list_licenses_limit(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let results = ctx
+            .dao
+            .list_licenses(ID_0, ID_1, &LicensesFilter::default(), Some(1), None)
+            .await?;
+        assert_eq!(1, results.items.len());
+        assert_eq!(LICENSE_KEY_0, results.items[0].license_key);
+        assert!(results.last_evaluated_key.is_some());
+
+        let results = ctx
+            .dao
+            .list_licenses(
+                ID_0,
+                ID_1,
+                &LicensesFilter::default(),
+                Some(1),
+                results.last_evaluated_key,
+            )
+            .await?;
+        assert_eq!(1, results.items.len());
+        assert_eq!(LICENSE_KEY_1, results.items[0].license_key);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn list_licenses_prefix(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let filter = LicensesFilter {
+            license_key_prefix: Some("weather.".into()),
+            ..LicensesFilter::default()
+        };
+
+        let results = ctx.dao.list_licenses(ID_0, ID_1, &filter, None, None).await?;
+        assert_eq!(1, results.items.len());
+        assert_eq!(LICENSE_KEY_1, results.items[0].license_key);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn list_licenses_descending(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let filter = LicensesFilter {
+            descending: true,
+            ..LicensesFilter::default()
+        };
+
+        let results = ctx.dao.list_licenses(ID_0, ID_1, &filter, None, None).await?;
+        assert_eq!(2, results.items.len());
+        assert_eq!(LICENSE_KEY_1, results.items[0].license_key);
+        assert_eq!(LICENSE_KEY_0, results.items[1].license_key);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn list_licenses_expired_only(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let filter = LicensesFilter {
+            expired_only: true,
+            ..LicensesFilter::default()
+        };
+
+        let results = ctx.dao.list_licenses(ID_0, ID_1, &filter, None, None).await?;
+        assert_eq!(1, results.items.len());
+        assert_eq!(LICENSE_KEY_1, results.items[0].license_key);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn list_licenses_active_only_filled(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let filter = LicensesFilter {
+            active_only: true,
+            descending: true,
+            ..LicensesFilter::default()
+        };
+
+        // first queried item is expired, so DAO needs to fetch next one to fill the page
+        let results = ctx.dao.list_licenses(ID_0, ID_1, &filter, Some(1), None).await?;
+        assert_eq!(1, results.items.len());
+        assert_eq!(LICENSE_KEY_0, results.items[0].license_key);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn list_licenses_lenient(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        ctx.client
+            .put_item()
+            .table_name(ctx.table_name.as_str())
+            .item("customerAndVesselId", S(key_of(&ID_0, &ID_1)))
+            .item("customerId", S(ID_0.to_string()))
+            .item("vesselId", S(ID_1.to_string()))
+            .item("licenseKey", S(LICENSE_KEY_2.into()))
+            .item("expiresAt", S("01.07.2023".into()))
+            .send()
+            .await?;
+
+        let strict = LicenseDao::new(*ctx.client.clone(), ctx.table_name.clone())
+            .with_lenient(false)
+            .list_licenses(ID_0, ID_1, &LicensesFilter::default(), None, None)
+            .await;
+        assert!(strict.is_err());
+
+        let results = ctx
+            .dao
+            .list_licenses(ID_0, ID_1, &LicensesFilter::default(), None, None)
+            .await?;
+        assert_eq!(2, results.items.len());
+        assert_eq!(1, results.skipped.len());
+        assert_eq!(LICENSE_KEY_2, results.skipped[0].license_key);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn summarize_vessel(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let summary = ctx.dao.summarize(ID_0, Some(ID_1), Duration::days(30)).await?;
+        assert_eq!(
+            LicensesSummary {
+                total: 2,
+                active: 1,
+                expired: 1,
+                expiring_soon: 0,
+                seats: 2,
+                unlimited_seats: false,
+            },
+            summary
+        );
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn summarize_customer(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let summary = ctx.dao.summarize(ID_0, None, Duration::days(30)).await?;
+        assert_eq!(3, summary.total);
+        assert_eq!(2, summary.active);
+        assert_eq!(1, summary.expired);
+        assert_eq!(4, summary.seats);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn summary_index_projection(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        ctx.dao
+            .create_license(License {
+                customer_id: ID_0,
+                vessel_id: ID_3,
+                license_key: LICENSE_KEY_0.to_string(),
+                count: Some(Seats::Limited(1)),
+                expires_at: None,
+                entitlements: Entitlements::from([("routing".to_string(), Entitlement::Flag(true))]),
+                trial: None,
+            })
+            .await?;
+
+        let items = ctx
+            .client
+            .query()
+            .table_name(ctx.table_name.as_str())
+            .index_name(SUMMARY_INDEX)
+            .key_condition_expression("customerId = :customerId AND customerAndVesselId = :customerAndVesselId")
+            .expression_attribute_values(":customerId", S(ID_0.to_string()))
+            .expression_attribute_values(":customerAndVesselId", S(key_of(&ID_0, &ID_3)))
+            .send()
+            .await?
+            .items
+            .unwrap_or_default();
+        assert_eq!(1, items.len());
+        assert!(items[0].contains_key("count"));
+        assert!(!items[0].contains_key("entitlements"));
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn summarize_unexisting(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let summary = ctx.dao.summarize(ID_3, None, Duration::days(30)).await?;
+        assert_eq!(LicensesSummary::default(), summary);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "testing")]
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn conformance(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        crate::testing::check_all(ctx.dao.as_ref()).await
+    }
+
     impl DynamoDbTestContext {
         async fn create_record(
             &self,
@@ -0,0 +1,141 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::model::{DynamoResultsPage, License, LicensesFilter, LicensesSummary, PageKey};
+use crate::runtime_error::RuntimeError;
+use async_trait::async_trait;
+use chrono::Duration;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait LicenseRepository: Send + Sync {
+    /**
+    Creates or overwrites license.
+
+    Returns `false` when license is allocated from a pool - such licenses are changed only through the pool.
+     */
+    async fn create_license(&self, license: License) -> Result<bool, RuntimeError>;
+
+    /**
+    Creates license only if there is no license with the same key yet.
+
+    Returns `false` when license already existed and nothing was changed.
+     */
+    async fn insert_license(&self, license: License) -> Result<bool, RuntimeError>;
+
+    /**
+    Lists licenses of a vessel.
+
+    When `limit` is given, page is filled with licenses matching the filter until the limit is reached or there are
+    no more items.
+     */
+    async fn list_licenses(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        filter: &LicensesFilter,
+        limit: Option<u16>,
+        page_key: Option<PageKey>,
+    ) -> Result<DynamoResultsPage<License, PageKey>, RuntimeError>;
+
+    async fn get_license(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        license_key: String,
+    ) -> Result<Option<License>, RuntimeError>;
+
+    /**
+    Deletes license, if it exists.
+
+    Returns `false` when license is allocated from a pool - such licenses are removed only by releasing the seats.
+     */
+    async fn delete_license(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Uuid,
+        license_key: String,
+    ) -> Result<bool, RuntimeError>;
+
+    /**
+    Aggregates licenses of a vessel, or of all customer vessels when `vessel_id` is not specified.
+     */
+    async fn summarize(
+        &self,
+        customer_id: Uuid,
+        vessel_id: Option<Uuid>,
+        expiring_within: Duration,
+    ) -> Result<LicensesSummary, RuntimeError>;
+}
+
+/**
+Fetches all licenses of a vessel matching the filter, following pages of `page_size` items until the last one.
+ */
+pub async fn list_all<R: LicenseRepository + ?Sized>(
+    repository: &R,
+    customer_id: Uuid,
+    vessel_id: Uuid,
+    filter: &LicensesFilter,
+    page_size: Option<u16>,
+) -> Result<Vec<License>, RuntimeError> {
+    let mut licenses = vec![];
+    let mut page_key = None;
+
+    loop {
+        let page = repository
+            .list_licenses(customer_id, vessel_id, filter, page_size, page_key)
+            .await?;
+        licenses.extend(page.items);
+
+        page_key = page.last_evaluated_key;
+        if page_key.is_none() {
+            return Ok(licenses);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::license_repository::list_all;
+    use crate::{InMemoryLicenseRepository, License, LicenseRepository, LicensesFilter, RuntimeError};
+    use licenses_api_types::Entitlements;
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    static CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000000");
+    static VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+
+    #[tokio_test]
+    async fn list_all_pages() -> Result<(), RuntimeError> {
+        let repository = InMemoryLicenseRepository::new();
+        for license_key in ["a", "b", "c"] {
+            repository
+                .create_license(License {
+                    customer_id: CUSTOMER_ID,
+                    vessel_id: VESSEL_ID,
+                    license_key: license_key.into(),
+                    count: None,
+                    expires_at: None,
+                    entitlements: Entitlements::new(),
+                    trial: None,
+                })
+                .await?;
+        }
+
+        let filter = LicensesFilter {
+            descending: true,
+            ..LicensesFilter::default()
+        };
+        let keys: Vec<_> = list_all(&repository, CUSTOMER_ID, VESSEL_ID, &filter, Some(1))
+            .await?
+            .into_iter()
+            .map(|license| license.license_key)
+            .collect();
+        assert_eq!(vec!["c", "b", "a"], keys);
+
+        Ok(())
+    }
+}
@@ -4,11 +4,17 @@
  * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
  */
 
-use chrono::{DateTime, FixedOffset};
+use crate::license_dao::key_of;
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use licenses_api_types::{
+    CreateLicenseRequest, CreatePoolRequest, Entitlements, EntitlementsResponse, LicenseResponse, ListLicensesRequest,
+    PoolResponse, ProductResponse, SaveProductRequest, Seats, SummaryResponse, Trial,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
 use uuid::Uuid;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 #[doc = "License entity."]
 pub struct License {
@@ -18,15 +24,978 @@ pub struct License {
     pub vessel_id: Uuid,
     #[doc = "License entry key."]
     pub license_key: String,
-    #[doc = "Number of license activation."]
+    #[doc = "Number of license activations - absent if neither request nor product specified it."]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub count: Option<u8>,
+    pub count: Option<Seats>,
     #[doc = "Date when license ends."]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub expires_at: Option<DateTime<FixedOffset>>,
+    #[doc = "Features and limits granted by license."]
+    #[serde(default, skip_serializing_if = "Entitlements::is_empty")]
+    pub entitlements: Entitlements,
+    #[doc = "Trial details - absent for paid licenses."]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trial: Option<Trial>,
+}
+
+impl License {
+    pub fn is_active(&self, now: &DateTime<Utc>) -> bool {
+        self.expires_at.map_or(true, |expires_at| expires_at > *now)
+    }
+
+    pub fn is_trial(&self) -> bool {
+        self.trial.is_some()
+    }
+
+    pub fn limited_seats(&self) -> u64 {
+        self.count.and_then(|count| count.limit()).map_or(0, u64::from)
+    }
+
+    pub fn has_unlimited_seats(&self) -> bool {
+        self.count == Some(Seats::Unlimited)
+    }
+}
+
+/**
+Merges entitlements of all active licenses into single effective set (see `Entitlement::merge()` for the rules).
+
+Licenses are applied in order of their expiration - grants of the licenses that last longer (never-ending ones being
+the last) take precedence.
+ */
+pub fn resolve_entitlements<'a, I>(licenses: I, now: &DateTime<Utc>) -> Entitlements
+where
+    I: IntoIterator<Item = &'a License>,
+{
+    let mut active: Vec<_> = licenses.into_iter().filter(|license| license.is_active(now)).collect();
+    active.sort_by_key(|license| (license.expires_at.is_none(), license.expires_at));
+
+    let mut effective = Entitlements::new();
+    for license in active {
+        for (name, entitlement) in &license.entitlements {
+            let merged = match effective.remove(name) {
+                Some(current) => current.merge(entitlement.clone()),
+                None => entitlement.clone(),
+            };
+            effective.insert(name.clone(), merged);
+        }
+    }
+    effective
+}
+
+impl From<CreateLicenseRequest> for License {
+    fn from(request: CreateLicenseRequest) -> Self {
+        Self {
+            customer_id: request.customer_id,
+            vessel_id: request.vessel_id,
+            license_key: request.license_key,
+            count: request.count,
+            expires_at: request.expires_at,
+            entitlements: request.entitlements,
+            trial: None,
+        }
+    }
+}
+
+impl From<License> for LicenseResponse {
+    fn from(model: License) -> Self {
+        Self {
+            license_key: model.license_key,
+            count: model.count,
+            expires_at: model.expires_at,
+            entitlements: model.entitlements,
+            trial: model.trial,
+        }
+    }
+}
+
+#[derive(PartialEq, Default, Debug)]
+#[doc = "Effective state of all active licenses of the vessel."]
+pub struct VesselEntitlements {
+    #[doc = "Keys of active licenses."]
+    pub license_keys: BTreeSet<String>,
+    #[doc = "Earliest end date of active licenses."]
+    pub expires_at: Option<DateTime<FixedOffset>>,
+    #[doc = "Total activations count of active licenses with limited seats."]
+    pub seats: u64,
+    #[doc = "Whether any active license grants unlimited activations."]
+    pub unlimited_seats: bool,
+    #[doc = "Merged features and limits of active licenses."]
+    pub entitlements: Entitlements,
+}
+
+impl VesselEntitlements {
+    /**
+    Combines licenses of single vessel, skipping the ones that already ended.
+
+    Overlapping renewals (eg. next-year license created before current one ends) are both counted as active - the
+    vessel is entitled to everything granted by either of them, until the earlier one ends.
+     */
+    pub fn resolve(licenses: &[License], now: &DateTime<Utc>) -> Self {
+        let active: Vec<_> = licenses.iter().filter(|license| license.is_active(now)).collect();
+
+        Self {
+            license_keys: active.iter().map(|license| license.license_key.clone()).collect(),
+            expires_at: active.iter().filter_map(|license| license.expires_at).min(),
+            seats: active.iter().map(|license| license.limited_seats()).sum(),
+            unlimited_seats: active.iter().any(|license| license.has_unlimited_seats()),
+            entitlements: resolve_entitlements(active, now),
+        }
+    }
+}
+
+impl From<VesselEntitlements> for EntitlementsResponse {
+    fn from(model: VesselEntitlements) -> Self {
+        Self {
+            license_keys: model.license_keys.into_iter().collect(),
+            expires_at: model.expires_at,
+            seats: model.seats,
+            unlimited_seats: model.unlimited_seats,
+            entitlements: model.entitlements,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Product catalogue entry."]
+pub struct Product {
+    #[doc = "Product key - license key of issued licenses."]
+    pub product_key: String,
+    #[doc = "Human-readable product name."]
+    pub display_name: String,
+    #[doc = "Number of activations of new licenses."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_count: Option<Seats>,
+    #[doc = "Validity period, in days, of new licenses."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_duration_days: Option<u16>,
+}
+
+impl Product {
+    /**
+    Fills license properties that were not specified explicitly with catalogue defaults.
+     */
+    pub fn apply_defaults(&self, license: &mut License, now: &DateTime<Utc>) {
+        if license.count.is_none() {
+            license.count = self.default_count;
+        }
+
+        if license.expires_at.is_none() {
+            license.expires_at = self
+                .default_duration_days
+                .map(|days| (*now + Duration::days(days.into())).into());
+        }
+    }
+}
+
+impl From<SaveProductRequest> for Product {
+    fn from(request: SaveProductRequest) -> Self {
+        Self {
+            product_key: request.product_key,
+            display_name: request.display_name,
+            default_count: request.default_count,
+            default_duration_days: request.default_duration_days,
+        }
+    }
+}
+
+impl From<Product> for ProductResponse {
+    fn from(model: Product) -> Self {
+        Self {
+            product_key: model.product_key,
+            display_name: model.display_name,
+            default_count: model.default_count,
+            default_duration_days: model.default_duration_days,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Customer pool of seats shared by the whole fleet."]
+pub struct Pool {
+    #[doc = "Owner ID."]
+    pub customer_id: Uuid,
+    #[doc = "Product key - license key of licenses allocated from the pool."]
+    pub product_key: String,
+    #[doc = "Number of seats bought for the fleet."]
+    pub total_seats: u32,
+    #[doc = "Number of seats not assigned to any vessel."]
+    pub available_seats: u32,
+    #[doc = "Date when pool ends."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<FixedOffset>>,
+}
+
+impl Pool {
+    pub fn is_active(&self, now: &DateTime<Utc>) -> bool {
+        self.expires_at.map_or(true, |expires_at| expires_at > *now)
+    }
+
+    pub fn allocated_seats(&self) -> u32 {
+        self.total_seats.saturating_sub(self.available_seats)
+    }
+
+    /**
+    Vessel license representing seats allocated from the pool - it lasts as long as the pool itself.
+     */
+    pub fn license_of(&self, vessel_id: Uuid, seats: u32) -> License {
+        License {
+            customer_id: self.customer_id,
+            vessel_id,
+            license_key: self.product_key.clone(),
+            count: Some(Seats::Limited(seats)),
+            expires_at: self.expires_at,
+            entitlements: Entitlements::new(),
+            trial: None,
+        }
+    }
+}
+
+impl From<CreatePoolRequest> for Pool {
+    fn from(request: CreatePoolRequest) -> Self {
+        Self {
+            customer_id: request.customer_id,
+            product_key: request.product_key,
+            total_seats: request.total_seats,
+            available_seats: request.total_seats,
+            expires_at: request.expires_at,
+        }
+    }
+}
+
+impl From<Pool> for PoolResponse {
+    fn from(model: Pool) -> Self {
+        Self {
+            allocated_seats: model.allocated_seats(),
+            product_key: model.product_key,
+            total_seats: model.total_seats,
+            expires_at: model.expires_at,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+#[doc = "Outcome of seats allocation attempt."]
+pub enum SeatsAllocation {
+    #[doc = "Seats were taken from the pool and vessel license was created."]
+    Allocated,
+    #[doc = "Pool has not enough free seats or already ended."]
+    InsufficientSeats,
+    #[doc = "Vessel already has license of pool product."]
+    AlreadyAllocated,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Trial history entry - kept after trial license is converted or deleted."]
+pub struct TrialRecord {
+    #[doc = "Owner ID."]
+    pub customer_id: Uuid,
+    #[doc = "Vessel ID."]
+    pub vessel_id: Uuid,
+    #[doc = "Trial license key."]
+    pub license_key: String,
+    #[doc = "Source of the trial."]
+    pub origin: String,
+    #[doc = "Date when trial started."]
+    pub started_at: DateTime<FixedOffset>,
+    #[doc = "Date when trial was turned into paid license."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converted_at: Option<DateTime<FixedOffset>>,
+}
+
+impl TrialRecord {
+    pub fn of(license: &License, trial: &Trial) -> Self {
+        Self {
+            customer_id: license.customer_id,
+            vessel_id: license.vessel_id,
+            license_key: license.license_key.clone(),
+            origin: trial.origin.clone(),
+            started_at: trial.started_at,
+            converted_at: None,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+#[doc = "Outcome of trial start attempt."]
+pub enum TrialStart {
+    #[doc = "Trial license was created."]
+    Started,
+    #[doc = "Vessel already had trial of the product."]
+    AlreadyUsed,
+    #[doc = "Vessel already has license of the product."]
+    LicenseExists,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Licenses listing criteria."]
+pub struct LicensesFilter {
+    #[doc = "Prefix of returned license keys."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license_key_prefix: Option<String>,
+    #[doc = "Return only licenses that already ended."]
+    #[serde(default)]
+    pub expired_only: bool,
+    #[doc = "Return only licenses that did not end yet."]
+    #[serde(default)]
+    pub active_only: bool,
+    #[doc = "List licenses in reversed key order."]
+    #[serde(default)]
+    pub descending: bool,
+    #[doc = "Moment of reconstructed state, also the reference for expired licenses - current state if absent."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub as_of: Option<DateTime<Utc>>,
+}
+
+impl From<&ListLicensesRequest> for LicensesFilter {
+    fn from(request: &ListLicensesRequest) -> Self {
+        Self {
+            license_key_prefix: request.license_key_prefix.clone(),
+            expired_only: request.expired_only,
+            active_only: request.active_only,
+            descending: request.descending,
+            as_of: request.as_of.map(|as_of| as_of.with_timezone(&Utc)),
+        }
+    }
+}
+
+impl LicensesFilter {
+    pub fn matches(&self, license: &License, now: &DateTime<Utc>) -> bool {
+        let expired = !license.is_active(now);
+
+        self.license_key_prefix
+            .as_ref()
+            .map_or(true, |prefix| license.license_key.starts_with(prefix))
+            && !(self.expired_only && !expired)
+            && !(self.active_only && expired)
+    }
+}
+
+/**
+Builds single page out of all licenses of the vessel, ordered by license key - for storages that can't page on their
+own.
+ */
+pub fn page_of(
+    licenses: Vec<License>,
+    filter: &LicensesFilter,
+    limit: Option<u16>,
+    page_key: Option<PageKey>,
+    now: &DateTime<Utc>,
+) -> DynamoResultsPage<License, PageKey> {
+    let start = page_key.and_then(|mut key| key.remove("licenseKey"));
+
+    let mut candidates = licenses;
+    if filter.descending {
+        candidates.reverse();
+    }
+
+    let mut page = DynamoResultsPage {
+        items: vec![],
+        last_evaluated_key: None,
+        skipped: vec![],
+    };
+    for license in candidates.into_iter().skip_while(|license| {
+        start.as_ref().map_or(false, |start| {
+            if filter.descending {
+                license.license_key >= *start
+            } else {
+                license.license_key <= *start
+            }
+        })
+    }) {
+        if !filter.matches(&license, now) {
+            continue;
+        }
+
+        let key = PageKey::from([
+            (
+                "customerAndVesselId".into(),
+                key_of(&license.customer_id, &license.vessel_id),
+            ),
+            ("licenseKey".into(), license.license_key.clone()),
+        ]);
+        page.items.push(license);
+        if limit.map_or(false, |limit| page.items.len() >= limit.into()) {
+            page.last_evaluated_key = Some(key);
+            break;
+        }
+    }
+
+    page
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[doc = "State of the license within its validity interval."]
+pub struct LicenseVersion {
+    #[doc = "License entry key."]
+    pub license_key: String,
+    #[doc = "Moment when the state was stored."]
+    pub valid_from: DateTime<Utc>,
+    #[doc = "Moment when the state was replaced - absent for the current one."]
+    pub valid_to: Option<DateTime<Utc>>,
+    #[doc = "License state - absent when license was deleted."]
+    pub license: Option<License>,
+}
+
+impl LicenseVersion {
+    /**
+    Turns recorded states, ordered by license key and time, into versions - each one lasts until the next state of
+    the same license.
+     */
+    pub fn from_states(states: Vec<(String, DateTime<Utc>, Option<License>)>) -> Vec<Self> {
+        let mut versions: Vec<Self> = Vec::with_capacity(states.len());
+        for (license_key, valid_from, license) in states {
+            if let Some(previous) = versions
+                .last_mut()
+                .filter(|previous| previous.license_key == license_key)
+            {
+                previous.valid_to = Some(valid_from);
+            }
+
+            versions.push(Self {
+                license_key,
+                valid_from,
+                valid_to: None,
+                license,
+            });
+        }
+        versions
+    }
+
+    pub fn is_valid_at(&self, at: &DateTime<Utc>) -> bool {
+        self.valid_from <= *at && self.valid_to.map_or(true, |valid_to| *at < valid_to)
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Aggregated statistics of licenses."]
+pub struct LicensesSummary {
+    #[doc = "Number of all licenses."]
+    pub total: usize,
+    #[doc = "Number of licenses that did not end yet."]
+    pub active: usize,
+    #[doc = "Number of licenses that already ended."]
+    pub expired: usize,
+    #[doc = "Number of active licenses that end before the given horizon."]
+    pub expiring_soon: usize,
+    #[doc = "Total activations count of active licenses with limited seats."]
+    pub seats: u64,
+    #[doc = "Whether any active license grants unlimited activations."]
+    pub unlimited_seats: bool,
+}
+
+impl LicensesSummary {
+    pub fn add(
+        &mut self,
+        count: Option<Seats>,
+        expires_at: Option<DateTime<FixedOffset>>,
+        now: &DateTime<Utc>,
+        horizon: &DateTime<Utc>,
+    ) {
+        self.total += 1;
+
+        if expires_at.map_or(false, |expires_at| expires_at <= *now) {
+            self.expired += 1;
+        } else {
+            self.active += 1;
+            match count {
+                Some(Seats::Limited(count)) => self.seats += u64::from(count),
+                Some(Seats::Unlimited) => self.unlimited_seats = true,
+                None => {}
+            }
+
+            if expires_at.map_or(false, |expires_at| expires_at <= *horizon) {
+                self.expiring_soon += 1;
+            }
+        }
+    }
+}
+
+impl From<LicensesSummary> for SummaryResponse {
+    fn from(model: LicensesSummary) -> Self {
+        Self {
+            total: model.total,
+            active: model.active,
+            expired: model.expired,
+            expiring_soon: model.expiring_soon,
+            seats: model.seats,
+            unlimited_seats: model.unlimited_seats,
+        }
+    }
+}
+
+#[doc = "Key attributes of the last evaluated item."]
+pub type PageKey = HashMap<String, String>;
+
+#[derive(PartialEq, Debug)]
+#[doc = "Entry left out of the results page, because it couldn't be read."]
+pub struct SkippedItem {
+    #[doc = "Sort key of the entry."]
+    pub license_key: String,
+    #[doc = "Why the entry was skipped."]
+    pub reason: String,
 }
 
 pub struct DynamoResultsPage<T, K> {
     pub items: Vec<T>,
     pub last_evaluated_key: Option<K>,
+    pub skipped: Vec<SkippedItem>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        resolve_entitlements, License, LicensesFilter, LicensesSummary, Pool, Product, TrialRecord, VesselEntitlements,
+    };
+    use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+    use licenses_api_types::{
+        CreateLicenseRequest, CreatePoolRequest, Entitlement, Entitlements, EntitlementsResponse, LicenseResponse,
+        ListLicensesRequest, PoolResponse, ProductResponse, SaveProductRequest, Seats, SummaryResponse, Trial,
+    };
+    use std::collections::BTreeSet;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000000");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+
+    fn license(license_key: &str, expires_at: Option<DateTime<FixedOffset>>) -> License {
+        License {
+            customer_id: CUSTOMER_ID,
+            vessel_id: VESSEL_ID,
+            license_key: license_key.to_string(),
+            count: None,
+            expires_at,
+            entitlements: Entitlements::new(),
+            trial: None,
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2023, 7, 1, 12, 0, 0).unwrap()
+    }
+
+    fn date(hour: u32) -> Option<DateTime<FixedOffset>> {
+        Some(
+            Utc.with_ymd_and_hms(2023, 7, 1, hour, 0, 0)
+                .unwrap()
+                .with_timezone(&FixedOffset::east_opt(7200).unwrap()),
+        )
+    }
+
+    #[test]
+    fn matches_everything_by_default() {
+        let filter = LicensesFilter::default();
+
+        assert!(filter.matches(&license("tides.2023", None), &now()));
+        assert!(filter.matches(&license("tides.2022", date(11)), &now()));
+    }
+
+    #[test]
+    fn matches_prefix() {
+        let filter = LicensesFilter {
+            license_key_prefix: Some("tides.".into()),
+            ..LicensesFilter::default()
+        };
+
+        assert!(filter.matches(&license("tides.2023", None), &now()));
+        assert!(!filter.matches(&license("weather.2023", None), &now()));
+    }
+
+    #[test]
+    fn matches_expired_only() {
+        let filter = LicensesFilter {
+            expired_only: true,
+            ..LicensesFilter::default()
+        };
+
+        assert!(filter.matches(&license("tides.2022", date(11)), &now()));
+        assert!(!filter.matches(&license("tides.2023", date(13)), &now()));
+        assert!(!filter.matches(&license("tides", None), &now()));
+    }
+
+    #[test]
+    fn matches_active_only() {
+        let filter = LicensesFilter {
+            active_only: true,
+            ..LicensesFilter::default()
+        };
+
+        assert!(!filter.matches(&license("tides.2022", date(11)), &now()));
+        assert!(filter.matches(&license("tides.2023", date(13)), &now()));
+        assert!(filter.matches(&license("tides", None), &now()));
+    }
+
+    #[test]
+    fn summary_add() {
+        let mut summary = LicensesSummary::default();
+        let horizon = Utc.with_ymd_and_hms(2023, 7, 1, 15, 0, 0).unwrap();
+
+        summary.add(Some(Seats::Limited(2)), None, &now(), &horizon);
+        summary.add(Some(Seats::Limited(3)), date(11), &now(), &horizon);
+        summary.add(None, date(13), &now(), &horizon);
+        summary.add(Some(Seats::Limited(4)), date(17), &now(), &horizon);
+
+        assert_eq!(
+            LicensesSummary {
+                total: 4,
+                active: 3,
+                expired: 1,
+                expiring_soon: 1,
+                seats: 6,
+                unlimited_seats: false,
+            },
+            summary
+        );
+    }
+
+    #[test]
+    fn summary_add_unlimited() {
+        let mut summary = LicensesSummary::default();
+        let horizon = Utc.with_ymd_and_hms(2023, 7, 1, 15, 0, 0).unwrap();
+
+        summary.add(Some(Seats::Unlimited), date(11), &now(), &horizon);
+        summary.add(Some(Seats::Limited(300)), None, &now(), &horizon);
+        assert!(!summary.unlimited_seats);
+
+        summary.add(Some(Seats::Unlimited), None, &now(), &horizon);
+        assert_eq!(300, summary.seats);
+        assert!(summary.unlimited_seats);
+    }
+
+    #[test]
+    fn license_from_request() {
+        let license = License::from(CreateLicenseRequest {
+            customer_id: CUSTOMER_ID,
+            vessel_id: VESSEL_ID,
+            license_key: "tides".to_string(),
+            count: Some(Seats::Limited(6)),
+            expires_at: date(14),
+            entitlements: Entitlements::from([("routing".to_string(), Entitlement::Flag(true))]),
+        });
+
+        assert_eq!(CUSTOMER_ID, license.customer_id);
+        assert_eq!(VESSEL_ID, license.vessel_id);
+        assert_eq!("tides", license.license_key);
+        assert_eq!(Some(Seats::Limited(6)), license.count);
+        assert_eq!(date(14), license.expires_at);
+        assert_eq!(Some(&Entitlement::Flag(true)), license.entitlements.get("routing"));
+    }
+
+    #[test]
+    fn response_from_license() {
+        let response = LicenseResponse::from(License {
+            count: Some(Seats::Limited(6)),
+            ..license("tides", None)
+        });
+
+        assert_eq!("tides", response.license_key);
+        assert_eq!(Some(Seats::Limited(6)), response.count);
+        assert!(response.expires_at.is_none());
+    }
+
+    #[test]
+    fn filter_from_request() {
+        let filter = LicensesFilter::from(&ListLicensesRequest {
+            customer_id: CUSTOMER_ID,
+            vessel_id: VESSEL_ID,
+            page_token: None,
+            limit: None,
+            license_key_prefix: Some("tides".to_string()),
+            expired_only: false,
+            active_only: true,
+            descending: true,
+            as_of: None,
+        });
+
+        assert_eq!(
+            LicensesFilter {
+                license_key_prefix: Some("tides".to_string()),
+                expired_only: false,
+                active_only: true,
+                descending: true,
+                as_of: None,
+            },
+            filter
+        );
+    }
+
+    #[test]
+    fn response_from_summary() {
+        let response = SummaryResponse::from(LicensesSummary {
+            total: 12,
+            active: 9,
+            expired: 3,
+            expiring_soon: 2,
+            seats: 20,
+            unlimited_seats: false,
+        });
+
+        assert_eq!(12, response.total);
+        assert_eq!(9, response.active);
+        assert_eq!(3, response.expired);
+        assert_eq!(2, response.expiring_soon);
+        assert_eq!(20, response.seats);
+    }
+
+    fn entitled(license_key: &str, expires_at: Option<DateTime<FixedOffset>>, entitlements: Entitlements) -> License {
+        License {
+            entitlements,
+            ..license(license_key, expires_at)
+        }
+    }
+
+    #[test]
+    fn resolve_entitlements_merges_active() {
+        let licenses = [
+            entitled(
+                "routing",
+                None,
+                Entitlements::from([
+                    ("routing".to_string(), Entitlement::Flag(true)),
+                    ("maxRoutes".to_string(), Entitlement::Limit(5)),
+                    ("forecastModel".to_string(), Entitlement::Value("ecmwf".to_string())),
+                ]),
+            ),
+            entitled(
+                "routing.trial",
+                date(15),
+                Entitlements::from([
+                    ("routing".to_string(), Entitlement::Flag(false)),
+                    ("maxRoutes".to_string(), Entitlement::Limit(20)),
+                    ("forecastModel".to_string(), Entitlement::Value("gfs".to_string())),
+                    ("weather".to_string(), Entitlement::Flag(true)),
+                ]),
+            ),
+            entitled(
+                "weather.2022",
+                date(11),
+                Entitlements::from([
+                    ("maxRoutes".to_string(), Entitlement::Limit(100)),
+                    ("tides".to_string(), Entitlement::Flag(true)),
+                ]),
+            ),
+        ];
+
+        assert_eq!(
+            Entitlements::from([
+                ("forecastModel".to_string(), Entitlement::Value("ecmwf".to_string())),
+                ("maxRoutes".to_string(), Entitlement::Limit(20)),
+                ("routing".to_string(), Entitlement::Flag(true)),
+                ("weather".to_string(), Entitlement::Flag(true)),
+            ]),
+            resolve_entitlements(&licenses, &now())
+        );
+    }
+
+    #[test]
+    fn resolve_entitlements_empty() {
+        assert!(resolve_entitlements(&[license("tides", None)], &now()).is_empty());
+    }
+
+    #[test]
+    fn vessel_entitlements() {
+        let licenses = [
+            License {
+                count: Some(Seats::Limited(2)),
+                ..entitled(
+                    "routing",
+                    date(15),
+                    Entitlements::from([("maxRoutes".to_string(), Entitlement::Limit(5))]),
+                )
+            },
+            License {
+                count: Some(Seats::Limited(3)),
+                ..license("tides", None)
+            },
+            License {
+                count: Some(Seats::Limited(10)),
+                ..license("weather", date(11))
+            },
+        ];
+
+        assert_eq!(
+            VesselEntitlements {
+                license_keys: BTreeSet::from(["routing".to_string(), "tides".to_string()]),
+                expires_at: date(15),
+                seats: 5,
+                unlimited_seats: false,
+                entitlements: Entitlements::from([("maxRoutes".to_string(), Entitlement::Limit(5))]),
+            },
+            VesselEntitlements::resolve(&licenses, &now())
+        );
+    }
+
+    #[test]
+    fn vessel_entitlements_unlimited() {
+        let licenses = [
+            License {
+                count: Some(Seats::Unlimited),
+                ..license("routing", None)
+            },
+            License {
+                count: Some(Seats::Limited(3)),
+                ..license("tides", None)
+            },
+        ];
+
+        let resolved = VesselEntitlements::resolve(&licenses, &now());
+        assert_eq!(3, resolved.seats);
+        assert!(resolved.unlimited_seats);
+    }
+
+    #[test]
+    fn vessel_entitlements_none_active() {
+        assert_eq!(
+            VesselEntitlements::default(),
+            VesselEntitlements::resolve(&[license("weather", date(11)), license("tides", date(12))], &now())
+        );
+    }
+
+    #[test]
+    fn response_from_vessel_entitlements() {
+        let response = EntitlementsResponse::from(VesselEntitlements {
+            license_keys: BTreeSet::from(["weather".to_string(), "tides".to_string()]),
+            expires_at: date(15),
+            seats: 4,
+            unlimited_seats: false,
+            entitlements: Entitlements::new(),
+        });
+
+        assert_eq!(vec!["tides", "weather"], response.license_keys);
+        assert_eq!(date(15), response.expires_at);
+        assert_eq!(4, response.seats);
+    }
+
+    fn product() -> Product {
+        Product {
+            product_key: "tides.2023".to_string(),
+            display_name: "Tides 2023".to_string(),
+            default_count: Some(Seats::Limited(3)),
+            default_duration_days: Some(10),
+        }
+    }
+
+    #[test]
+    fn product_defaults() {
+        let mut license = license("tides.2023", None);
+        product().apply_defaults(&mut license, &now());
+
+        assert_eq!(Some(Seats::Limited(3)), license.count);
+        assert_eq!(
+            Some(Utc.with_ymd_and_hms(2023, 7, 11, 12, 0, 0).unwrap()),
+            license.expires_at.map(|expires_at| expires_at.with_timezone(&Utc))
+        );
+    }
+
+    #[test]
+    fn product_defaults_explicit() {
+        let mut license = License {
+            count: Some(Seats::Limited(1)),
+            ..license("tides.2023", date(15))
+        };
+        product().apply_defaults(&mut license, &now());
+
+        assert_eq!(Some(Seats::Limited(1)), license.count);
+        assert_eq!(date(15), license.expires_at);
+    }
+
+    #[test]
+    fn product_defaults_unspecified() {
+        let mut license = license("tides.2023", None);
+        Product {
+            default_count: None,
+            default_duration_days: None,
+            ..product()
+        }
+        .apply_defaults(&mut license, &now());
+
+        assert!(license.count.is_none());
+        assert!(license.expires_at.is_none());
+    }
+
+    #[test]
+    fn product_from_request() {
+        let product = Product::from(SaveProductRequest {
+            product_key: "tides.2023".to_string(),
+            display_name: "Tides 2023".to_string(),
+            default_count: Some(Seats::Limited(3)),
+            default_duration_days: None,
+        });
+
+        assert_eq!("tides.2023", product.product_key);
+        assert_eq!("Tides 2023", product.display_name);
+        assert_eq!(Some(Seats::Limited(3)), product.default_count);
+        assert!(product.default_duration_days.is_none());
+    }
+
+    #[test]
+    fn response_from_product() {
+        let response = ProductResponse::from(product());
+
+        assert_eq!("tides.2023", response.product_key);
+        assert_eq!("Tides 2023", response.display_name);
+        assert_eq!(Some(Seats::Limited(3)), response.default_count);
+        assert_eq!(Some(10), response.default_duration_days);
+    }
+
+    #[test]
+    fn pool_from_request() {
+        let pool = Pool::from(CreatePoolRequest {
+            customer_id: CUSTOMER_ID,
+            product_key: "tides".to_string(),
+            total_seats: 50,
+            expires_at: date(14),
+        });
+
+        assert_eq!(50, pool.available_seats);
+        assert_eq!(0, pool.allocated_seats());
+        assert!(pool.is_active(&now()));
+    }
+
+    #[test]
+    fn pool_license() {
+        let pool = Pool {
+            customer_id: CUSTOMER_ID,
+            product_key: "tides".to_string(),
+            total_seats: 50,
+            available_seats: 38,
+            expires_at: date(10),
+        };
+        let license = pool.license_of(VESSEL_ID, 3);
+
+        assert!(!pool.is_active(&now()));
+        assert_eq!(VESSEL_ID, license.vessel_id);
+        assert_eq!("tides", license.license_key);
+        assert_eq!(Some(Seats::Limited(3)), license.count);
+        assert_eq!(date(10), license.expires_at);
+
+        let response = PoolResponse::from(pool);
+        assert_eq!(50, response.total_seats);
+        assert_eq!(12, response.allocated_seats);
+    }
+
+    #[test]
+    fn trial_record() {
+        let mut trial = license("tides", date(14));
+        assert!(!trial.is_trial());
+
+        let details = Trial {
+            origin: "web".to_string(),
+            started_at: date(10).unwrap(),
+        };
+        let record = TrialRecord::of(&trial, &details);
+        trial.trial = Some(details);
+
+        assert!(trial.is_trial());
+        assert_eq!(VESSEL_ID, record.vessel_id);
+        assert_eq!("tides", record.license_key);
+        assert_eq!("web", record.origin);
+        assert_eq!(date(10).unwrap(), record.started_at);
+        assert!(record.converted_at.is_none());
+        assert_eq!(trial.trial, LicenseResponse::from(trial.clone()).trial);
+    }
 }
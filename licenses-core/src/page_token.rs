@@ -0,0 +1,200 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::api_error::ApiError;
+use crate::model::{LicensesFilter, PageKey};
+use crate::runtime_error::RuntimeError;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_slice, to_vec};
+use sha2::Sha256;
+use std::env::var;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+#[doc = "Query scope to which page token is bound."]
+pub struct PageScope {
+    #[doc = "Owner ID."]
+    pub customer_id: Uuid,
+    #[doc = "Vessel ID."]
+    pub vessel_id: Uuid,
+    #[doc = "Listing criteria."]
+    pub filter: LicensesFilter,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PageTokenPayload {
+    scope: PageScope,
+    key: PageKey,
+}
+
+pub struct PageTokenCodec {
+    mac: HmacSha256,
+}
+
+/**
+Required environment variables:
+<dl>
+    <dt><code>PAGE_TOKEN_SECRET</code></dt>
+    <dd>Secret used to sign page tokens.</dd>
+</dl>
+ */
+impl PageTokenCodec {
+    pub fn load_from_env() -> Result<Self, RuntimeError> {
+        var("PAGE_TOKEN_SECRET")
+            .map_err(RuntimeError::ClientConfigLoadingError)
+            .and_then(|secret| Self::new(secret.as_bytes()))
+    }
+
+    pub fn new(secret: &[u8]) -> Result<Self, RuntimeError> {
+        Ok(Self {
+            mac: HmacSha256::new_from_slice(secret)?,
+        })
+    }
+
+    pub fn encode(&self, scope: PageScope, key: PageKey) -> Result<String, RuntimeError> {
+        let payload = to_vec(&PageTokenPayload { scope, key })?;
+
+        let mut mac = self.mac.clone();
+        mac.update(&payload);
+
+        Ok(format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(&payload),
+            URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+        ))
+    }
+
+    pub fn decode(&self, token: &str, scope: &PageScope) -> Result<PageKey, ApiError> {
+        let (payload, signature) = token.split_once('.').ok_or(ApiError::InvalidPageToken)?;
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(|_| ApiError::InvalidPageToken)?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| ApiError::InvalidPageToken)?;
+
+        let mut mac = self.mac.clone();
+        mac.update(&payload);
+        mac.verify_slice(&signature).map_err(|_| ApiError::InvalidPageToken)?;
+
+        let payload: PageTokenPayload = from_slice(&payload).map_err(|_| ApiError::InvalidPageToken)?;
+        if payload.scope == *scope {
+            Ok(payload.key)
+        } else {
+            Err(ApiError::InvalidPageToken)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ApiError, LicensesFilter, PageKey, PageScope, PageTokenCodec};
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000000");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    const OTHER_VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    const LICENSE_KEY: &str = "tides.2023";
+
+    fn scope(vessel_id: Uuid) -> PageScope {
+        PageScope {
+            customer_id: CUSTOMER_ID,
+            vessel_id,
+            filter: LicensesFilter::default(),
+        }
+    }
+
+    fn page_key() -> PageKey {
+        PageKey::from([
+            ("customerAndVesselId".into(), format!("{CUSTOMER_ID}:{VESSEL_ID}")),
+            ("licenseKey".into(), LICENSE_KEY.into()),
+        ])
+    }
+
+    #[test]
+    fn round_trip() {
+        let codec = PageTokenCodec::new(b"secret").unwrap();
+
+        let token = codec.encode(scope(VESSEL_ID), page_key()).unwrap();
+        assert!(!token.contains(LICENSE_KEY));
+
+        let key = codec.decode(&token, &scope(VESSEL_ID)).unwrap();
+        assert_eq!(page_key(), key);
+    }
+
+    #[test]
+    fn different_scope() {
+        let codec = PageTokenCodec::new(b"secret").unwrap();
+
+        let token = codec.encode(scope(VESSEL_ID), page_key()).unwrap();
+        match codec.decode(&token, &scope(OTHER_VESSEL_ID)) {
+            Err(ApiError::InvalidPageToken) => {}
+            _ => panic!("Token accepted for different vessel."),
+        }
+    }
+
+    #[test]
+    fn different_filter() {
+        let codec = PageTokenCodec::new(b"secret").unwrap();
+
+        let token = codec.encode(scope(VESSEL_ID), page_key()).unwrap();
+        let filtered = PageScope {
+            filter: LicensesFilter {
+                expired_only: true,
+                ..LicensesFilter::default()
+            },
+            ..scope(VESSEL_ID)
+        };
+        match codec.decode(&token, &filtered) {
+            Err(ApiError::InvalidPageToken) => {}
+            _ => panic!("Token accepted for different filter."),
+        }
+    }
+
+    #[test]
+    fn different_secret() {
+        let token = PageTokenCodec::new(b"secret")
+            .unwrap()
+            .encode(scope(VESSEL_ID), page_key())
+            .unwrap();
+
+        match PageTokenCodec::new(b"other").unwrap().decode(&token, &scope(VESSEL_ID)) {
+            Err(ApiError::InvalidPageToken) => {}
+            _ => panic!("Token accepted with different secret."),
+        }
+    }
+
+    #[test]
+    fn tampered_token() {
+        let codec = PageTokenCodec::new(b"secret").unwrap();
+
+        let token = codec.encode(scope(VESSEL_ID), page_key()).unwrap();
+        let forged = codec.encode(scope(VESSEL_ID), PageKey::new()).unwrap();
+        let (_, signature) = token.split_once('.').unwrap();
+        let (payload, _) = forged.split_once('.').unwrap();
+
+        match codec.decode(&format!("{payload}.{signature}"), &scope(VESSEL_ID)) {
+            Err(ApiError::InvalidPageToken) => {}
+            _ => panic!("Tampered token accepted."),
+        }
+    }
+
+    #[test]
+    fn raw_license_key() {
+        let codec = PageTokenCodec::new(b"secret").unwrap();
+
+        match codec.decode(LICENSE_KEY, &scope(VESSEL_ID)) {
+            Err(ApiError::InvalidPageToken) => {}
+            _ => panic!("Raw license key accepted as page token."),
+        }
+    }
+}
@@ -0,0 +1,602 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::license_dao::{
+    history_entry, key_of, license_item, load_client_from_env, retention_from_env, DEFAULT_RETENTION_DAYS,
+    POOLED_ATTRIBUTE,
+};
+use crate::model::{Pool, SeatsAllocation};
+use crate::pool_repository::PoolRepository;
+use crate::runtime_error::{failed_conditions, RuntimeError};
+use async_trait::async_trait;
+use aws_sdk_dynamodb::operation::put_item::PutItemError;
+use aws_sdk_dynamodb::types::AttributeValue::{Bool, N, S};
+use aws_sdk_dynamodb::types::{Delete, Put, TransactWriteItem, Update};
+use aws_sdk_dynamodb::Client;
+use chrono::{Duration, Utc};
+use serde_dynamo::{from_item, from_items, to_item};
+use std::env::var;
+use tracing::{Instrument, Span};
+use uuid::Uuid;
+use xray::aws_metadata;
+
+pub struct PoolDao {
+    client: Box<Client>,
+    pools_table_name: String,
+    licenses_table_name: String,
+    history_table_name: Option<String>,
+    retention: Duration,
+}
+
+/**
+Required environment variables:
+<dl>
+    <dt><code>POOLS_TABLE</code></dt>
+    <dd>Name of DynamoDB customer pools table.</dd>
+    <dt><code>LICENSES_TABLE</code></dt>
+    <dd>Name of DynamoDB licenses table.</dd>
+</dl>
+
+Optional environment variables:
+<dl>
+    <dt><code>LICENSE_RETENTION_DAYS</code></dt>
+    <dd>How long expired licenses are kept before DynamoDB removes them, by default <code>365</code> days.</dd>
+    <dt><code>LICENSE_HISTORY_TABLE</code></dt>
+    <dd>Name of DynamoDB license history table - without it changes are not recorded.</dd>
+</dl>
+ */
+impl PoolDao {
+    pub async fn load_from_env() -> Result<Self, RuntimeError> {
+        let client = load_client_from_env().await;
+
+        let dao = Self::new(
+            client,
+            var("POOLS_TABLE").map_err(RuntimeError::ClientConfigLoadingError)?,
+            var("LICENSES_TABLE").map_err(RuntimeError::ClientConfigLoadingError)?,
+        )
+        .with_retention(retention_from_env()?);
+
+        Ok(match var("LICENSE_HISTORY_TABLE") {
+            Ok(history_table_name) => dao.with_history(history_table_name),
+            Err(_) => dao,
+        })
+    }
+
+    pub fn new(client: Client, pools_table_name: String, licenses_table_name: String) -> Self {
+        Self {
+            client: Box::new(client),
+            pools_table_name,
+            licenses_table_name,
+            history_table_name: None,
+            retention: Duration::days(DEFAULT_RETENTION_DAYS),
+        }
+    }
+
+    /**
+    Allocated licenses expire together with the pool and are kept for retention period, like any other license.
+     */
+    pub fn with_retention(self, retention: Duration) -> Self {
+        Self { retention, ..self }
+    }
+
+    /**
+    Allocations and releases are recorded in license history table, as creation and deletion of vessel license.
+     */
+    pub fn with_history(self, history_table_name: String) -> Self {
+        Self {
+            history_table_name: Some(history_table_name),
+            ..self
+        }
+    }
+
+    fn instrumentation(&self) -> Span {
+        aws_metadata(
+            self.client.conf().region().map(|value| value.to_string()).as_deref(),
+            Some(self.pools_table_name.as_str()),
+        )
+    }
+}
+
+#[async_trait]
+impl PoolRepository for PoolDao {
+    async fn insert_pool(&self, pool: Pool) -> Result<bool, RuntimeError> {
+        match self
+            .client
+            .put_item()
+            .table_name(self.pools_table_name.as_str())
+            .set_item(Some(to_item(pool)?))
+            .condition_expression("attribute_not_exists(productKey)")
+            .send()
+            .instrument(self.instrumentation())
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(error)
+                if error
+                    .as_service_error()
+                    .map_or(false, PutItemError::is_conditional_check_failed_exception) =>
+            {
+                Ok(false)
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    async fn get_pool(&self, customer_id: Uuid, product_key: String) -> Result<Option<Pool>, RuntimeError> {
+        self.client
+            .get_item()
+            .table_name(self.pools_table_name.as_str())
+            .key("customerId", S(customer_id.to_string()))
+            .key("productKey", S(product_key))
+            .send()
+            .instrument(self.instrumentation())
+            .await?
+            .item
+            .map(from_item::<_, Pool>)
+            .map_or(Ok(None), |pool| pool.map(Some))
+            .map_err(RuntimeError::from)
+    }
+
+    async fn list_pools(&self, customer_id: Uuid) -> Result<Vec<Pool>, RuntimeError> {
+        let mut pools = Vec::new();
+        let mut page_key = None;
+        loop {
+            let results = self
+                .client
+                .query()
+                .table_name(self.pools_table_name.as_str())
+                .key_condition_expression("customerId = :customerId")
+                .expression_attribute_values(":customerId", S(customer_id.to_string()))
+                .set_exclusive_start_key(page_key)
+                .send()
+                .instrument(self.instrumentation())
+                .await?;
+
+            if let Some(items) = results.items {
+                pools.extend(from_items::<_, Pool>(items)?);
+            }
+
+            page_key = results.last_evaluated_key;
+            if page_key.is_none() {
+                return Ok(pools);
+            }
+        }
+    }
+
+    /**
+    Pool update and license creation are executed as single transaction - seats are taken only if the license was
+    created and vice-versa.
+
+    Dates are stored with their original offsets, so they can not be compared by DynamoDB - pool expiration is
+    checked against the given pool state.
+     */
+    async fn allocate_seats(&self, pool: &Pool, vessel_id: Uuid, seats: u32) -> Result<SeatsAllocation, RuntimeError> {
+        if !pool.is_active(&Utc::now()) {
+            return Ok(SeatsAllocation::InsufficientSeats);
+        }
+
+        let license = pool.license_of(vessel_id, seats);
+        let entry = self
+            .history_table_name
+            .as_deref()
+            .map(|table_name| {
+                history_entry(
+                    table_name,
+                    &license.customer_id,
+                    &license.vessel_id,
+                    &license.license_key,
+                    Some(license.clone()),
+                )
+            })
+            .transpose()?;
+
+        let mut request = self
+            .client
+            .transact_write_items()
+            .transact_items(
+                TransactWriteItem::builder()
+                    .update(
+                        Update::builder()
+                            .table_name(self.pools_table_name.as_str())
+                            .key("customerId", S(pool.customer_id.to_string()))
+                            .key("productKey", S(pool.product_key.clone()))
+                            .update_expression("SET availableSeats = availableSeats - :seats")
+                            .condition_expression("availableSeats >= :seats")
+                            .expression_attribute_values(":seats", N(seats.to_string()))
+                            .build(),
+                    )
+                    .build(),
+            )
+            .transact_items(
+                TransactWriteItem::builder()
+                    .put(
+                        Put::builder()
+                            .table_name(self.licenses_table_name.as_str())
+                            .set_item(Some(license_item(license, self.retention)?))
+                            .item(POOLED_ATTRIBUTE, Bool(true))
+                            .condition_expression("attribute_not_exists(licenseKey)")
+                            .build(),
+                    )
+                    .build(),
+            );
+        // history entry goes last, so indexes of failed conditions stay the same
+        if let Some(entry) = entry {
+            request = request.transact_items(entry);
+        }
+        let result = request.send().instrument(self.instrumentation()).await;
+
+        match result {
+            Ok(_) => Ok(SeatsAllocation::Allocated),
+            Err(error) => match failed_conditions(&error).as_deref() {
+                Some([true, ..]) => Ok(SeatsAllocation::InsufficientSeats),
+                Some([_, true, ..]) => Ok(SeatsAllocation::AlreadyAllocated),
+                _ => Err(error.into()),
+            },
+        }
+    }
+
+    /**
+    Seats count is taken from the license itself - license deletion is conditioned on that count, so concurrent
+    release can not return the seats twice.
+     */
+    async fn release_seats(
+        &self,
+        customer_id: Uuid,
+        product_key: String,
+        vessel_id: Uuid,
+    ) -> Result<bool, RuntimeError> {
+        let key = key_of(&customer_id, &vessel_id);
+
+        let item = self
+            .client
+            .get_item()
+            .table_name(self.licenses_table_name.as_str())
+            .key("customerAndVesselId", S(key.clone()))
+            .key("licenseKey", S(product_key.clone()))
+            .send()
+            .instrument(self.instrumentation())
+            .await?
+            .item;
+
+        let Some(count) = item
+            .filter(|item| item.get(POOLED_ATTRIBUTE).and_then(|value| value.as_bool().ok()) == Some(&true))
+            .and_then(|mut item| item.remove("count"))
+        else {
+            return Ok(false);
+        };
+        let seats = count
+            .as_n()
+            .map_err(|value| RuntimeError::DataError(value.clone(), "count".into()))?
+            .clone();
+        let entry = self
+            .history_table_name
+            .as_deref()
+            .map(|table_name| history_entry(table_name, &customer_id, &vessel_id, &product_key, None))
+            .transpose()?;
+
+        let mut request = self
+            .client
+            .transact_write_items()
+            .transact_items(
+                TransactWriteItem::builder()
+                    .delete(
+                        Delete::builder()
+                            .table_name(self.licenses_table_name.as_str())
+                            .key("customerAndVesselId", S(key))
+                            .key("licenseKey", S(product_key.clone()))
+                            .condition_expression("#pooled = :pooled AND #count = :seats")
+                            .expression_attribute_names("#pooled", POOLED_ATTRIBUTE)
+                            .expression_attribute_names("#count", "count")
+                            .expression_attribute_values(":pooled", Bool(true))
+                            .expression_attribute_values(":seats", N(seats.clone()))
+                            .build(),
+                    )
+                    .build(),
+            )
+            .transact_items(
+                TransactWriteItem::builder()
+                    .update(
+                        Update::builder()
+                            .table_name(self.pools_table_name.as_str())
+                            .key("customerId", S(customer_id.to_string()))
+                            .key("productKey", S(product_key))
+                            .update_expression("SET availableSeats = availableSeats + :seats")
+                            .condition_expression("attribute_exists(productKey)")
+                            .expression_attribute_values(":seats", N(seats))
+                            .build(),
+                    )
+                    .build(),
+            );
+        if let Some(entry) = entry {
+            request = request.transact_items(entry);
+        }
+        let result = request.send().instrument(self.instrumentation()).await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(error) if failed_conditions(&error).map_or(false, |failed| failed.contains(&true)) => Ok(false),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::license_dao::key_of;
+    use crate::{License, LicenseDao, LicenseRepository, Pool, PoolDao, PoolRepository, RuntimeError, SeatsAllocation};
+    use async_trait::async_trait;
+    use aws_config::load_from_env;
+    use aws_sdk_dynamodb::config::Builder;
+    use aws_sdk_dynamodb::types::{
+        AttributeDefinition, AttributeValue::S, KeySchemaElement, KeyType, ProvisionedThroughput, ScalarAttributeType,
+    };
+    use aws_sdk_dynamodb::Client;
+    use chrono::{Duration, Utc};
+    use licenses_api_types::{Entitlements, Seats};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use test_context::{test_context, AsyncTestContext};
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    struct DynamoDbTestContext {
+        client: Box<Client>,
+        dao: Box<PoolDao>,
+        licenses: Box<LicenseDao>,
+        pools_table_name: String,
+        licenses_table_name: String,
+    }
+
+    static NUMBER: AtomicUsize = AtomicUsize::new(0);
+
+    // customers
+    static ID_0: Uuid = uuid!("00000000-0000-0000-0000-000000000000");
+    // vessels
+    static ID_1: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    static ID_2: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    // products
+    static PRODUCT_KEY_0: &str = "tides.2023";
+    static PRODUCT_KEY_1: &str = "weather.2023";
+
+    async fn create_table(client: &Client, table_name: &str, hash_key: &str, range_key: &str) {
+        client
+            .create_table()
+            .table_name(table_name)
+            .attribute_definitions(
+                AttributeDefinition::builder()
+                    .attribute_name(hash_key)
+                    .attribute_type(ScalarAttributeType::S)
+                    .build(),
+            )
+            .attribute_definitions(
+                AttributeDefinition::builder()
+                    .attribute_name(range_key)
+                    .attribute_type(ScalarAttributeType::S)
+                    .build(),
+            )
+            .key_schema(
+                KeySchemaElement::builder()
+                    .attribute_name(hash_key)
+                    .key_type(KeyType::Hash)
+                    .build(),
+            )
+            .key_schema(
+                KeySchemaElement::builder()
+                    .attribute_name(range_key)
+                    .key_type(KeyType::Range)
+                    .build(),
+            )
+            .provisioned_throughput(
+                ProvisionedThroughput::builder()
+                    .read_capacity_units(1000)
+                    .write_capacity_units(1000)
+                    .build(),
+            )
+            .send()
+            .await
+            .unwrap();
+    }
+
+    fn pool(product_key: &str, total_seats: u32) -> Pool {
+        Pool {
+            customer_id: ID_0,
+            product_key: product_key.into(),
+            total_seats,
+            available_seats: total_seats,
+            expires_at: Some((Utc::now() + Duration::days(30)).into()),
+        }
+    }
+
+    #[async_trait]
+    impl AsyncTestContext for DynamoDbTestContext {
+        async fn setup() -> DynamoDbTestContext {
+            let number = NUMBER.fetch_add(1, Ordering::SeqCst);
+            let pools_table_name = format!("Pools{number}");
+            let licenses_table_name = format!("PooledLicenses{number}");
+            let config = load_from_env().await;
+            let local_config = Builder::from(&config).endpoint_url("http://localhost:8000").build();
+            let client = Client::from_conf(local_config);
+
+            create_table(&client, pools_table_name.as_str(), "customerId", "productKey").await;
+            create_table(
+                &client,
+                licenses_table_name.as_str(),
+                "customerAndVesselId",
+                "licenseKey",
+            )
+            .await;
+
+            let dao = PoolDao::new(client.clone(), pools_table_name.clone(), licenses_table_name.clone());
+            dao.insert_pool(pool(PRODUCT_KEY_1, 5)).await.unwrap();
+            dao.insert_pool(pool(PRODUCT_KEY_0, 3)).await.unwrap();
+
+            DynamoDbTestContext {
+                client: Box::new(client.clone()),
+                dao: Box::new(dao),
+                licenses: Box::new(LicenseDao::new(client, licenses_table_name.clone())),
+                pools_table_name,
+                licenses_table_name,
+            }
+        }
+
+        async fn teardown(self) {
+            for table_name in [self.pools_table_name, self.licenses_table_name] {
+                self.client.delete_table().table_name(table_name).send().await.unwrap();
+            }
+        }
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn insert_pool(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        assert!(!ctx.dao.insert_pool(pool(PRODUCT_KEY_0, 10)).await?);
+
+        let item = ctx
+            .client
+            .get_item()
+            .table_name(ctx.pools_table_name.as_str())
+            .key("customerId", S(ID_0.to_string()))
+            .key("productKey", S(PRODUCT_KEY_0.into()))
+            .send()
+            .await?
+            .item
+            .unwrap();
+        assert_eq!("3", item["totalSeats"].as_n().unwrap());
+        assert_eq!("3", item["availableSeats"].as_n().unwrap());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn list_pools(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let pools = ctx.dao.list_pools(ID_0).await?;
+
+        assert_eq!(2, pools.len());
+        assert_eq!(PRODUCT_KEY_0, pools[0].product_key);
+        assert_eq!(PRODUCT_KEY_1, pools[1].product_key);
+        assert!(ctx.dao.list_pools(ID_1).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn allocate_and_release(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let current = ctx.dao.get_pool(ID_0, PRODUCT_KEY_0.into()).await?.unwrap();
+
+        assert_eq!(
+            SeatsAllocation::Allocated,
+            ctx.dao.allocate_seats(&current, ID_1, 2).await?
+        );
+        assert_eq!(
+            SeatsAllocation::InsufficientSeats,
+            ctx.dao.allocate_seats(&current, ID_2, 2).await?
+        );
+        assert_eq!(
+            SeatsAllocation::AlreadyAllocated,
+            ctx.dao.allocate_seats(&current, ID_1, 1).await?
+        );
+
+        let item = ctx
+            .client
+            .get_item()
+            .table_name(ctx.licenses_table_name.as_str())
+            .key("customerAndVesselId", S(key_of(&ID_0, &ID_1)))
+            .key("licenseKey", S(PRODUCT_KEY_0.into()))
+            .send()
+            .await?
+            .item
+            .unwrap();
+        assert_eq!("2", item["count"].as_n().unwrap());
+        assert!(item["pooled"].as_bool().unwrap());
+        assert!(item.contains_key("ttl"));
+        assert_eq!(
+            1,
+            ctx.dao
+                .get_pool(ID_0, PRODUCT_KEY_0.into())
+                .await?
+                .unwrap()
+                .available_seats
+        );
+
+        assert!(ctx.dao.release_seats(ID_0, PRODUCT_KEY_0.into(), ID_1).await?);
+        assert!(!ctx.dao.release_seats(ID_0, PRODUCT_KEY_0.into(), ID_1).await?);
+        assert!(ctx
+            .licenses
+            .get_license(ID_0, ID_1, PRODUCT_KEY_0.into())
+            .await?
+            .is_none());
+        assert_eq!(
+            3,
+            ctx.dao
+                .get_pool(ID_0, PRODUCT_KEY_0.into())
+                .await?
+                .unwrap()
+                .available_seats
+        );
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn modify_pooled(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let current = ctx.dao.get_pool(ID_0, PRODUCT_KEY_0.into()).await?.unwrap();
+        ctx.dao.allocate_seats(&current, ID_1, 2).await?;
+
+        let mut license = ctx
+            .licenses
+            .get_license(ID_0, ID_1, PRODUCT_KEY_0.into())
+            .await?
+            .unwrap();
+        license.count = Some(Seats::Unlimited);
+        assert!(!ctx.licenses.create_license(license).await?);
+        assert!(!ctx.licenses.delete_license(ID_0, ID_1, PRODUCT_KEY_0.into()).await?);
+
+        let license = ctx
+            .licenses
+            .get_license(ID_0, ID_1, PRODUCT_KEY_0.into())
+            .await?
+            .unwrap();
+        assert_eq!(Some(Seats::Limited(2)), license.count);
+        assert!(ctx.dao.release_seats(ID_0, PRODUCT_KEY_0.into(), ID_1).await?);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn release_not_pooled(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        ctx.licenses
+            .create_license(License {
+                customer_id: ID_0,
+                vessel_id: ID_1,
+                license_key: PRODUCT_KEY_1.into(),
+                count: Some(Seats::Limited(1)),
+                expires_at: None,
+                entitlements: Entitlements::new(),
+                trial: None,
+            })
+            .await?;
+
+        assert!(!ctx.dao.release_seats(ID_0, PRODUCT_KEY_1.into(), ID_1).await?);
+        assert!(ctx
+            .licenses
+            .get_license(ID_0, ID_1, PRODUCT_KEY_1.into())
+            .await?
+            .is_some());
+        assert_eq!(
+            5,
+            ctx.dao
+                .get_pool(ID_0, PRODUCT_KEY_1.into())
+                .await?
+                .unwrap()
+                .available_seats
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::model::{Pool, SeatsAllocation};
+use crate::runtime_error::RuntimeError;
+use async_trait::async_trait;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait PoolRepository: Send + Sync {
+    /**
+    Stores new pool - returns `false` if the customer already has pool of the same product.
+     */
+    async fn insert_pool(&self, pool: Pool) -> Result<bool, RuntimeError>;
+
+    async fn get_pool(&self, customer_id: Uuid, product_key: String) -> Result<Option<Pool>, RuntimeError>;
+
+    /**
+    Lists all pools of the customer, ordered by product key.
+     */
+    async fn list_pools(&self, customer_id: Uuid) -> Result<Vec<Pool>, RuntimeError>;
+
+    /**
+    Atomically takes seats from the pool and creates vessel license of pool product.
+     */
+    async fn allocate_seats(&self, pool: &Pool, vessel_id: Uuid, seats: u32) -> Result<SeatsAllocation, RuntimeError>;
+
+    /**
+    Atomically removes vessel license allocated from the pool and returns its seats back - returns `false` if the
+    vessel has no such allocation.
+     */
+    async fn release_seats(
+        &self,
+        customer_id: Uuid,
+        product_key: String,
+        vessel_id: Uuid,
+    ) -> Result<bool, RuntimeError>;
+}
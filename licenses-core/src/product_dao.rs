@@ -0,0 +1,279 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::license_dao::load_client_from_env;
+use crate::model::Product;
+use crate::product_repository::ProductRepository;
+use crate::runtime_error::RuntimeError;
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue::S;
+use aws_sdk_dynamodb::Client;
+use serde_dynamo::{from_item, from_items, to_item};
+use std::env::var;
+use tracing::{Instrument, Span};
+use xray::aws_metadata;
+
+pub struct ProductDao {
+    client: Box<Client>,
+    table_name: String,
+}
+
+/**
+Required environment variables:
+<dl>
+    <dt><code>PRODUCTS_TABLE</code></dt>
+    <dd>Name of DynamoDB product catalogue table.</dd>
+</dl>
+ */
+impl ProductDao {
+    pub async fn load_from_env() -> Result<Self, RuntimeError> {
+        let client = load_client_from_env().await;
+
+        var("PRODUCTS_TABLE")
+            .map(|table_name| Self::new(client, table_name))
+            .map_err(RuntimeError::ClientConfigLoadingError)
+    }
+
+    pub fn new(client: Client, table_name: String) -> Self {
+        Self {
+            client: Box::new(client),
+            table_name,
+        }
+    }
+
+    fn instrumentation(&self) -> Span {
+        aws_metadata(
+            self.client.conf().region().map(|value| value.to_string()).as_deref(),
+            Some(self.table_name.as_str()),
+        )
+    }
+}
+
+#[async_trait]
+impl ProductRepository for ProductDao {
+    async fn save_product(&self, product: Product) -> Result<(), RuntimeError> {
+        self.client
+            .put_item()
+            .table_name(self.table_name.as_str())
+            .set_item(Some(to_item(product)?))
+            .send()
+            .instrument(self.instrumentation())
+            .await?;
+        Ok(())
+    }
+
+    /**
+    Catalogue is small, so it's simply scanned as a whole.
+     */
+    async fn list_products(&self) -> Result<Vec<Product>, RuntimeError> {
+        let mut products = Vec::new();
+        let mut page_key = None;
+        loop {
+            let results = self
+                .client
+                .scan()
+                .table_name(self.table_name.as_str())
+                .set_exclusive_start_key(page_key)
+                .send()
+                .instrument(self.instrumentation())
+                .await?;
+
+            if let Some(items) = results.items {
+                products.extend(from_items::<_, Product>(items)?);
+            }
+
+            page_key = results.last_evaluated_key;
+            if page_key.is_none() {
+                products.sort_by(|left, right| left.product_key.cmp(&right.product_key));
+                return Ok(products);
+            }
+        }
+    }
+
+    async fn get_product(&self, product_key: String) -> Result<Option<Product>, RuntimeError> {
+        self.client
+            .get_item()
+            .table_name(self.table_name.as_str())
+            .key("productKey", S(product_key))
+            .send()
+            .instrument(self.instrumentation())
+            .await?
+            .item
+            .map(from_item::<_, Product>)
+            .map_or(Ok(None), |product| product.map(Some))
+            .map_err(RuntimeError::from)
+    }
+
+    async fn delete_product(&self, product_key: String) -> Result<(), RuntimeError> {
+        self.client
+            .delete_item()
+            .table_name(self.table_name.as_str())
+            .key("productKey", S(product_key))
+            .send()
+            .instrument(self.instrumentation())
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Product, ProductDao, ProductRepository, RuntimeError};
+    use async_trait::async_trait;
+    use aws_config::load_from_env;
+    use aws_sdk_dynamodb::config::Builder;
+    use aws_sdk_dynamodb::types::{
+        AttributeDefinition, AttributeValue::S, KeySchemaElement, KeyType, ProvisionedThroughput, ScalarAttributeType,
+    };
+    use aws_sdk_dynamodb::Client;
+    use licenses_api_types::Seats;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use test_context::{test_context, AsyncTestContext};
+    use tokio::test as tokio_test;
+
+    struct DynamoDbTestContext {
+        client: Box<Client>,
+        dao: Box<ProductDao>,
+        table_name: String,
+    }
+
+    static NUMBER: AtomicUsize = AtomicUsize::new(0);
+
+    static PRODUCT_KEY_0: &str = "tides.2023";
+    static PRODUCT_KEY_1: &str = "weather.2022";
+    static PRODUCT_KEY_2: &str = "dummy";
+
+    #[async_trait]
+    impl AsyncTestContext for DynamoDbTestContext {
+        async fn setup() -> DynamoDbTestContext {
+            let table_name = format!("Products{}", NUMBER.fetch_add(1, Ordering::SeqCst));
+            let config = load_from_env().await;
+            let local_config = Builder::from(&config).endpoint_url("http://localhost:8000").build();
+            let client = Client::from_conf(local_config);
+
+            client
+                .create_table()
+                .table_name(table_name.as_str())
+                .attribute_definitions(
+                    AttributeDefinition::builder()
+                        .attribute_name("productKey")
+                        .attribute_type(ScalarAttributeType::S)
+                        .build(),
+                )
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("productKey")
+                        .key_type(KeyType::Hash)
+                        .build(),
+                )
+                .provisioned_throughput(
+                    ProvisionedThroughput::builder()
+                        .read_capacity_units(1000)
+                        .write_capacity_units(1000)
+                        .build(),
+                )
+                .send()
+                .await
+                .unwrap();
+
+            for product_key in [PRODUCT_KEY_1, PRODUCT_KEY_0] {
+                client
+                    .put_item()
+                    .table_name(table_name.as_str())
+                    .item("productKey", S(product_key.into()))
+                    .item("displayName", S(product_key.to_uppercase()))
+                    .send()
+                    .await
+                    .unwrap();
+            }
+
+            DynamoDbTestContext {
+                client: Box::new(client.clone()),
+                dao: Box::new(ProductDao::new(client, table_name.clone())),
+                table_name,
+            }
+        }
+
+        async fn teardown(self) {
+            self.client
+                .delete_table()
+                .table_name(self.table_name)
+                .send()
+                .await
+                .unwrap();
+        }
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn save_product(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        ctx.dao
+            .save_product(Product {
+                product_key: PRODUCT_KEY_2.into(),
+                display_name: "Dummy".into(),
+                default_count: Some(Seats::Limited(4)),
+                default_duration_days: Some(30),
+            })
+            .await?;
+
+        let product = ctx
+            .client
+            .get_item()
+            .table_name(ctx.table_name.as_str())
+            .key("productKey", S(PRODUCT_KEY_2.into()))
+            .send()
+            .await?
+            .item
+            .unwrap();
+        assert_eq!("Dummy", product["displayName"].as_s().unwrap());
+        assert_eq!("4", product["defaultCount"].as_n().unwrap());
+        assert_eq!("30", product["defaultDurationDays"].as_n().unwrap());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn list_products(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let products = ctx.dao.list_products().await?;
+
+        assert_eq!(2, products.len());
+        assert_eq!(PRODUCT_KEY_0, products[0].product_key);
+        assert_eq!(PRODUCT_KEY_1, products[1].product_key);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn get_product(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        let product = ctx.dao.get_product(PRODUCT_KEY_0.into()).await?.unwrap();
+
+        assert_eq!("TIDES.2023", product.display_name);
+        assert!(product.default_count.is_none());
+        assert!(product.default_duration_days.is_none());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn get_product_unexisting(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        assert!(ctx.dao.get_product(PRODUCT_KEY_2.into()).await?.is_none());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn delete_product(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        ctx.dao.delete_product(PRODUCT_KEY_0.into()).await?;
+
+        assert!(ctx.dao.get_product(PRODUCT_KEY_0.into()).await?.is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::model::Product;
+use crate::runtime_error::RuntimeError;
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait ProductRepository: Send + Sync {
+    async fn save_product(&self, product: Product) -> Result<(), RuntimeError>;
+
+    /**
+    Lists whole catalogue, ordered by product key.
+     */
+    async fn list_products(&self) -> Result<Vec<Product>, RuntimeError>;
+
+    async fn get_product(&self, product_key: String) -> Result<Option<Product>, RuntimeError>;
+
+    async fn delete_product(&self, product_key: String) -> Result<(), RuntimeError>;
+}
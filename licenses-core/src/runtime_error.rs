@@ -4,13 +4,19 @@
  * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
  */
 
+use aws_sdk_dynamodb::error::ProvideErrorMetadata;
 use aws_sdk_dynamodb::operation::delete_item::DeleteItemError;
 use aws_sdk_dynamodb::operation::get_item::GetItemError;
 use aws_sdk_dynamodb::operation::put_item::PutItemError;
 use aws_sdk_dynamodb::operation::query::QueryError;
-use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::operation::scan::ScanError;
+use aws_sdk_dynamodb::operation::transact_write_items::TransactWriteItemsError;
+use aws_sdk_dynamodb::types::{AttributeValue, CancellationReason};
 use aws_smithy_http::result::SdkError;
+use hmac::digest::InvalidLength;
+use licenses_api_types::ErrorCode;
 use serde_dynamo::Error as SerializationError;
+use serde_json::Error as JsonError;
 use std::env::VarError;
 use std::fmt::{Debug, Display, Formatter, Result};
 use thiserror::Error;
@@ -18,12 +24,17 @@ use thiserror::Error;
 #[derive(Error, Debug)]
 pub enum RuntimeError {
     ClientConfigLoadingError(VarError),
+    InvalidConfigError(String),
     DeleteItemError(#[from] SdkError<DeleteItemError>),
     GetItemError(#[from] SdkError<GetItemError>),
     PutItemError(#[from] SdkError<PutItemError>),
     QueryError(#[from] SdkError<QueryError>),
+    ScanError(#[from] SdkError<ScanError>),
+    TransactWriteItemsError(#[from] SdkError<TransactWriteItemsError>),
     DataError(AttributeValue, String),
     SerializationError(#[from] SerializationError),
+    JsonError(#[from] JsonError),
+    SecretError(#[from] InvalidLength),
 }
 
 impl Display for RuntimeError {
@@ -31,3 +42,173 @@ impl Display for RuntimeError {
         write!(formatter, "{self:?}")
     }
 }
+
+fn sdk_error_code<E: ProvideErrorMetadata>(error: &SdkError<E>) -> ErrorCode {
+    match error {
+        SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) => ErrorCode::ServiceUnavailable,
+        _ => match error.as_service_error().and_then(ProvideErrorMetadata::code) {
+            Some("ProvisionedThroughputExceededException" | "RequestLimitExceeded" | "ThrottlingException") => {
+                ErrorCode::Throttled
+            }
+            Some("InternalServerError" | "ServiceUnavailable" | "TransactionInProgressException") => {
+                ErrorCode::ServiceUnavailable
+            }
+            _ => ErrorCode::InternalError,
+        },
+    }
+}
+
+/**
+Cancelled transaction is only safe to retry when it lost a race or was throttled - failed condition won't hold on
+retry either.
+ */
+fn transaction_error_code(error: &SdkError<TransactWriteItemsError>) -> ErrorCode {
+    let Some(TransactWriteItemsError::TransactionCanceledException(exception)) = error.as_service_error() else {
+        return sdk_error_code(error);
+    };
+
+    if failed_conditions(error).map_or(false, |failed| failed.contains(&true)) {
+        return ErrorCode::Conflict;
+    }
+
+    let reasons = exception
+        .cancellation_reasons()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(CancellationReason::code)
+        .collect::<Vec<_>>();
+    if reasons.contains(&"TransactionConflict") {
+        ErrorCode::ServiceUnavailable
+    } else if reasons
+        .iter()
+        .any(|code| matches!(*code, "ProvisionedThroughputExceeded" | "ThrottlingError"))
+    {
+        ErrorCode::Throttled
+    } else {
+        ErrorCode::InternalError
+    }
+}
+
+/**
+Flags of transaction items that failed their conditions, in order of the items - `None` if transaction failed for
+any other reason.
+ */
+pub fn failed_conditions(error: &SdkError<TransactWriteItemsError>) -> Option<Vec<bool>> {
+    match error.as_service_error() {
+        Some(TransactWriteItemsError::TransactionCanceledException(exception)) => Some(
+            exception
+                .cancellation_reasons()
+                .unwrap_or_default()
+                .iter()
+                .map(|reason| reason.code() == Some("ConditionalCheckFailed"))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+impl RuntimeError {
+    /**
+    Public code of the failure - details of the runtime error itself are never exposed to callers.
+     */
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::DeleteItemError(error) => sdk_error_code(error),
+            Self::GetItemError(error) => sdk_error_code(error),
+            Self::PutItemError(error) => sdk_error_code(error),
+            Self::QueryError(error) => sdk_error_code(error),
+            Self::ScanError(error) => sdk_error_code(error),
+            Self::TransactWriteItemsError(error) => transaction_error_code(error),
+            _ => ErrorCode::InternalError,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RuntimeError;
+    use aws_sdk_dynamodb::operation::query::QueryError;
+    use aws_sdk_dynamodb::operation::scan::ScanError;
+    use aws_sdk_dynamodb::operation::transact_write_items::TransactWriteItemsError;
+    use aws_sdk_dynamodb::types::error::TransactionCanceledException;
+    use aws_sdk_dynamodb::types::CancellationReason;
+    use aws_smithy_http::body::SdkBody;
+    use aws_smithy_http::operation::Response;
+    use aws_smithy_http::result::SdkError;
+    use aws_smithy_types::error::ErrorMetadata;
+    use licenses_api_types::ErrorCode;
+    use std::io::{Error, ErrorKind};
+
+    fn service_error(code: &str) -> RuntimeError {
+        RuntimeError::QueryError(SdkError::service_error(
+            QueryError::generic(ErrorMetadata::builder().code(code).build()),
+            Response::new(http::Response::new(SdkBody::empty())),
+        ))
+    }
+
+    #[test]
+    fn throttled_code() {
+        assert_eq!(
+            ErrorCode::Throttled,
+            service_error("ProvisionedThroughputExceededException").code()
+        );
+    }
+
+    #[test]
+    fn unavailable_code() {
+        assert_eq!(
+            ErrorCode::ServiceUnavailable,
+            service_error("InternalServerError").code()
+        );
+    }
+
+    #[test]
+    fn timeout_code() {
+        let error = RuntimeError::QueryError(SdkError::timeout_error(Error::from(ErrorKind::TimedOut)));
+
+        assert_eq!(ErrorCode::ServiceUnavailable, error.code());
+    }
+
+    fn cancelled_transaction(reasons: &[&str]) -> RuntimeError {
+        let exception = reasons
+            .iter()
+            .fold(TransactionCanceledException::builder(), |builder, code| {
+                builder.cancellation_reasons(CancellationReason::builder().code(*code).build())
+            })
+            .build();
+
+        RuntimeError::TransactWriteItemsError(SdkError::service_error(
+            TransactWriteItemsError::TransactionCanceledException(exception),
+            Response::new(http::Response::new(SdkBody::empty())),
+        ))
+    }
+
+    #[test]
+    fn transaction_conflict_code() {
+        let error = cancelled_transaction(&["None", "TransactionConflict"]);
+
+        assert_eq!(ErrorCode::ServiceUnavailable, error.code());
+        assert!(error.code().is_retryable());
+    }
+
+    #[test]
+    fn transaction_throttled_code() {
+        assert_eq!(
+            ErrorCode::Throttled,
+            cancelled_transaction(&["ThrottlingError", "None"]).code()
+        );
+    }
+
+    #[test]
+    fn transaction_condition_failed_code() {
+        let error = cancelled_transaction(&["ConditionalCheckFailed", "TransactionConflict"]);
+
+        assert_eq!(ErrorCode::Conflict, error.code());
+        assert!(!error.code().is_retryable());
+    }
+
+    #[test]
+    fn internal_code() {
+        assert_eq!(ErrorCode::InternalError, service_error("ValidationException").code());
+    }
+}
@@ -0,0 +1,261 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::license_repository::LicenseRepository;
+use crate::model::{License, LicensesFilter};
+use crate::runtime_error::RuntimeError;
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use licenses_api_types::{Entitlement, Entitlements, Seats};
+use uuid::Uuid;
+
+// together exceed 1 MB - maximum amount of data returned by single DynamoDB query
+const LARGE_PAGE_SIZE: usize = 12;
+const LARGE_ITEM_SIZE: usize = 100_000;
+
+fn license(customer_id: Uuid, vessel_id: Uuid, license_key: &str, count: Option<Seats>) -> License {
+    License {
+        customer_id,
+        vessel_id,
+        license_key: license_key.into(),
+        count,
+        expires_at: None,
+        entitlements: Entitlements::new(),
+        trial: None,
+    }
+}
+
+/**
+Runs complete conformance suite against given repository.
+
+Every check works on own, randomly generated customer and vessel, so the suite can be run against shared storage.
+ */
+pub async fn check_all<R: LicenseRepository>(repository: &R) -> Result<(), RuntimeError> {
+    check_create_overwrites(repository).await?;
+    check_insert_keeps_existing(repository).await?;
+    check_pagination_ordering(repository).await?;
+    check_not_found(repository).await?;
+    check_expires_at_timezone(repository).await?;
+    check_large_page(repository).await
+}
+
+/**
+Creating license with existing key replaces previous state.
+ */
+pub async fn check_create_overwrites<R: LicenseRepository>(repository: &R) -> Result<(), RuntimeError> {
+    let (customer_id, vessel_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+    repository
+        .create_license(license(customer_id, vessel_id, "tides.2023", Some(Seats::Limited(1))))
+        .await?;
+    repository
+        .create_license(license(customer_id, vessel_id, "tides.2023", Some(Seats::Limited(2))))
+        .await?;
+
+    let license = repository
+        .get_license(customer_id, vessel_id, "tides.2023".into())
+        .await?;
+    assert_eq!(Some(Seats::Limited(2)), license.and_then(|license| license.count));
+
+    let page = repository
+        .list_licenses(customer_id, vessel_id, &LicensesFilter::default(), None, None)
+        .await?;
+    assert_eq!(1, page.items.len());
+
+    Ok(())
+}
+
+/**
+Inserting license with existing key keeps previous state.
+ */
+pub async fn check_insert_keeps_existing<R: LicenseRepository>(repository: &R) -> Result<(), RuntimeError> {
+    let (customer_id, vessel_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+    assert!(
+        repository
+            .insert_license(license(customer_id, vessel_id, "tides.2023", Some(Seats::Limited(1))))
+            .await?
+    );
+    assert!(
+        !repository
+            .insert_license(license(customer_id, vessel_id, "tides.2023", Some(Seats::Limited(2))))
+            .await?
+    );
+
+    let license = repository
+        .get_license(customer_id, vessel_id, "tides.2023".into())
+        .await?;
+    assert_eq!(Some(Seats::Limited(1)), license.and_then(|license| license.count));
+
+    Ok(())
+}
+
+/**
+Pages follow license keys order in both directions and together contain every license exactly once.
+ */
+pub async fn check_pagination_ordering<R: LicenseRepository>(repository: &R) -> Result<(), RuntimeError> {
+    let (customer_id, vessel_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let keys = ["weather.2022", "tides.2023", "dummy", "weather.2023", "routes"];
+    for key in keys {
+        repository
+            .create_license(license(customer_id, vessel_id, key, None))
+            .await?;
+    }
+
+    let mut expected = keys.map(String::from).to_vec();
+    expected.sort();
+
+    for descending in [false, true] {
+        let filter = LicensesFilter {
+            descending,
+            ..LicensesFilter::default()
+        };
+
+        let mut listed = vec![];
+        let mut page_key = None;
+        loop {
+            let page = repository
+                .list_licenses(customer_id, vessel_id, &filter, Some(2), page_key)
+                .await?;
+            assert!(page.items.len() <= 2);
+            listed.extend(page.items.into_iter().map(|license| license.license_key));
+
+            page_key = page.last_evaluated_key;
+            if page_key.is_none() {
+                break;
+            }
+        }
+
+        if descending {
+            expected.reverse();
+        }
+        assert_eq!(expected, listed);
+    }
+
+    Ok(())
+}
+
+/**
+Missing licenses are reported as empty results, not errors.
+ */
+pub async fn check_not_found<R: LicenseRepository>(repository: &R) -> Result<(), RuntimeError> {
+    let (customer_id, vessel_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+    assert!(repository
+        .get_license(customer_id, vessel_id, "tides.2023".into())
+        .await?
+        .is_none());
+    repository
+        .delete_license(customer_id, vessel_id, "tides.2023".into())
+        .await?;
+
+    let page = repository
+        .list_licenses(customer_id, vessel_id, &LicensesFilter::default(), None, None)
+        .await?;
+    assert!(page.items.is_empty());
+    assert!(page.last_evaluated_key.is_none());
+
+    Ok(())
+}
+
+/**
+Expiration date is returned in the same timezone in which it was saved.
+ */
+pub async fn check_expires_at_timezone<R: LicenseRepository>(repository: &R) -> Result<(), RuntimeError> {
+    let (customer_id, vessel_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let offset = FixedOffset::east_opt(5 * 3600 + 1800).unwrap();
+    let expires_at: DateTime<FixedOffset> = Utc
+        .with_ymd_and_hms(2099, 7, 2, 1, 20, 0)
+        .unwrap()
+        .with_timezone(&offset);
+
+    repository
+        .create_license(License {
+            expires_at: Some(expires_at),
+            ..license(customer_id, vessel_id, "tides.2099", None)
+        })
+        .await?;
+
+    let stored = repository
+        .get_license(customer_id, vessel_id, "tides.2099".into())
+        .await?
+        .and_then(|license| license.expires_at);
+    assert_eq!(Some(expires_at), stored);
+    assert_eq!(Some(offset), stored.map(|expires_at| *expires_at.offset()));
+
+    Ok(())
+}
+
+/**
+Pages bigger than the amount of data underlying storage returns at once are filled with subsequent reads, and
+listing without limit can still be continued until all licenses are returned.
+ */
+pub async fn check_large_page<R: LicenseRepository>(repository: &R) -> Result<(), RuntimeError> {
+    let (customer_id, vessel_id) = (Uuid::new_v4(), Uuid::new_v4());
+    for index in 0..LARGE_PAGE_SIZE {
+        repository
+            .create_license(License {
+                entitlements: Entitlements::from([(
+                    "notes".to_string(),
+                    Entitlement::Value("x".repeat(LARGE_ITEM_SIZE)),
+                )]),
+                ..license(
+                    customer_id,
+                    vessel_id,
+                    &format!("license.{index:04}"),
+                    Some(Seats::Limited(1)),
+                )
+            })
+            .await?;
+    }
+
+    let page = repository
+        .list_licenses(
+            customer_id,
+            vessel_id,
+            &LicensesFilter::default(),
+            Some(LARGE_PAGE_SIZE as u16),
+            None,
+        )
+        .await?;
+    assert_eq!(LARGE_PAGE_SIZE, page.items.len());
+    assert_eq!("license.0000", page.items[0].license_key);
+    assert_eq!(
+        format!("license.{:04}", LARGE_PAGE_SIZE - 1),
+        page.items[LARGE_PAGE_SIZE - 1].license_key
+    );
+
+    // storage may not know yet that the page was the last one
+    if let Some(page_key) = page.last_evaluated_key {
+        let next = repository
+            .list_licenses(
+                customer_id,
+                vessel_id,
+                &LicensesFilter::default(),
+                Some(LARGE_PAGE_SIZE as u16),
+                Some(page_key),
+            )
+            .await?;
+        assert!(next.items.is_empty());
+        assert!(next.last_evaluated_key.is_none());
+    }
+
+    let mut listed = 0;
+    let mut page_key = None;
+    loop {
+        let page = repository
+            .list_licenses(customer_id, vessel_id, &LicensesFilter::default(), None, page_key)
+            .await?;
+        listed += page.items.len();
+
+        page_key = page.last_evaluated_key;
+        if page_key.is_none() {
+            break;
+        }
+    }
+    assert_eq!(LARGE_PAGE_SIZE, listed);
+
+    Ok(())
+}
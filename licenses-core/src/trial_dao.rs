@@ -0,0 +1,439 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::license_dao::{
+    history_entry, key_of, license_item, load_client_from_env, retention_from_env, DEFAULT_RETENTION_DAYS,
+};
+use crate::model::{License, TrialRecord, TrialStart};
+use crate::runtime_error::{failed_conditions, RuntimeError};
+use crate::trial_repository::TrialRepository;
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue::S;
+use aws_sdk_dynamodb::types::{Put, TransactWriteItem, Update};
+use aws_sdk_dynamodb::Client;
+use chrono::{Duration, Utc};
+use licenses_api_types::Trial;
+use serde_dynamo::to_item;
+use std::env::var;
+use tracing::{Instrument, Span};
+use xray::aws_metadata;
+
+pub struct TrialDao {
+    client: Box<Client>,
+    trials_table_name: String,
+    licenses_table_name: String,
+    history_table_name: Option<String>,
+    retention: Duration,
+}
+
+/**
+Required environment variables:
+<dl>
+    <dt><code>TRIALS_TABLE</code></dt>
+    <dd>Name of DynamoDB trials history table.</dd>
+    <dt><code>LICENSES_TABLE</code></dt>
+    <dd>Name of DynamoDB licenses table.</dd>
+</dl>
+
+Optional environment variables:
+<dl>
+    <dt><code>LICENSE_RETENTION_DAYS</code></dt>
+    <dd>How long expired licenses are kept before DynamoDB removes them, by default <code>365</code> days.</dd>
+    <dt><code>LICENSE_HISTORY_TABLE</code></dt>
+    <dd>Name of DynamoDB license history table - without it changes are not recorded.</dd>
+</dl>
+ */
+impl TrialDao {
+    pub async fn load_from_env() -> Result<Self, RuntimeError> {
+        let client = load_client_from_env().await;
+
+        let dao = Self::new(
+            client,
+            var("TRIALS_TABLE").map_err(RuntimeError::ClientConfigLoadingError)?,
+            var("LICENSES_TABLE").map_err(RuntimeError::ClientConfigLoadingError)?,
+        )
+        .with_retention(retention_from_env()?);
+
+        Ok(match var("LICENSE_HISTORY_TABLE") {
+            Ok(history_table_name) => dao.with_history(history_table_name),
+            Err(_) => dao,
+        })
+    }
+
+    pub fn new(client: Client, trials_table_name: String, licenses_table_name: String) -> Self {
+        Self {
+            client: Box::new(client),
+            trials_table_name,
+            licenses_table_name,
+            history_table_name: None,
+            retention: Duration::days(DEFAULT_RETENTION_DAYS),
+        }
+    }
+
+    /**
+    Trial licenses expire and are removed as any other license.
+     */
+    pub fn with_retention(self, retention: Duration) -> Self {
+        Self { retention, ..self }
+    }
+
+    /**
+    Trial starts and conversions are recorded in license history table as new license states.
+     */
+    pub fn with_history(self, history_table_name: String) -> Self {
+        Self {
+            history_table_name: Some(history_table_name),
+            ..self
+        }
+    }
+
+    fn history_entry_of(&self, license: &License) -> Result<Option<TransactWriteItem>, RuntimeError> {
+        self.history_table_name
+            .as_deref()
+            .map(|table_name| {
+                history_entry(
+                    table_name,
+                    &license.customer_id,
+                    &license.vessel_id,
+                    &license.license_key,
+                    Some(license.clone()),
+                )
+            })
+            .transpose()
+    }
+
+    fn instrumentation(&self) -> Span {
+        aws_metadata(
+            self.client.conf().region().map(|value| value.to_string()).as_deref(),
+            Some(self.trials_table_name.as_str()),
+        )
+    }
+}
+
+#[async_trait]
+impl TrialRepository for TrialDao {
+    async fn start_trial(&self, mut license: License, trial: Trial) -> Result<TrialStart, RuntimeError> {
+        let key = key_of(&license.customer_id, &license.vessel_id);
+        let record = TrialRecord::of(&license, &trial);
+        license.trial = Some(trial);
+        let entry = self.history_entry_of(&license)?;
+
+        let mut request = self
+            .client
+            .transact_write_items()
+            .transact_items(
+                TransactWriteItem::builder()
+                    .put(
+                        Put::builder()
+                            .table_name(self.trials_table_name.as_str())
+                            .set_item(Some(to_item(record)?))
+                            .item("customerAndVesselId", S(key))
+                            .condition_expression("attribute_not_exists(licenseKey)")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .transact_items(
+                TransactWriteItem::builder()
+                    .put(
+                        Put::builder()
+                            .table_name(self.licenses_table_name.as_str())
+                            .set_item(Some(license_item(license, self.retention)?))
+                            .condition_expression("attribute_not_exists(licenseKey)")
+                            .build(),
+                    )
+                    .build(),
+            );
+        // history entry goes last, so indexes of failed conditions stay the same
+        if let Some(entry) = entry {
+            request = request.transact_items(entry);
+        }
+        let result = request.send().instrument(self.instrumentation()).await;
+
+        match result {
+            Ok(_) => Ok(TrialStart::Started),
+            Err(error) => match failed_conditions(&error).as_deref() {
+                Some([true, ..]) => Ok(TrialStart::AlreadyUsed),
+                Some([_, true, ..]) => Ok(TrialStart::LicenseExists),
+                _ => Err(error.into()),
+            },
+        }
+    }
+
+    /**
+    License replacement is conditioned on the stored license still being a trial, so concurrent conversions can not
+    overwrite each other.
+     */
+    async fn convert_trial(&self, license: License) -> Result<bool, RuntimeError> {
+        let key = key_of(&license.customer_id, &license.vessel_id);
+        let license_key = license.license_key.clone();
+        let entry = self.history_entry_of(&license)?;
+
+        let mut request = self
+            .client
+            .transact_write_items()
+            .transact_items(
+                TransactWriteItem::builder()
+                    .put(
+                        Put::builder()
+                            .table_name(self.licenses_table_name.as_str())
+                            .set_item(Some(license_item(license, self.retention)?))
+                            .condition_expression("attribute_exists(#trial)")
+                            .expression_attribute_names("#trial", "trial")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .transact_items(
+                TransactWriteItem::builder()
+                    .update(
+                        Update::builder()
+                            .table_name(self.trials_table_name.as_str())
+                            .key("customerAndVesselId", S(key))
+                            .key("licenseKey", S(license_key))
+                            .update_expression("SET convertedAt = :now")
+                            .expression_attribute_values(":now", S(Utc::now().to_rfc3339()))
+                            .build(),
+                    )
+                    .build(),
+            );
+        if let Some(entry) = entry {
+            request = request.transact_items(entry);
+        }
+        let result = request.send().instrument(self.instrumentation()).await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(error) if failed_conditions(&error).map_or(false, |failed| failed.contains(&true)) => Ok(false),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::license_dao::key_of;
+    use crate::{License, LicenseDao, LicenseRepository, RuntimeError, TrialDao, TrialRepository, TrialStart};
+    use async_trait::async_trait;
+    use aws_config::load_from_env;
+    use aws_sdk_dynamodb::config::Builder;
+    use aws_sdk_dynamodb::types::{
+        AttributeDefinition, AttributeValue::S, KeySchemaElement, KeyType, ProvisionedThroughput, ScalarAttributeType,
+    };
+    use aws_sdk_dynamodb::Client;
+    use chrono::{Duration, Utc};
+    use licenses_api_types::{Entitlements, Seats, Trial};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use test_context::{test_context, AsyncTestContext};
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    struct DynamoDbTestContext {
+        client: Box<Client>,
+        dao: Box<TrialDao>,
+        licenses: Box<LicenseDao>,
+        trials_table_name: String,
+        licenses_table_name: String,
+    }
+
+    static NUMBER: AtomicUsize = AtomicUsize::new(0);
+
+    // customers
+    static ID_0: Uuid = uuid!("00000000-0000-0000-0000-000000000000");
+    // vessels
+    static ID_1: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    // licenses
+    static LICENSE_KEY_0: &str = "tides.2023";
+
+    async fn create_table(client: &Client, table_name: &str) {
+        client
+            .create_table()
+            .table_name(table_name)
+            .attribute_definitions(
+                AttributeDefinition::builder()
+                    .attribute_name("customerAndVesselId")
+                    .attribute_type(ScalarAttributeType::S)
+                    .build(),
+            )
+            .attribute_definitions(
+                AttributeDefinition::builder()
+                    .attribute_name("licenseKey")
+                    .attribute_type(ScalarAttributeType::S)
+                    .build(),
+            )
+            .key_schema(
+                KeySchemaElement::builder()
+                    .attribute_name("customerAndVesselId")
+                    .key_type(KeyType::Hash)
+                    .build(),
+            )
+            .key_schema(
+                KeySchemaElement::builder()
+                    .attribute_name("licenseKey")
+                    .key_type(KeyType::Range)
+                    .build(),
+            )
+            .provisioned_throughput(
+                ProvisionedThroughput::builder()
+                    .read_capacity_units(1000)
+                    .write_capacity_units(1000)
+                    .build(),
+            )
+            .send()
+            .await
+            .unwrap();
+    }
+
+    fn license() -> License {
+        License {
+            customer_id: ID_0,
+            vessel_id: ID_1,
+            license_key: LICENSE_KEY_0.into(),
+            count: Some(Seats::Limited(1)),
+            expires_at: Some((Utc::now() + Duration::days(30)).into()),
+            entitlements: Entitlements::new(),
+            trial: None,
+        }
+    }
+
+    fn trial() -> Trial {
+        Trial {
+            origin: "web".into(),
+            started_at: Utc::now().into(),
+        }
+    }
+
+    #[async_trait]
+    impl AsyncTestContext for DynamoDbTestContext {
+        async fn setup() -> DynamoDbTestContext {
+            let number = NUMBER.fetch_add(1, Ordering::SeqCst);
+            let trials_table_name = format!("Trials{number}");
+            let licenses_table_name = format!("TrialLicenses{number}");
+            let config = load_from_env().await;
+            let local_config = Builder::from(&config).endpoint_url("http://localhost:8000").build();
+            let client = Client::from_conf(local_config);
+
+            create_table(&client, trials_table_name.as_str()).await;
+            create_table(&client, licenses_table_name.as_str()).await;
+
+            DynamoDbTestContext {
+                client: Box::new(client.clone()),
+                dao: Box::new(TrialDao::new(
+                    client.clone(),
+                    trials_table_name.clone(),
+                    licenses_table_name.clone(),
+                )),
+                licenses: Box::new(LicenseDao::new(client, licenses_table_name.clone())),
+                trials_table_name,
+                licenses_table_name,
+            }
+        }
+
+        async fn teardown(self) {
+            for table_name in [self.trials_table_name, self.licenses_table_name] {
+                self.client.delete_table().table_name(table_name).send().await.unwrap();
+            }
+        }
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn start_trial(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        assert_eq!(TrialStart::Started, ctx.dao.start_trial(license(), trial()).await?);
+
+        let stored = ctx
+            .licenses
+            .get_license(ID_0, ID_1, LICENSE_KEY_0.into())
+            .await?
+            .unwrap();
+        assert_eq!("web", stored.trial.unwrap().origin);
+
+        let item = ctx
+            .client
+            .get_item()
+            .table_name(ctx.licenses_table_name.as_str())
+            .key("customerAndVesselId", S(key_of(&ID_0, &ID_1)))
+            .key("licenseKey", S(LICENSE_KEY_0.into()))
+            .send()
+            .await?
+            .item
+            .unwrap();
+        assert!(item.contains_key("ttl"));
+
+        ctx.licenses.delete_license(ID_0, ID_1, LICENSE_KEY_0.into()).await?;
+        assert_eq!(TrialStart::AlreadyUsed, ctx.dao.start_trial(license(), trial()).await?);
+
+        let record = ctx
+            .client
+            .get_item()
+            .table_name(ctx.trials_table_name.as_str())
+            .key("customerAndVesselId", S(key_of(&ID_0, &ID_1)))
+            .key("licenseKey", S(LICENSE_KEY_0.into()))
+            .send()
+            .await?
+            .item
+            .unwrap();
+        assert_eq!("web", record["origin"].as_s().unwrap());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn start_trial_with_existing_license(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        ctx.licenses.create_license(license()).await?;
+
+        assert_eq!(
+            TrialStart::LicenseExists,
+            ctx.dao.start_trial(license(), trial()).await?
+        );
+        assert!(ctx
+            .licenses
+            .get_license(ID_0, ID_1, LICENSE_KEY_0.into())
+            .await?
+            .unwrap()
+            .trial
+            .is_none());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn convert_trial(ctx: &DynamoDbTestContext) -> Result<(), RuntimeError> {
+        ctx.dao.start_trial(license(), trial()).await?;
+
+        let paid = License {
+            expires_at: None,
+            ..license()
+        };
+        assert!(ctx.dao.convert_trial(paid.clone()).await?);
+        assert!(!ctx.dao.convert_trial(paid).await?);
+
+        let stored = ctx
+            .licenses
+            .get_license(ID_0, ID_1, LICENSE_KEY_0.into())
+            .await?
+            .unwrap();
+        assert!(stored.trial.is_none());
+        assert!(stored.expires_at.is_none());
+        assert_eq!(Some(Seats::Limited(1)), stored.count);
+
+        let record = ctx
+            .client
+            .get_item()
+            .table_name(ctx.trials_table_name.as_str())
+            .key("customerAndVesselId", S(key_of(&ID_0, &ID_1)))
+            .key("licenseKey", S(LICENSE_KEY_0.into()))
+            .send()
+            .await?
+            .item
+            .unwrap();
+        assert!(record.contains_key("convertedAt"));
+
+        Ok(())
+    }
+}
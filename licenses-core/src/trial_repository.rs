@@ -0,0 +1,25 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::model::{License, TrialStart};
+use crate::runtime_error::RuntimeError;
+use async_trait::async_trait;
+use licenses_api_types::Trial;
+
+#[async_trait]
+pub trait TrialRepository: Send + Sync {
+    /**
+    Atomically records trial in the history and creates the license marked as trial - nothing is stored if the vessel
+    already had trial of the product or already has its license.
+     */
+    async fn start_trial(&self, license: License, trial: Trial) -> Result<TrialStart, RuntimeError>;
+
+    /**
+    Replaces trial license with the given paid one and marks history entry as converted - returns `false` if the
+    license is no longer a trial.
+     */
+    async fn convert_trial(&self, license: License) -> Result<bool, RuntimeError>;
+}
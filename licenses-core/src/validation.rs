@@ -0,0 +1,678 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::api_error::ApiError;
+use crate::runtime_error::RuntimeError;
+use chrono::{DateTime, FixedOffset, Utc};
+use licenses_api_types::{
+    AllocateSeatsRequest, ConvertTrialRequest, CreateLicenseRequest, CreatePoolRequest, EntitlementsRequest,
+    LicenseRequest, ListLicensesRequest, ListPoolsRequest, ProductRequest, ReleaseSeatsRequest, SaveProductRequest,
+    Seats, StartTrialRequest, SummaryRequest,
+};
+use std::collections::BTreeSet;
+use std::env::var;
+use uuid::Uuid;
+
+pub const DEFAULT_LICENSE_KEY_MAX_LENGTH: usize = 64;
+pub const DEFAULT_LICENSE_KEY_CHARACTERS: &str = ".-_";
+
+// separates license key from version in history sort keys, so it can never be part of the key itself
+const RESERVED_CHARACTER: char = '#';
+
+#[derive(PartialEq, Debug)]
+#[doc = "Single violated constraint."]
+pub struct FieldError {
+    #[doc = "Name of the invalid field, as in the request payload."]
+    pub field: String,
+    #[doc = "Description of the violation."]
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[doc = "Constraints of license keys."]
+pub struct ValidationRules {
+    #[doc = "Maximum length of license key."]
+    pub license_key_max_length: usize,
+    #[doc = "Characters allowed in license key, apart from ASCII letters and digits."]
+    pub license_key_characters: String,
+    #[doc = "Registered license keys - any key is accepted if not set."]
+    pub products: Option<BTreeSet<String>>,
+}
+
+impl Default for ValidationRules {
+    fn default() -> Self {
+        Self {
+            license_key_max_length: DEFAULT_LICENSE_KEY_MAX_LENGTH,
+            license_key_characters: DEFAULT_LICENSE_KEY_CHARACTERS.into(),
+            products: None,
+        }
+    }
+}
+
+/**
+Optional environment variables:
+<dl>
+    <dt><code>LICENSE_KEY_MAX_LENGTH</code></dt>
+    <dd>Maximum length of license key, by default <code>64</code>.</dd>
+    <dt><code>LICENSE_KEY_CHARACTERS</code></dt>
+    <dd>Characters allowed in license key apart from ASCII letters and digits, by default <code>.-_</code> -
+    <code>#</code> is always rejected.</dd>
+    <dt><code>LICENSE_PRODUCTS</code></dt>
+    <dd>Comma-separated list of registered license keys - when set, other keys are rejected.</dd>
+</dl>
+ */
+impl ValidationRules {
+    pub fn load_from_env() -> Result<Self, RuntimeError> {
+        let defaults = Self::default();
+
+        Ok(Self {
+            license_key_max_length: match var("LICENSE_KEY_MAX_LENGTH") {
+                Ok(value) => value
+                    .parse()
+                    .map_err(|_| RuntimeError::InvalidConfigError(format!("LICENSE_KEY_MAX_LENGTH={value}")))?,
+                Err(_) => defaults.license_key_max_length,
+            },
+            license_key_characters: var("LICENSE_KEY_CHARACTERS").unwrap_or(defaults.license_key_characters),
+            products: var("LICENSE_PRODUCTS").ok().map(|products| {
+                products
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|product| !product.is_empty())
+                    .map(String::from)
+                    .collect()
+            }),
+        })
+    }
+
+    fn check_characters(&self, field: &str, value: &str, errors: &mut Vec<FieldError>) {
+        if value.chars().count() > self.license_key_max_length {
+            errors.push(FieldError::new(
+                field,
+                format!("must be at most {} characters long", self.license_key_max_length),
+            ));
+        }
+
+        if value.contains(RESERVED_CHARACTER) {
+            errors.push(FieldError::new(
+                field,
+                format!("must not contain \"{RESERVED_CHARACTER}\""),
+            ));
+        }
+
+        if value.chars().any(|character| {
+            !character.is_ascii_alphanumeric()
+                && character != RESERVED_CHARACTER
+                && !self.license_key_characters.contains(character)
+        }) {
+            errors.push(FieldError::new(
+                field,
+                format!(
+                    "may only contain ASCII letters, digits and \"{}\"",
+                    self.license_key_characters
+                ),
+            ));
+        }
+    }
+
+    fn check_license_key(&self, field: &str, value: &str, errors: &mut Vec<FieldError>) {
+        if value.is_empty() {
+            errors.push(FieldError::new(field, "must not be empty"));
+            return;
+        }
+
+        self.check_characters(field, value, errors);
+
+        if let Some(products) = &self.products {
+            if !products.contains(value) {
+                errors.push(FieldError::new(field, "is not a registered product"));
+            }
+        }
+    }
+}
+
+fn check_key(rules: &ValidationRules, field: &str, value: &str, errors: &mut Vec<FieldError>) {
+    if value.is_empty() {
+        errors.push(FieldError::new(field, "must not be empty"));
+    } else {
+        rules.check_characters(field, value, errors);
+    }
+}
+
+// existing rows may predate the charset and length rules, so lookups only reject what can never be stored
+fn check_lookup_key(field: &str, value: &str, errors: &mut Vec<FieldError>) {
+    if value.is_empty() {
+        errors.push(FieldError::new(field, "must not be empty"));
+    } else if value.contains(RESERVED_CHARACTER) {
+        errors.push(FieldError::new(
+            field,
+            format!("must not contain \"{RESERVED_CHARACTER}\""),
+        ));
+    }
+}
+
+fn check_id(field: &str, value: &Uuid, errors: &mut Vec<FieldError>) {
+    if value.is_nil() {
+        errors.push(FieldError::new(field, "must not be nil UUID"));
+    }
+}
+
+fn check_as_of(value: Option<DateTime<FixedOffset>>, errors: &mut Vec<FieldError>) {
+    if value.map_or(false, |as_of| as_of > Utc::now()) {
+        errors.push(FieldError::new("asOf", "must not be in the future"));
+    }
+}
+
+pub trait Validate {
+    /**
+    Collects all constraint violations of the request.
+     */
+    fn violations(&self, rules: &ValidationRules) -> Vec<FieldError>;
+
+    fn validate(&self, rules: &ValidationRules) -> Result<(), ApiError> {
+        let errors = self.violations(rules);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::ValidationFailed(errors))
+        }
+    }
+}
+
+impl Validate for CreateLicenseRequest {
+    fn violations(&self, rules: &ValidationRules) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        check_id("customerId", &self.customer_id, &mut errors);
+        check_id("vesselId", &self.vessel_id, &mut errors);
+        rules.check_license_key("licenseKey", &self.license_key, &mut errors);
+
+        if self.count == Some(Seats::Limited(0)) {
+            errors.push(FieldError::new("count", "must be positive"));
+        }
+
+        if let Some(expires_at) = self.expires_at {
+            if expires_at < Utc::now() {
+                errors.push(FieldError::new("expiresAt", "must not be in the past"));
+            }
+        }
+
+        for name in self.entitlements.keys() {
+            check_key(rules, "entitlements", name, &mut errors);
+        }
+
+        errors
+    }
+}
+
+impl Validate for LicenseRequest {
+    fn violations(&self, _rules: &ValidationRules) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        check_id("customerId", &self.customer_id, &mut errors);
+        check_id("vesselId", &self.vessel_id, &mut errors);
+
+        // product list is not enforced here - licenses of withdrawn products must remain accessible
+        check_lookup_key("licenseKey", &self.license_key, &mut errors);
+        check_as_of(self.as_of, &mut errors);
+
+        errors
+    }
+}
+
+impl Validate for ListLicensesRequest {
+    fn violations(&self, rules: &ValidationRules) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        check_id("customerId", &self.customer_id, &mut errors);
+        check_id("vesselId", &self.vessel_id, &mut errors);
+
+        if self.limit == Some(0) {
+            errors.push(FieldError::new("limit", "must be positive"));
+        }
+
+        if let Some(prefix) = &self.license_key_prefix {
+            rules.check_characters("licenseKeyPrefix", prefix, &mut errors);
+        }
+
+        if self.expired_only && self.active_only {
+            errors.push(FieldError::new("activeOnly", "can not be combined with expiredOnly"));
+        }
+
+        check_as_of(self.as_of, &mut errors);
+
+        errors
+    }
+}
+
+impl Validate for SummaryRequest {
+    fn violations(&self, _rules: &ValidationRules) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        check_id("customerId", &self.customer_id, &mut errors);
+        if let Some(vessel_id) = &self.vessel_id {
+            check_id("vesselId", vessel_id, &mut errors);
+        }
+
+        errors
+    }
+}
+
+impl Validate for EntitlementsRequest {
+    fn violations(&self, _rules: &ValidationRules) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        check_id("customerId", &self.customer_id, &mut errors);
+        check_id("vesselId", &self.vessel_id, &mut errors);
+
+        errors
+    }
+}
+
+impl Validate for SaveProductRequest {
+    fn violations(&self, rules: &ValidationRules) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        check_key(rules, "productKey", &self.product_key, &mut errors);
+
+        if self.display_name.trim().is_empty() {
+            errors.push(FieldError::new("displayName", "must not be empty"));
+        }
+
+        if self.default_count == Some(Seats::Limited(0)) {
+            errors.push(FieldError::new("defaultCount", "must be positive"));
+        }
+
+        if self.default_duration_days == Some(0) {
+            errors.push(FieldError::new("defaultDurationDays", "must be positive"));
+        }
+
+        errors
+    }
+}
+
+impl Validate for ProductRequest {
+    fn violations(&self, rules: &ValidationRules) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        check_key(rules, "productKey", &self.product_key, &mut errors);
+
+        errors
+    }
+}
+
+impl Validate for CreatePoolRequest {
+    fn violations(&self, rules: &ValidationRules) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        check_id("customerId", &self.customer_id, &mut errors);
+        rules.check_license_key("productKey", &self.product_key, &mut errors);
+
+        if self.total_seats == 0 {
+            errors.push(FieldError::new("totalSeats", "must be positive"));
+        }
+
+        if let Some(expires_at) = self.expires_at {
+            if expires_at < Utc::now() {
+                errors.push(FieldError::new("expiresAt", "must not be in the past"));
+            }
+        }
+
+        errors
+    }
+}
+
+impl Validate for ListPoolsRequest {
+    fn violations(&self, _rules: &ValidationRules) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        check_id("customerId", &self.customer_id, &mut errors);
+
+        errors
+    }
+}
+
+impl Validate for AllocateSeatsRequest {
+    fn violations(&self, rules: &ValidationRules) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        check_id("customerId", &self.customer_id, &mut errors);
+        check_key(rules, "productKey", &self.product_key, &mut errors);
+        check_id("vesselId", &self.vessel_id, &mut errors);
+
+        if self.seats == 0 {
+            errors.push(FieldError::new("seats", "must be positive"));
+        }
+
+        errors
+    }
+}
+
+impl Validate for ReleaseSeatsRequest {
+    fn violations(&self, rules: &ValidationRules) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        check_id("customerId", &self.customer_id, &mut errors);
+        check_key(rules, "productKey", &self.product_key, &mut errors);
+        check_id("vesselId", &self.vessel_id, &mut errors);
+
+        errors
+    }
+}
+
+impl Validate for StartTrialRequest {
+    fn violations(&self, rules: &ValidationRules) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        check_id("customerId", &self.customer_id, &mut errors);
+        check_id("vesselId", &self.vessel_id, &mut errors);
+        rules.check_license_key("licenseKey", &self.license_key, &mut errors);
+
+        if self.origin.trim().is_empty() {
+            errors.push(FieldError::new("origin", "must not be empty"));
+        }
+
+        errors
+    }
+}
+
+impl Validate for ConvertTrialRequest {
+    fn violations(&self, rules: &ValidationRules) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        check_id("customerId", &self.customer_id, &mut errors);
+        check_id("vesselId", &self.vessel_id, &mut errors);
+        check_key(rules, "licenseKey", &self.license_key, &mut errors);
+
+        if let Some(expires_at) = self.expires_at {
+            if expires_at < Utc::now() {
+                errors.push(FieldError::new("expiresAt", "must not be in the past"));
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ApiError, FieldError, Validate, ValidationRules};
+    use chrono::{Duration, Utc};
+    use licenses_api_types::{
+        AllocateSeatsRequest, CreateLicenseRequest, CreatePoolRequest, Entitlement, Entitlements, LicenseRequest,
+        ListLicensesRequest, ProductRequest, SaveProductRequest, Seats, StartTrialRequest, SummaryRequest,
+    };
+    use std::collections::BTreeSet;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    const LICENSE_KEY: &str = "tides.2023";
+
+    fn create_request(license_key: &str) -> CreateLicenseRequest {
+        CreateLicenseRequest {
+            customer_id: CUSTOMER_ID,
+            vessel_id: VESSEL_ID,
+            license_key: license_key.into(),
+            count: Some(Seats::Limited(2)),
+            expires_at: Some((Utc::now() + Duration::days(1)).into()),
+            entitlements: Entitlements::new(),
+        }
+    }
+
+    fn fields(errors: Vec<FieldError>) -> Vec<String> {
+        errors.into_iter().map(|error| error.field).collect()
+    }
+
+    #[test]
+    fn valid_create_request() {
+        create_request(LICENSE_KEY)
+            .validate(&ValidationRules::default())
+            .unwrap();
+    }
+
+    #[test]
+    fn invalid_create_request() {
+        let request = CreateLicenseRequest {
+            customer_id: Uuid::nil(),
+            vessel_id: Uuid::nil(),
+            license_key: "".into(),
+            count: Some(Seats::Limited(0)),
+            expires_at: Some((Utc::now() - Duration::days(1)).into()),
+            entitlements: Entitlements::new(),
+        };
+
+        match request.validate(&ValidationRules::default()) {
+            Err(ApiError::ValidationFailed(errors)) => assert_eq!(
+                vec!["customerId", "vesselId", "licenseKey", "count", "expiresAt"],
+                fields(errors)
+            ),
+            _ => panic!("Validation error expected."),
+        }
+    }
+
+    #[test]
+    fn invalid_entitlement_name() {
+        let request = CreateLicenseRequest {
+            entitlements: Entitlements::from([
+                ("maxRoutes".to_string(), Entitlement::Limit(5)),
+                ("".to_string(), Entitlement::Flag(true)),
+            ]),
+            ..create_request(LICENSE_KEY)
+        };
+
+        assert_eq!(
+            vec![FieldError::new("entitlements", "must not be empty")],
+            request.violations(&ValidationRules::default())
+        );
+    }
+
+    #[test]
+    fn license_key_too_long() {
+        let errors = create_request(&"a".repeat(2048)).violations(&ValidationRules::default());
+
+        assert_eq!(
+            vec![FieldError::new("licenseKey", "must be at most 64 characters long")],
+            errors
+        );
+    }
+
+    #[test]
+    fn license_key_control_characters() {
+        let errors = create_request("tides\n2023").violations(&ValidationRules::default());
+
+        assert_eq!(vec!["licenseKey"], fields(errors));
+    }
+
+    #[test]
+    fn license_key_custom_characters() {
+        let rules = ValidationRules {
+            license_key_characters: ":".into(),
+            ..ValidationRules::default()
+        };
+
+        assert!(create_request("tides:2023").violations(&rules).is_empty());
+        assert_eq!(
+            vec!["licenseKey"],
+            fields(create_request(LICENSE_KEY).violations(&rules))
+        );
+    }
+
+    #[test]
+    fn license_key_reserved_character() {
+        let rules = ValidationRules {
+            license_key_characters: "#".into(),
+            ..ValidationRules::default()
+        };
+
+        assert_eq!(
+            vec![FieldError::new("licenseKey", "must not contain \"#\"")],
+            create_request("tides#2023").violations(&rules)
+        );
+    }
+
+    #[test]
+    fn license_key_reserved_character_not_allowed() {
+        assert_eq!(
+            vec![FieldError::new("licenseKey", "must not contain \"#\"")],
+            create_request("tides#2023").violations(&ValidationRules::default())
+        );
+    }
+
+    #[test]
+    fn license_lookup_legacy_key() {
+        let request = LicenseRequest {
+            customer_id: CUSTOMER_ID,
+            vessel_id: VESSEL_ID,
+            license_key: format!("tides 2023/{}", "a".repeat(128)),
+            as_of: None,
+        };
+
+        assert!(request.violations(&ValidationRules::default()).is_empty());
+    }
+
+    #[test]
+    fn license_lookup_reserved_character() {
+        let request = LicenseRequest {
+            customer_id: CUSTOMER_ID,
+            vessel_id: VESSEL_ID,
+            license_key: "tides#2023".into(),
+            as_of: None,
+        };
+
+        assert_eq!(
+            vec![FieldError::new("licenseKey", "must not contain \"#\"")],
+            request.violations(&ValidationRules::default())
+        );
+    }
+
+    #[test]
+    fn license_key_products() {
+        let rules = ValidationRules {
+            products: Some(BTreeSet::from([LICENSE_KEY.to_string()])),
+            ..ValidationRules::default()
+        };
+
+        assert!(create_request(LICENSE_KEY).violations(&rules).is_empty());
+        assert_eq!(
+            vec![FieldError::new("licenseKey", "is not a registered product")],
+            create_request("weather.2022").violations(&rules)
+        );
+
+        // existing licenses can still be accessed
+        assert!(LicenseRequest {
+            customer_id: CUSTOMER_ID,
+            vessel_id: VESSEL_ID,
+            license_key: "weather.2022".into(),
+            as_of: None,
+        }
+        .violations(&rules)
+        .is_empty());
+    }
+
+    #[test]
+    fn invalid_list_request() {
+        let request = ListLicensesRequest {
+            customer_id: CUSTOMER_ID,
+            vessel_id: Uuid::nil(),
+            page_token: None,
+            limit: Some(0),
+            license_key_prefix: Some("tides\0".into()),
+            expired_only: true,
+            active_only: true,
+            descending: false,
+            as_of: Some((Utc::now() + Duration::days(1)).into()),
+        };
+
+        assert_eq!(
+            vec!["vesselId", "limit", "licenseKeyPrefix", "activeOnly", "asOf"],
+            fields(request.violations(&ValidationRules::default()))
+        );
+    }
+
+    #[test]
+    fn invalid_summary_request() {
+        let request = SummaryRequest {
+            customer_id: CUSTOMER_ID,
+            vessel_id: Some(Uuid::nil()),
+            expiring_within_days: 30,
+        };
+
+        assert_eq!(
+            vec!["vesselId"],
+            fields(request.violations(&ValidationRules::default()))
+        );
+    }
+
+    #[test]
+    fn invalid_save_product_request() {
+        let request = SaveProductRequest {
+            product_key: "tides 2023".into(),
+            display_name: " ".into(),
+            default_count: Some(Seats::Limited(0)),
+            default_duration_days: Some(0),
+        };
+
+        assert_eq!(
+            vec!["productKey", "displayName", "defaultCount", "defaultDurationDays"],
+            fields(request.violations(&ValidationRules::default()))
+        );
+    }
+
+    #[test]
+    fn invalid_create_pool_request() {
+        let request = CreatePoolRequest {
+            customer_id: Uuid::nil(),
+            product_key: LICENSE_KEY.into(),
+            total_seats: 0,
+            expires_at: Some((Utc::now() - Duration::days(1)).into()),
+        };
+
+        assert_eq!(
+            vec!["customerId", "totalSeats", "expiresAt"],
+            fields(request.violations(&ValidationRules::default()))
+        );
+    }
+
+    #[test]
+    fn invalid_allocate_seats_request() {
+        let request = AllocateSeatsRequest {
+            customer_id: CUSTOMER_ID,
+            product_key: "".into(),
+            vessel_id: Uuid::nil(),
+            seats: 0,
+        };
+
+        assert_eq!(
+            vec!["productKey", "vesselId", "seats"],
+            fields(request.violations(&ValidationRules::default()))
+        );
+    }
+
+    #[test]
+    fn invalid_start_trial_request() {
+        let request = StartTrialRequest {
+            customer_id: CUSTOMER_ID,
+            vessel_id: Uuid::nil(),
+            license_key: LICENSE_KEY.into(),
+            origin: " ".into(),
+        };
+
+        assert_eq!(
+            vec!["vesselId", "origin"],
+            fields(request.violations(&ValidationRules::default()))
+        );
+    }
+}
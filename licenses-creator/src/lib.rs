@@ -0,0 +1,162 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use chrono::Utc;
+use licenses_api_types::CreateLicenseRequest;
+use licenses_core::{ApiError, FieldError, License, LicenseRepository, ProductRepository, Validate, ValidationRules};
+
+/**
+Builds license out of the creation request - license key must be registered in product catalogue, which also
+provides defaults for properties not specified in the request.
+ */
+pub async fn license_of<P: ProductRepository>(
+    products: &P,
+    rules: &ValidationRules,
+    request: CreateLicenseRequest,
+) -> Result<License, ApiError> {
+    request.validate(rules)?;
+
+    let product = products
+        .get_product(request.license_key.clone())
+        .await?
+        .ok_or_else(|| {
+            ApiError::ValidationFailed(vec![FieldError::new("licenseKey", "is not a registered product")])
+        })?;
+
+    let mut license = License::from(request);
+    product.apply_defaults(&mut license, &Utc::now());
+    Ok(license)
+}
+
+pub async fn handler<R: LicenseRepository, P: ProductRepository>(
+    repository: &R,
+    products: &P,
+    rules: &ValidationRules,
+    request: CreateLicenseRequest,
+) -> Result<String, ApiError> {
+    let license = license_of(products, rules, request).await?;
+
+    let license_key = license.license_key.clone();
+    if !repository.create_license(license).await? {
+        return Err(ApiError::PooledLicense(license_key));
+    }
+
+    Ok(license_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handler;
+    use chrono::Utc;
+    use licenses_api_types::{CreateLicenseRequest, Entitlements, Seats};
+    use licenses_core::{
+        ApiError, InMemoryLicenseRepository, InMemoryProductRepository, LicenseRepository, Product, ValidationRules,
+    };
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    const LICENSE_KEY: &str = "weather0";
+    const COUNT: Seats = Seats::Limited(2);
+
+    fn products() -> InMemoryProductRepository {
+        InMemoryProductRepository::from([Product {
+            product_key: LICENSE_KEY.to_string(),
+            display_name: "Weather".to_string(),
+            default_count: Some(Seats::Limited(5)),
+            default_duration_days: Some(365),
+        }])
+    }
+
+    fn request(license_key: &str, count: Option<Seats>) -> CreateLicenseRequest {
+        CreateLicenseRequest {
+            customer_id: CUSTOMER_ID,
+            vessel_id: VESSEL_ID,
+            license_key: license_key.to_string(),
+            count,
+            expires_at: None,
+            entitlements: Entitlements::new(),
+        }
+    }
+
+    async fn assert_rejected(license_key: &str) {
+        let repository = InMemoryLicenseRepository::new();
+
+        let result = handler(
+            &repository,
+            &products(),
+            &ValidationRules::default(),
+            request(license_key, Some(COUNT)),
+        )
+        .await;
+
+        match result {
+            Err(ApiError::ValidationFailed(errors)) => assert_eq!("licenseKey", errors[0].field),
+            _ => panic!("Validation error expected."),
+        }
+        assert!(repository
+            .list_licenses(CUSTOMER_ID, VESSEL_ID, &Default::default(), None, None)
+            .await
+            .unwrap()
+            .items
+            .is_empty());
+    }
+
+    #[tokio_test]
+    async fn create_license() {
+        let repository = InMemoryLicenseRepository::new();
+
+        let license_key = handler(
+            &repository,
+            &products(),
+            &ValidationRules::default(),
+            request(LICENSE_KEY, Some(COUNT)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(LICENSE_KEY, license_key);
+
+        let license = repository
+            .get_license(CUSTOMER_ID, VESSEL_ID, LICENSE_KEY.to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some(COUNT), license.count);
+    }
+
+    #[tokio_test]
+    async fn create_license_defaults() {
+        let repository = InMemoryLicenseRepository::new();
+
+        handler(
+            &repository,
+            &products(),
+            &ValidationRules::default(),
+            request(LICENSE_KEY, None),
+        )
+        .await
+        .unwrap();
+
+        let license = repository
+            .get_license(CUSTOMER_ID, VESSEL_ID, LICENSE_KEY.to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some(Seats::Limited(5)), license.count);
+        assert!(license.expires_at.unwrap() > Utc::now() + chrono::Duration::days(364));
+    }
+
+    #[tokio_test]
+    async fn create_license_invalid() {
+        assert_rejected("weather\n0").await;
+    }
+
+    #[tokio_test]
+    async fn create_license_unknown_product() {
+        assert_rejected("weather1").await;
+    }
+}
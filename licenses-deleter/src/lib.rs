@@ -0,0 +1,145 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use licenses_api_types::LicenseRequest;
+use licenses_core::{ApiError, FieldError, LicenseRepository, Validate, ValidationRules};
+
+pub async fn handler<R: LicenseRepository>(
+    repository: &R,
+    rules: &ValidationRules,
+    request: LicenseRequest,
+) -> Result<(), ApiError> {
+    request.validate(rules)?;
+
+    // past states are read-only
+    if request.as_of.is_some() {
+        return Err(ApiError::ValidationFailed(vec![FieldError::new(
+            "asOf",
+            "is not supported for deletion",
+        )]));
+    }
+
+    let license_key = request.license_key.clone();
+    if !repository
+        .delete_license(request.customer_id, request.vessel_id, request.license_key)
+        .await?
+    {
+        return Err(ApiError::PooledLicense(license_key));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handler;
+    use chrono::Utc;
+    use licenses_api_types::{Entitlements, LicenseRequest};
+    use licenses_core::{
+        ApiError, InMemoryLicenseRepository, InMemoryPoolRepository, License, LicenseRepository, Pool, PoolRepository,
+        ValidationRules,
+    };
+    use std::sync::Arc;
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    const LICENSE_KEY: &str = "WEATHER_FORECAST";
+
+    #[tokio_test]
+    async fn delete_license() {
+        let repository = InMemoryLicenseRepository::new();
+        repository
+            .create_license(License {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+                license_key: LICENSE_KEY.to_string(),
+                count: None,
+                expires_at: None,
+                entitlements: Entitlements::new(),
+                trial: None,
+            })
+            .await
+            .unwrap();
+
+        handler(
+            &repository,
+            &ValidationRules::default(),
+            LicenseRequest {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+                license_key: LICENSE_KEY.to_string(),
+                as_of: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        assert!(repository
+            .get_license(CUSTOMER_ID, VESSEL_ID, LICENSE_KEY.to_string())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio_test]
+    async fn delete_pooled_license() {
+        let licenses = Arc::new(InMemoryLicenseRepository::new());
+        let pools = InMemoryPoolRepository::new(licenses.clone());
+        let pool = Pool {
+            customer_id: CUSTOMER_ID,
+            product_key: LICENSE_KEY.to_string(),
+            total_seats: 2,
+            available_seats: 2,
+            expires_at: None,
+        };
+        pools.insert_pool(pool.clone()).await.unwrap();
+        pools.allocate_seats(&pool, VESSEL_ID, 1).await.unwrap();
+
+        let result = handler(
+            licenses.as_ref(),
+            &ValidationRules::default(),
+            LicenseRequest {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+                license_key: LICENSE_KEY.to_string(),
+                as_of: None,
+            },
+        )
+        .await;
+
+        match result {
+            Err(ApiError::PooledLicense(license_key)) => assert_eq!(LICENSE_KEY, license_key),
+            _ => panic!("Invalid result."),
+        }
+        assert!(licenses
+            .get_license(CUSTOMER_ID, VESSEL_ID, LICENSE_KEY.to_string())
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio_test]
+    async fn delete_license_as_of() {
+        let result = handler(
+            &InMemoryLicenseRepository::new(),
+            &ValidationRules::default(),
+            LicenseRequest {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+                license_key: LICENSE_KEY.to_string(),
+                as_of: Some(Utc::now().into()),
+            },
+        )
+        .await;
+
+        match result {
+            Err(ApiError::ValidationFailed(errors)) => assert_eq!("asOf", errors[0].field),
+            _ => panic!("Invalid result."),
+        }
+    }
+}
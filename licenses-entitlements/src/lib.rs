@@ -0,0 +1,189 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use chrono::Utc;
+use licenses_api_types::{EntitlementsRequest, EntitlementsResponse};
+use licenses_core::{
+    list_all, ApiError, LicenseRepository, LicensesFilter, Validate, ValidationRules, VesselEntitlements,
+};
+
+pub async fn handler<R: LicenseRepository>(
+    repository: &R,
+    rules: &ValidationRules,
+    request: EntitlementsRequest,
+) -> Result<EntitlementsResponse, ApiError> {
+    request.validate(rules)?;
+
+    // expired licenses are not filtered out by the query - they are dropped when resolving, against single point in time
+    let licenses = list_all(
+        repository,
+        request.customer_id,
+        request.vessel_id,
+        &LicensesFilter::default(),
+        None,
+    )
+    .await?;
+
+    Ok(EntitlementsResponse::from(VesselEntitlements::resolve(
+        &licenses,
+        &Utc::now(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handler;
+    use chrono::{DateTime, Duration, FixedOffset, Utc};
+    use licenses_api_types::{Entitlement, Entitlements, EntitlementsRequest, Seats};
+    use licenses_core::{ApiError, InMemoryLicenseRepository, License, LicenseRepository, ValidationRules};
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+
+    fn days(days: i64) -> Option<DateTime<FixedOffset>> {
+        Some((Utc::now() + Duration::days(days)).into())
+    }
+
+    fn license(license_key: &str, count: Option<Seats>, expires_at: Option<DateTime<FixedOffset>>) -> License {
+        License {
+            customer_id: CUSTOMER_ID,
+            vessel_id: VESSEL_ID,
+            license_key: license_key.to_string(),
+            count,
+            expires_at,
+            entitlements: Entitlements::new(),
+            trial: None,
+        }
+    }
+
+    async fn repository(licenses: Vec<License>) -> InMemoryLicenseRepository {
+        let repository = InMemoryLicenseRepository::new();
+        for license in licenses {
+            repository.create_license(license).await.unwrap();
+        }
+        repository
+    }
+
+    fn request() -> EntitlementsRequest {
+        EntitlementsRequest {
+            customer_id: CUSTOMER_ID,
+            vessel_id: VESSEL_ID,
+        }
+    }
+
+    #[tokio_test]
+    async fn resolve_active_licenses() {
+        let expires_at = days(30);
+        let repository = repository(vec![
+            License {
+                entitlements: Entitlements::from([("routing".to_string(), Entitlement::Flag(true))]),
+                ..license("routing.2023", Some(Seats::Limited(2)), expires_at)
+            },
+            license("tides", Some(Seats::Limited(1)), None),
+            license("weather.2022", Some(Seats::Limited(5)), days(-1)),
+        ])
+        .await;
+
+        let response = handler(&repository, &ValidationRules::default(), request())
+            .await
+            .unwrap();
+
+        assert_eq!(vec!["routing.2023", "tides"], response.license_keys);
+        assert_eq!(expires_at, response.expires_at);
+        assert_eq!(3, response.seats);
+        assert_eq!(Some(&Entitlement::Flag(true)), response.entitlements.get("routing"));
+    }
+
+    #[tokio_test]
+    async fn resolve_overlapping_renewals() {
+        let expires_at = days(10);
+        let repository = repository(vec![
+            License {
+                entitlements: Entitlements::from([("maxRoutes".to_string(), Entitlement::Limit(5))]),
+                ..license("routing.2023", Some(Seats::Limited(2)), expires_at)
+            },
+            License {
+                entitlements: Entitlements::from([("maxRoutes".to_string(), Entitlement::Limit(20))]),
+                ..license("routing.2024", Some(Seats::Limited(2)), days(375))
+            },
+        ])
+        .await;
+
+        let response = handler(&repository, &ValidationRules::default(), request())
+            .await
+            .unwrap();
+
+        assert_eq!(vec!["routing.2023", "routing.2024"], response.license_keys);
+        assert_eq!(expires_at, response.expires_at);
+        assert_eq!(4, response.seats);
+        assert_eq!(Some(&Entitlement::Limit(20)), response.entitlements.get("maxRoutes"));
+    }
+
+    #[tokio_test]
+    async fn resolve_renewal_after_expiry() {
+        let repository = repository(vec![
+            license("routing.2022", Some(Seats::Limited(4)), days(-5)),
+            license("routing.2023", Some(Seats::Limited(2)), days(360)),
+        ])
+        .await;
+
+        let response = handler(&repository, &ValidationRules::default(), request())
+            .await
+            .unwrap();
+
+        assert_eq!(vec!["routing.2023"], response.license_keys);
+        assert_eq!(2, response.seats);
+    }
+
+    #[tokio_test]
+    async fn resolve_perpetual_only() {
+        let repository = repository(vec![license("tides", None, None)]).await;
+
+        let response = handler(&repository, &ValidationRules::default(), request())
+            .await
+            .unwrap();
+
+        assert_eq!(vec!["tides"], response.license_keys);
+        assert!(response.expires_at.is_none());
+        assert_eq!(0, response.seats);
+    }
+
+    #[tokio_test]
+    async fn resolve_no_licenses() {
+        let response = handler(
+            &InMemoryLicenseRepository::new(),
+            &ValidationRules::default(),
+            request(),
+        )
+        .await
+        .unwrap();
+
+        assert!(response.license_keys.is_empty());
+        assert!(response.expires_at.is_none());
+        assert_eq!(0, response.seats);
+        assert!(response.entitlements.is_empty());
+    }
+
+    #[tokio_test]
+    async fn resolve_invalid_request() {
+        let result = handler(
+            &InMemoryLicenseRepository::new(),
+            &ValidationRules::default(),
+            EntitlementsRequest {
+                customer_id: Uuid::nil(),
+                vessel_id: VESSEL_ID,
+            },
+        )
+        .await;
+
+        match result {
+            Err(ApiError::ValidationFailed(errors)) => assert_eq!("customerId", errors[0].field),
+            _ => panic!("Validation error expected."),
+        }
+    }
+}
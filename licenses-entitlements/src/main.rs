@@ -0,0 +1,21 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+#![feature(future_join)]
+
+use lambda_runtime::{Error, LambdaEvent};
+use licenses_api_types::EntitlementsRequest;
+use licenses_core::{run_lambda, LicenseDao, ValidationRules};
+use licenses_entitlements::handler;
+use tokio::main as tokio_main;
+
+#[tokio_main]
+async fn main() -> Result<(), Error> {
+    let dao = &LicenseDao::load_from_env().await?;
+    let rules = &ValidationRules::load_from_env()?;
+
+    run_lambda!(move |event: LambdaEvent<EntitlementsRequest>| handler(dao, rules, event.payload))
+}
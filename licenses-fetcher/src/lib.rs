@@ -0,0 +1,149 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use chrono::Utc;
+use licenses_api_types::{LicenseRequest, LicenseResponse};
+use licenses_core::{ApiError, HistoryRepository, LicenseRepository, Validate, ValidationRules};
+
+/**
+With `asOf` the license is reconstructed from history - past state is served even if the license is gone by now.
+ */
+pub async fn handler<R: LicenseRepository + HistoryRepository>(
+    repository: &R,
+    rules: &ValidationRules,
+    request: LicenseRequest,
+) -> Result<LicenseResponse, ApiError> {
+    request.validate(rules)?;
+
+    let license = match request.as_of {
+        Some(as_of) => {
+            repository
+                .license_as_of(
+                    request.customer_id,
+                    request.vessel_id,
+                    request.license_key.clone(),
+                    as_of.with_timezone(&Utc),
+                )
+                .await?
+        }
+        None => {
+            repository
+                .get_license(request.customer_id, request.vessel_id, request.license_key.clone())
+                .await?
+        }
+    };
+
+    match license {
+        None => Err(ApiError::LicenseNotFound(request.license_key)),
+        Some(license) => Ok(LicenseResponse::from(license)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handler;
+    use chrono::{Duration, Utc};
+    use licenses_api_types::{Entitlement, Entitlements, LicenseRequest, Seats};
+    use licenses_core::{ApiError, InMemoryLicenseRepository, License, LicenseRepository, ValidationRules};
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    const LICENSE_KEY: &str = "tides.2023";
+    const COUNT: Seats = Seats::Limited(6);
+
+    #[tokio_test]
+    async fn fetch_license() {
+        let repository = InMemoryLicenseRepository::new();
+        repository
+            .create_license(License {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+                license_key: LICENSE_KEY.to_string(),
+                count: Some(COUNT),
+                expires_at: None,
+                entitlements: Entitlements::from([("routing".to_string(), Entitlement::Flag(true))]),
+                trial: None,
+            })
+            .await
+            .unwrap();
+
+        let response = handler(
+            &repository,
+            &ValidationRules::default(),
+            LicenseRequest {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+                license_key: LICENSE_KEY.to_string(),
+                as_of: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(LICENSE_KEY, response.license_key);
+        assert_eq!(Some(COUNT), response.count);
+        assert_eq!(Some(&Entitlement::Flag(true)), response.entitlements.get("routing"));
+    }
+
+    #[tokio_test]
+    async fn fetch_license_unexisting() {
+        let result = handler(
+            &InMemoryLicenseRepository::new(),
+            &ValidationRules::default(),
+            LicenseRequest {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+                license_key: LICENSE_KEY.to_string(),
+                as_of: None,
+            },
+        )
+        .await;
+
+        match result {
+            Err(ApiError::LicenseNotFound(license_key)) => assert_eq!(LICENSE_KEY, license_key),
+            _ => panic!("Invalid result."),
+        }
+    }
+
+    #[tokio_test]
+    async fn fetch_license_as_of() {
+        let repository = InMemoryLicenseRepository::new();
+        let license = |count| License {
+            customer_id: CUSTOMER_ID,
+            vessel_id: VESSEL_ID,
+            license_key: LICENSE_KEY.to_string(),
+            count: Some(count),
+            expires_at: None,
+            entitlements: Entitlements::new(),
+            trial: None,
+        };
+        let before = Utc::now() - Duration::seconds(1);
+        repository.create_license(license(COUNT)).await.unwrap();
+        let as_of = Utc::now();
+        repository.create_license(license(Seats::Unlimited)).await.unwrap();
+        repository
+            .delete_license(CUSTOMER_ID, VESSEL_ID, LICENSE_KEY.to_string())
+            .await
+            .unwrap();
+
+        let request = |as_of| LicenseRequest {
+            customer_id: CUSTOMER_ID,
+            vessel_id: VESSEL_ID,
+            license_key: LICENSE_KEY.to_string(),
+            as_of: Some(as_of),
+        };
+
+        let response = handler(&repository, &ValidationRules::default(), request(as_of.into()))
+            .await
+            .unwrap();
+        assert_eq!(Some(COUNT), response.count);
+
+        let result = handler(&repository, &ValidationRules::default(), request(before.into())).await;
+        assert!(matches!(result, Err(ApiError::LicenseNotFound(_))));
+    }
+}
@@ -0,0 +1,619 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use http::{Method, StatusCode};
+use licenses_api_types::{
+    CreateLicenseBody, CreateLicenseRequest, ErrorCode, ErrorResponse, LicenseQuery, LicenseRequest, ListLicensesQuery,
+    ListLicensesRequest, UpdateLicenseBody,
+};
+use licenses_core::{
+    ApiError, HistoryRepository, LicenseRepository, PageTokenCodec, ProductRepository, RuntimeError, ValidationRules,
+};
+use log::error;
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_slice, to_string};
+use uuid::Uuid;
+
+#[doc = "Parts of incoming HTTP request needed for routing."]
+pub struct HttpRequest<'a> {
+    pub method: &'a Method,
+    pub path: &'a str,
+    pub query: Option<&'a str>,
+    pub body: &'a [u8],
+}
+
+#[derive(Debug)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub body: String,
+}
+
+impl HttpResponse {
+    fn json<T: Serialize>(status: StatusCode, body: &T) -> Result<Self, ApiError> {
+        Ok(Self {
+            status,
+            body: to_string(body).map_err(RuntimeError::from)?,
+        })
+    }
+
+    fn empty(status: StatusCode) -> Self {
+        Self {
+            status,
+            body: String::new(),
+        }
+    }
+
+    fn error(status: StatusCode, error: &ErrorResponse) -> Self {
+        Self {
+            status,
+            body: to_string(error).unwrap_or_default(),
+        }
+    }
+
+    fn method_not_allowed() -> Self {
+        Self::error(
+            StatusCode::METHOD_NOT_ALLOWED,
+            &ErrorResponse::new(ErrorCode::MethodNotAllowed, "Method not allowed."),
+        )
+    }
+}
+
+pub fn status_of(code: ErrorCode) -> StatusCode {
+    match code {
+        ErrorCode::LicenseNotFound
+        | ErrorCode::ProductNotFound
+        | ErrorCode::PoolNotFound
+        | ErrorCode::RouteNotFound => StatusCode::NOT_FOUND,
+        ErrorCode::LicenseAlreadyExists
+        | ErrorCode::PoolAlreadyExists
+        | ErrorCode::InsufficientSeats
+        | ErrorCode::TrialAlreadyUsed
+        | ErrorCode::NotTrial
+        | ErrorCode::PooledLicense
+        | ErrorCode::Conflict => StatusCode::CONFLICT,
+        ErrorCode::InvalidPageToken | ErrorCode::InvalidRequest | ErrorCode::ValidationFailed => {
+            StatusCode::BAD_REQUEST
+        }
+        ErrorCode::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+        ErrorCode::Throttled => StatusCode::TOO_MANY_REQUESTS,
+        ErrorCode::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+        ErrorCode::InternalError | ErrorCode::Unknown => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn parse_id(value: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(value).map_err(|_| ApiError::InvalidRequest(format!("invalid ID \"{value}\"")))
+}
+
+fn parse_body<'a, T: Deserialize<'a>>(body: &'a [u8]) -> Result<T, ApiError> {
+    from_slice(body).map_err(|error| ApiError::InvalidRequest(error.to_string()))
+}
+
+fn parse_query<'a, T: Deserialize<'a>>(query: Option<&'a str>) -> Result<T, ApiError> {
+    serde_urlencoded::from_str(query.unwrap_or_default()).map_err(|error| ApiError::InvalidRequest(error.to_string()))
+}
+
+/**
+Maps REST request onto licenses operations:
+<dl>
+    <dt><code>POST /customers/{customerId}/vessels/{vesselId}/licenses</code></dt>
+    <dd>Creates new license; fails with <code>409</code> if it already exists.</dd>
+    <dt><code>GET /customers/{customerId}/vessels/{vesselId}/licenses</code></dt>
+    <dd>Lists licenses of the vessel.</dd>
+    <dt><code>GET /customers/{customerId}/vessels/{vesselId}/licenses/{licenseKey}</code></dt>
+    <dd>Fetches single license; <code>asOf</code> query parameter reconstructs its past state.</dd>
+    <dt><code>PUT /customers/{customerId}/vessels/{vesselId}/licenses/{licenseKey}</code></dt>
+    <dd>Creates or replaces license.</dd>
+    <dt><code>DELETE /customers/{customerId}/vessels/{vesselId}/licenses/{licenseKey}</code></dt>
+    <dd>Deletes license.</dd>
+</dl>
+ */
+pub async fn dispatch<R: LicenseRepository + HistoryRepository, P: ProductRepository>(
+    repository: &R,
+    products: &P,
+    codec: &PageTokenCodec,
+    rules: &ValidationRules,
+    request: HttpRequest<'_>,
+) -> HttpResponse {
+    match route(repository, products, codec, rules, request).await {
+        Ok(response) => response,
+        Err(error) => {
+            // raw cause stays in logs only
+            error!("Request failed: {error:?}");
+
+            let response = ErrorResponse::from(&error);
+            HttpResponse::error(status_of(response.code), &response)
+        }
+    }
+}
+
+async fn route<R: LicenseRepository + HistoryRepository, P: ProductRepository>(
+    repository: &R,
+    products: &P,
+    codec: &PageTokenCodec,
+    rules: &ValidationRules,
+    HttpRequest {
+        method,
+        path,
+        query,
+        body,
+    }: HttpRequest<'_>,
+) -> Result<HttpResponse, ApiError> {
+    let segments = path
+        .trim_matches('/')
+        .split('/')
+        .map(|segment| {
+            percent_decode_str(segment)
+                .decode_utf8()
+                .map(|segment| segment.into_owned())
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| ApiError::InvalidRequest("path is not valid UTF-8".into()))?;
+
+    match segments.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["customers", customer_id, "vessels", vessel_id, "licenses"] => {
+            let (customer_id, vessel_id) = (parse_id(customer_id)?, parse_id(vessel_id)?);
+
+            match *method {
+                Method::POST => create(repository, products, rules, customer_id, vessel_id, parse_body(body)?).await,
+                Method::GET => list(repository, codec, rules, customer_id, vessel_id, parse_query(query)?).await,
+                _ => Ok(HttpResponse::method_not_allowed()),
+            }
+        }
+        ["customers", customer_id, "vessels", vessel_id, "licenses", license_key] => {
+            let (customer_id, vessel_id) = (parse_id(customer_id)?, parse_id(vessel_id)?);
+            let license_key = license_key.to_string();
+
+            match *method {
+                Method::GET => {
+                    let query: LicenseQuery = parse_query(query)?;
+                    HttpResponse::json(
+                        StatusCode::OK,
+                        &licenses_fetcher::handler(
+                            repository,
+                            rules,
+                            LicenseRequest {
+                                customer_id,
+                                vessel_id,
+                                license_key,
+                                as_of: query.as_of,
+                            },
+                        )
+                        .await?,
+                    )
+                }
+                Method::PUT => {
+                    let body: UpdateLicenseBody = parse_body(body)?;
+                    HttpResponse::json(
+                        StatusCode::OK,
+                        &licenses_creator::handler(
+                            repository,
+                            products,
+                            rules,
+                            CreateLicenseRequest {
+                                customer_id,
+                                vessel_id,
+                                license_key,
+                                count: body.count,
+                                expires_at: body.expires_at,
+                                entitlements: body.entitlements,
+                            },
+                        )
+                        .await?,
+                    )
+                }
+                Method::DELETE => {
+                    licenses_deleter::handler(
+                        repository,
+                        rules,
+                        LicenseRequest {
+                            customer_id,
+                            vessel_id,
+                            license_key,
+                            as_of: None,
+                        },
+                    )
+                    .await?;
+                    Ok(HttpResponse::empty(StatusCode::NO_CONTENT))
+                }
+                _ => Ok(HttpResponse::method_not_allowed()),
+            }
+        }
+        _ => Ok(HttpResponse::error(
+            StatusCode::NOT_FOUND,
+            &ErrorResponse::new(ErrorCode::RouteNotFound, "Not found."),
+        )),
+    }
+}
+
+async fn create<R: LicenseRepository, P: ProductRepository>(
+    repository: &R,
+    products: &P,
+    rules: &ValidationRules,
+    customer_id: Uuid,
+    vessel_id: Uuid,
+    body: CreateLicenseBody,
+) -> Result<HttpResponse, ApiError> {
+    let license_key = body.license_key.clone();
+    let request = CreateLicenseRequest {
+        customer_id,
+        vessel_id,
+        license_key: body.license_key,
+        count: body.count,
+        expires_at: body.expires_at,
+        entitlements: body.entitlements,
+    };
+    let license = licenses_creator::license_of(products, rules, request).await?;
+
+    if repository.insert_license(license).await? {
+        HttpResponse::json(StatusCode::CREATED, &license_key)
+    } else {
+        Err(ApiError::LicenseAlreadyExists(license_key))
+    }
+}
+
+async fn list<R: LicenseRepository + HistoryRepository>(
+    repository: &R,
+    codec: &PageTokenCodec,
+    rules: &ValidationRules,
+    customer_id: Uuid,
+    vessel_id: Uuid,
+    query: ListLicensesQuery,
+) -> Result<HttpResponse, ApiError> {
+    HttpResponse::json(
+        StatusCode::OK,
+        &licenses_lister::handler(
+            repository,
+            codec,
+            rules,
+            ListLicensesRequest {
+                customer_id,
+                vessel_id,
+                page_token: query.page_token,
+                limit: query.limit,
+                license_key_prefix: query.license_key_prefix,
+                expired_only: query.expired_only,
+                active_only: query.active_only,
+                descending: query.descending,
+                as_of: query.as_of,
+            },
+        )
+        .await?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{dispatch, HttpRequest};
+    use http::{Method, StatusCode};
+    use licenses_api_types::{Entitlements, Seats};
+    use licenses_core::{
+        InMemoryLicenseRepository, InMemoryProductRepository, License, LicenseRepository, PageTokenCodec, Product,
+        ValidationRules,
+    };
+    use serde_json::{from_str, Value};
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    const LICENSE_KEY: &str = "tides.2023";
+
+    fn collection() -> String {
+        format!("/customers/{CUSTOMER_ID}/vessels/{VESSEL_ID}/licenses")
+    }
+
+    fn resource() -> String {
+        format!("{}/{LICENSE_KEY}", collection())
+    }
+
+    async fn repository() -> InMemoryLicenseRepository {
+        let repository = InMemoryLicenseRepository::new();
+        repository
+            .create_license(License {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+                license_key: LICENSE_KEY.to_string(),
+                count: Some(Seats::Limited(2)),
+                expires_at: None,
+                entitlements: Entitlements::new(),
+                trial: None,
+            })
+            .await
+            .unwrap();
+        repository
+    }
+
+    fn products() -> InMemoryProductRepository {
+        InMemoryProductRepository::from([Product {
+            product_key: LICENSE_KEY.to_string(),
+            display_name: "Tides 2023".to_string(),
+            default_count: None,
+            default_duration_days: None,
+        }])
+    }
+
+    fn codec() -> PageTokenCodec {
+        PageTokenCodec::new(b"secret").unwrap()
+    }
+
+    fn request<'a>(method: &'a Method, path: &'a str, query: Option<&'a str>, body: &'a [u8]) -> HttpRequest<'a> {
+        HttpRequest {
+            method,
+            path,
+            query,
+            body,
+        }
+    }
+
+    #[tokio_test]
+    async fn create_license() {
+        let repository = InMemoryLicenseRepository::new();
+        let body = format!("{{\"licenseKey\":\"{LICENSE_KEY}\",\"count\":3}}");
+
+        let response = dispatch(
+            &repository,
+            &products(),
+            &codec(),
+            &ValidationRules::default(),
+            request(&Method::POST, &collection(), None, body.as_bytes()),
+        )
+        .await;
+        assert_eq!(StatusCode::CREATED, response.status);
+
+        let response = dispatch(
+            &repository,
+            &products(),
+            &codec(),
+            &ValidationRules::default(),
+            request(&Method::POST, &collection(), None, body.as_bytes()),
+        )
+        .await;
+        assert_eq!(StatusCode::CONFLICT, response.status);
+        let body: Value = from_str(&response.body).unwrap();
+        assert_eq!("LICENSE_ALREADY_EXISTS", body["code"]);
+    }
+
+    #[tokio_test]
+    async fn create_license_invalid_body() {
+        let response = dispatch(
+            &InMemoryLicenseRepository::new(),
+            &products(),
+            &codec(),
+            &ValidationRules::default(),
+            request(&Method::POST, &collection(), None, b"{}"),
+        )
+        .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status);
+    }
+
+    #[tokio_test]
+    async fn create_license_invalid_key() {
+        let response = dispatch(
+            &InMemoryLicenseRepository::new(),
+            &products(),
+            &codec(),
+            &ValidationRules::default(),
+            request(
+                &Method::POST,
+                &collection(),
+                None,
+                b"{\"licenseKey\":\"tides 2023\",\"count\":3}",
+            ),
+        )
+        .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status);
+        let body: Value = from_str(&response.body).unwrap();
+        assert_eq!("VALIDATION_FAILED", body["code"]);
+        assert!(body["details"]["licenseKey"].is_string());
+    }
+
+    #[tokio_test]
+    async fn create_license_unknown_product() {
+        let response = dispatch(
+            &InMemoryLicenseRepository::new(),
+            &products(),
+            &codec(),
+            &ValidationRules::default(),
+            request(
+                &Method::POST,
+                &collection(),
+                None,
+                b"{\"licenseKey\":\"weather.2022\",\"count\":3}",
+            ),
+        )
+        .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status);
+        let body: Value = from_str(&response.body).unwrap();
+        assert_eq!("VALIDATION_FAILED", body["code"]);
+    }
+
+    #[tokio_test]
+    async fn fetch_license() {
+        let response = dispatch(
+            &repository().await,
+            &products(),
+            &codec(),
+            &ValidationRules::default(),
+            request(&Method::GET, &resource(), None, b""),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status);
+
+        let body: Value = from_str(&response.body).unwrap();
+        assert_eq!(2, body["count"].as_u64().unwrap());
+    }
+
+    #[tokio_test]
+    async fn fetch_license_unexisting() {
+        let response = dispatch(
+            &InMemoryLicenseRepository::new(),
+            &products(),
+            &codec(),
+            &ValidationRules::default(),
+            request(&Method::GET, &resource(), None, b""),
+        )
+        .await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status);
+        let body: Value = from_str(&response.body).unwrap();
+        assert_eq!("LICENSE_NOT_FOUND", body["code"]);
+    }
+
+    #[tokio_test]
+    async fn fetch_license_as_of_future() {
+        let response = dispatch(
+            &repository().await,
+            &products(),
+            &codec(),
+            &ValidationRules::default(),
+            request(&Method::GET, &resource(), Some("asOf=2999-01-01T00:00:00Z"), b""),
+        )
+        .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status);
+        let body: Value = from_str(&response.body).unwrap();
+        assert_eq!("VALIDATION_FAILED", body["code"]);
+        assert!(body["details"]["asOf"].is_string());
+    }
+
+    #[tokio_test]
+    async fn update_license() {
+        let repository = repository().await;
+
+        let response = dispatch(
+            &repository,
+            &products(),
+            &codec(),
+            &ValidationRules::default(),
+            request(&Method::PUT, &resource(), None, b"{\"count\":7}"),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status);
+
+        let license = repository
+            .get_license(CUSTOMER_ID, VESSEL_ID, LICENSE_KEY.to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some(Seats::Limited(7)), license.count);
+    }
+
+    #[tokio_test]
+    async fn delete_license() {
+        let repository = repository().await;
+
+        let response = dispatch(
+            &repository,
+            &products(),
+            &codec(),
+            &ValidationRules::default(),
+            request(&Method::DELETE, &resource(), None, b""),
+        )
+        .await;
+        assert_eq!(StatusCode::NO_CONTENT, response.status);
+        assert!(repository
+            .get_license(CUSTOMER_ID, VESSEL_ID, LICENSE_KEY.to_string())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio_test]
+    async fn list_licenses() {
+        let response = dispatch(
+            &repository().await,
+            &products(),
+            &codec(),
+            &ValidationRules::default(),
+            request(&Method::GET, &collection(), Some("limit=10&activeOnly=true"), b""),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, response.status);
+
+        let body: Value = from_str(&response.body).unwrap();
+        assert_eq!(1, body["licenses"].as_array().unwrap().len());
+    }
+
+    #[tokio_test]
+    async fn list_licenses_invalid_page_token() {
+        let response = dispatch(
+            &repository().await,
+            &products(),
+            &codec(),
+            &ValidationRules::default(),
+            request(&Method::GET, &collection(), Some("pageToken=tides.2023"), b""),
+        )
+        .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status);
+        let body: Value = from_str(&response.body).unwrap();
+        assert_eq!("INVALID_PAGE_TOKEN", body["code"]);
+    }
+
+    #[tokio_test]
+    async fn list_licenses_invalid_query() {
+        let response = dispatch(
+            &repository().await,
+            &products(),
+            &codec(),
+            &ValidationRules::default(),
+            request(
+                &Method::GET,
+                &collection(),
+                Some("limit=0&expiredOnly=true&activeOnly=true"),
+                b"",
+            ),
+        )
+        .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status);
+        let body: Value = from_str(&response.body).unwrap();
+        assert_eq!("VALIDATION_FAILED", body["code"]);
+        assert!(body["details"]["limit"].is_string());
+        assert!(body["details"]["activeOnly"].is_string());
+    }
+
+    #[tokio_test]
+    async fn invalid_id() {
+        let response = dispatch(
+            &InMemoryLicenseRepository::new(),
+            &products(),
+            &codec(),
+            &ValidationRules::default(),
+            request(&Method::GET, "/customers/abc/vessels/def/licenses", None, b""),
+        )
+        .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status);
+    }
+
+    #[tokio_test]
+    async fn unknown_route() {
+        let response = dispatch(
+            &InMemoryLicenseRepository::new(),
+            &products(),
+            &codec(),
+            &ValidationRules::default(),
+            request(&Method::GET, "/licenses", None, b""),
+        )
+        .await;
+        assert_eq!(StatusCode::NOT_FOUND, response.status);
+        let body: Value = from_str(&response.body).unwrap();
+        assert_eq!("ROUTE_NOT_FOUND", body["code"]);
+    }
+
+    #[tokio_test]
+    async fn method_not_allowed() {
+        let response = dispatch(
+            &InMemoryLicenseRepository::new(),
+            &products(),
+            &codec(),
+            &ValidationRules::default(),
+            request(&Method::PATCH, &resource(), None, b""),
+        )
+        .await;
+        assert_eq!(StatusCode::METHOD_NOT_ALLOWED, response.status);
+        let body: Value = from_str(&response.body).unwrap();
+        assert_eq!("METHOD_NOT_ALLOWED", body["code"]);
+    }
+}
@@ -0,0 +1,51 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+#![feature(future_join)]
+
+use http::header::CONTENT_TYPE;
+use lambda_http::{run, service_fn, Body, Error, Request, RequestExt, Response};
+use licenses_core::{init_lambda, LicenseDao, PageTokenCodec, ProductDao, ValidationRules};
+use licenses_http::{dispatch, HttpRequest};
+use tokio::main as tokio_main;
+
+#[tokio_main]
+async fn main() -> Result<(), Error> {
+    init_lambda()?;
+
+    let dao = &LicenseDao::load_from_env().await?;
+    let products = &ProductDao::load_from_env().await?;
+    let codec = &PageTokenCodec::load_from_env()?;
+    let rules = &ValidationRules::load_from_env()?;
+
+    run(service_fn(move |request: Request| async move {
+        // URI of API Gateway REST requests contains stage prefix, which is not part of the routes
+        let path = match request.raw_http_path() {
+            "" => request.uri().path(),
+            path => path,
+        };
+
+        let response = dispatch(
+            dao,
+            products,
+            codec,
+            rules,
+            HttpRequest {
+                method: request.method(),
+                path,
+                query: request.uri().query(),
+                body: request.body(),
+            },
+        )
+        .await;
+
+        Response::builder()
+            .status(response.status)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(response.body))
+    }))
+    .await
+}
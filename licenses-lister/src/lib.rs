@@ -0,0 +1,346 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use licenses_api_types::{LicenseResponse, ListLicensesRequest, ListLicensesResponse, SkippedLicense};
+use licenses_core::{
+    page_of, ApiError, DynamoResultsPage, HistoryRepository, License, LicenseRepository, LicensesFilter, PageKey,
+    PageScope, PageTokenCodec, RuntimeError, Validate, ValidationRules,
+};
+
+fn response_of(
+    page: DynamoResultsPage<License, PageKey>,
+    codec: &PageTokenCodec,
+    scope: PageScope,
+) -> Result<ListLicensesResponse, RuntimeError> {
+    Ok(ListLicensesResponse {
+        licenses: page.items.into_iter().map(LicenseResponse::from).collect(),
+        page_token: page
+            .last_evaluated_key
+            .map(|key| codec.encode(scope, key))
+            .transpose()?,
+        partial: !page.skipped.is_empty(),
+        warnings: page
+            .skipped
+            .into_iter()
+            .map(|skipped| SkippedLicense {
+                license_key: skipped.license_key,
+                reason: skipped.reason,
+            })
+            .collect(),
+    })
+}
+
+/**
+With `asOf` licenses are reconstructed from history and paged in memory - `asOf` is also the moment against which
+expired licenses are filtered.
+ */
+pub async fn handler<R: LicenseRepository + HistoryRepository>(
+    repository: &R,
+    codec: &PageTokenCodec,
+    rules: &ValidationRules,
+    request: ListLicensesRequest,
+) -> Result<ListLicensesResponse, ApiError> {
+    request.validate(rules)?;
+
+    let scope = PageScope {
+        customer_id: request.customer_id,
+        vessel_id: request.vessel_id,
+        filter: LicensesFilter::from(&request),
+    };
+    let page_key = request
+        .page_token
+        .map(|token| codec.decode(&token, &scope))
+        .transpose()?;
+
+    let page = match scope.filter.as_of {
+        Some(as_of) => page_of(
+            repository
+                .licenses_as_of(scope.customer_id, scope.vessel_id, as_of)
+                .await?,
+            &scope.filter,
+            request.limit,
+            page_key,
+            &as_of,
+        ),
+        None => {
+            repository
+                .list_licenses(
+                    scope.customer_id,
+                    scope.vessel_id,
+                    &scope.filter,
+                    request.limit,
+                    page_key,
+                )
+                .await?
+        }
+    };
+
+    Ok(response_of(page, codec, scope)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{handler, response_of};
+    use chrono::Utc;
+    use licenses_api_types::{Entitlement, Entitlements, ListLicensesRequest, Seats};
+    use licenses_core::{
+        ApiError, DynamoResultsPage, FieldError, InMemoryLicenseRepository, License, LicenseRepository, LicensesFilter,
+        PageKey, PageScope, PageTokenCodec, SkippedItem, ValidationRules,
+    };
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    const LICENSE_KEY: &str = "Test0";
+    const COUNT: Seats = Seats::Limited(42);
+    const PAGE_TOKEN: &str = "abc";
+
+    #[test]
+    fn response_from_model() {
+        let codec = PageTokenCodec::new(b"secret").unwrap();
+        let scope = || PageScope {
+            customer_id: CUSTOMER_ID,
+            vessel_id: VESSEL_ID,
+            filter: LicensesFilter::default(),
+        };
+        let page_key = PageKey::from([("licenseKey".into(), LICENSE_KEY.to_string())]);
+
+        let response = response_of(
+            DynamoResultsPage {
+                items: vec![License {
+                    customer_id: CUSTOMER_ID,
+                    vessel_id: VESSEL_ID,
+                    license_key: LICENSE_KEY.to_string(),
+                    count: Some(COUNT),
+                    expires_at: None,
+                    entitlements: Entitlements::from([("maxRoutes".to_string(), Entitlement::Limit(10))]),
+                    trial: None,
+                }],
+                last_evaluated_key: Some(page_key.clone()),
+                skipped: vec![],
+            },
+            &codec,
+            scope(),
+        )
+        .unwrap();
+
+        assert_eq!(1, response.licenses.len());
+        assert_eq!(LICENSE_KEY, response.licenses[0].license_key);
+        assert_eq!(
+            Some(&Entitlement::Limit(10)),
+            response.licenses[0].entitlements.get("maxRoutes")
+        );
+        assert_eq!(page_key, codec.decode(&response.page_token.unwrap(), &scope()).unwrap());
+        assert!(!response.partial);
+        assert!(response.warnings.is_empty());
+    }
+
+    #[test]
+    fn response_from_model_partial() {
+        let response = response_of(
+            DynamoResultsPage {
+                items: vec![],
+                last_evaluated_key: None,
+                skipped: vec![SkippedItem {
+                    license_key: LICENSE_KEY.to_string(),
+                    reason: "invalid date".to_string(),
+                }],
+            },
+            &PageTokenCodec::new(b"secret").unwrap(),
+            PageScope {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+                filter: LicensesFilter::default(),
+            },
+        )
+        .unwrap();
+
+        assert!(response.partial);
+        assert_eq!(1, response.warnings.len());
+        assert_eq!(LICENSE_KEY, response.warnings[0].license_key);
+        assert_eq!("invalid date", response.warnings[0].reason);
+    }
+
+    #[test]
+    fn response_from_model_last_page() {
+        let response = response_of(
+            DynamoResultsPage {
+                items: vec![],
+                last_evaluated_key: None,
+                skipped: vec![],
+            },
+            &PageTokenCodec::new(b"secret").unwrap(),
+            PageScope {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+                filter: LicensesFilter::default(),
+            },
+        )
+        .unwrap();
+
+        assert!(response.licenses.is_empty());
+        assert!(response.page_token.is_none());
+    }
+
+    fn list_request(page_token: Option<String>) -> ListLicensesRequest {
+        ListLicensesRequest {
+            customer_id: CUSTOMER_ID,
+            vessel_id: VESSEL_ID,
+            page_token,
+            limit: Some(1),
+            license_key_prefix: None,
+            expired_only: false,
+            active_only: false,
+            descending: false,
+            as_of: None,
+        }
+    }
+
+    #[tokio_test]
+    async fn list_licenses() {
+        let repository = InMemoryLicenseRepository::new();
+        let codec = PageTokenCodec::new(b"secret").unwrap();
+        for license_key in ["Test0", "Test1"] {
+            repository
+                .create_license(License {
+                    customer_id: CUSTOMER_ID,
+                    vessel_id: VESSEL_ID,
+                    license_key: license_key.to_string(),
+                    count: Some(COUNT),
+                    expires_at: None,
+                    entitlements: Entitlements::new(),
+                    trial: None,
+                })
+                .await
+                .unwrap();
+        }
+
+        let first = handler(&repository, &codec, &ValidationRules::default(), list_request(None))
+            .await
+            .unwrap();
+        assert_eq!(1, first.licenses.len());
+        assert_eq!("Test0", first.licenses[0].license_key);
+
+        let second = handler(
+            &repository,
+            &codec,
+            &ValidationRules::default(),
+            list_request(first.page_token),
+        )
+        .await
+        .unwrap();
+        assert_eq!(1, second.licenses.len());
+        assert_eq!("Test1", second.licenses[0].license_key);
+    }
+
+    #[tokio_test]
+    async fn list_licenses_as_of() {
+        let repository = InMemoryLicenseRepository::new();
+        let codec = PageTokenCodec::new(b"secret").unwrap();
+        for license_key in ["Test0", "Test1"] {
+            repository
+                .create_license(License {
+                    customer_id: CUSTOMER_ID,
+                    vessel_id: VESSEL_ID,
+                    license_key: license_key.to_string(),
+                    count: Some(COUNT),
+                    expires_at: None,
+                    entitlements: Entitlements::new(),
+                    trial: None,
+                })
+                .await
+                .unwrap();
+        }
+        let as_of = Utc::now();
+        repository
+            .delete_license(CUSTOMER_ID, VESSEL_ID, "Test0".to_string())
+            .await
+            .unwrap();
+
+        let request = |page_token| ListLicensesRequest {
+            as_of: Some(as_of.into()),
+            ..list_request(page_token)
+        };
+
+        let first = handler(&repository, &codec, &ValidationRules::default(), request(None))
+            .await
+            .unwrap();
+        assert_eq!(1, first.licenses.len());
+        assert_eq!("Test0", first.licenses[0].license_key);
+
+        let second = handler(
+            &repository,
+            &codec,
+            &ValidationRules::default(),
+            request(first.page_token),
+        )
+        .await
+        .unwrap();
+        assert_eq!(1, second.licenses.len());
+        assert_eq!("Test1", second.licenses[0].license_key);
+    }
+
+    #[tokio_test]
+    async fn list_licenses_zero_limit() {
+        let result = handler(
+            &InMemoryLicenseRepository::new(),
+            &PageTokenCodec::new(b"secret").unwrap(),
+            &ValidationRules::default(),
+            ListLicensesRequest {
+                limit: Some(0),
+                ..list_request(None)
+            },
+        )
+        .await;
+
+        match result {
+            Err(ApiError::ValidationFailed(errors)) => {
+                assert_eq!(vec![FieldError::new("limit", "must be positive")], errors)
+            }
+            _ => panic!("Validation error expected."),
+        }
+    }
+
+    #[tokio_test]
+    async fn list_licenses_conflicting_states() {
+        let result = handler(
+            &InMemoryLicenseRepository::new(),
+            &PageTokenCodec::new(b"secret").unwrap(),
+            &ValidationRules::default(),
+            ListLicensesRequest {
+                expired_only: true,
+                active_only: true,
+                ..list_request(None)
+            },
+        )
+        .await;
+
+        match result {
+            Err(ApiError::ValidationFailed(errors)) => assert_eq!(
+                vec![FieldError::new("activeOnly", "can not be combined with expiredOnly")],
+                errors
+            ),
+            _ => panic!("Validation error expected."),
+        }
+    }
+
+    #[tokio_test]
+    async fn list_licenses_invalid_page_token() {
+        let result = handler(
+            &InMemoryLicenseRepository::new(),
+            &PageTokenCodec::new(b"secret").unwrap(),
+            &ValidationRules::default(),
+            list_request(Some(PAGE_TOKEN.to_string())),
+        )
+        .await;
+
+        match result {
+            Err(ApiError::InvalidPageToken) => {}
+            _ => panic!("Invalid result."),
+        }
+    }
+}
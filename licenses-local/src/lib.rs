@@ -0,0 +1,710 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use aws_sdk_dynamodb::types::{
+    AttributeDefinition, BillingMode, GlobalSecondaryIndex, KeySchemaElement, KeyType, Projection, ProjectionType,
+    ScalarAttributeType, TimeToLiveSpecification,
+};
+use aws_sdk_dynamodb::Client;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderValue, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::Router;
+use chrono::Duration;
+use licenses_api_types::{ErrorResponse, InvocationError};
+use licenses_core::{
+    ApiError, HistoryRepository, LicenseRepository, PageTokenCodec, PoolRepository, ProductRepository, TrialRepository,
+    ValidationRules,
+};
+use licenses_http::{dispatch, HttpRequest};
+use licenses_trial_starter::DEFAULT_TRIAL_DURATION_DAYS;
+use log::error;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{from_slice, json, to_string};
+use std::any::type_name;
+use std::future::Future;
+use std::sync::Arc;
+
+const FUNCTION_ERROR_HEADER: &str = "X-Amz-Function-Error";
+
+pub struct LocalState<R, P, Q, T> {
+    pub repository: R,
+    pub products: P,
+    pub pools: Q,
+    pub trials: T,
+    pub codec: PageTokenCodec,
+    pub rules: ValidationRules,
+}
+
+/**
+Builds local server routes:
+<dl>
+    <dt><code>POST /2015-03-31/functions/{functionName}/invocations</code></dt>
+    <dd>Emulates Lambda <code>Invoke</code> API for <code>licenses-api</code>, <code>licenses-creator</code>,
+        <code>licenses-deleter</code>, <code>licenses-fetcher</code>, <code>licenses-lister</code>,
+        <code>licenses-summary</code>, <code>licenses-entitlements</code>, <code>licenses-products</code>,
+        <code>licenses-pools</code> and <code>licenses-trial-starter</code> functions.</dd>
+    <dt>any other path</dt>
+    <dd>REST API, same as served by <code>licenses-http</code>.</dd>
+</dl>
+ */
+pub fn router<R, P, Q, T>(state: LocalState<R, P, Q, T>) -> Router
+where
+    R: LicenseRepository + HistoryRepository + 'static,
+    P: ProductRepository + 'static,
+    Q: PoolRepository + 'static,
+    T: TrialRepository + 'static,
+{
+    Router::new()
+        .route(
+            "/2015-03-31/functions/:function_name/invocations",
+            post(invoke::<R, P, Q, T>),
+        )
+        .fallback(rest::<R, P, Q, T>)
+        .with_state(Arc::new(state))
+}
+
+async fn rest<R: LicenseRepository + HistoryRepository, P: ProductRepository, Q: PoolRepository, T: TrialRepository>(
+    State(state): State<Arc<LocalState<R, P, Q, T>>>,
+    method: Method,
+    uri: Uri,
+    body: Bytes,
+) -> Response {
+    let response = dispatch(
+        &state.repository,
+        &state.products,
+        &state.codec,
+        &state.rules,
+        HttpRequest {
+            method: &method,
+            path: uri.path(),
+            query: uri.query(),
+            body: &body,
+        },
+    )
+    .await;
+
+    (response.status, [(CONTENT_TYPE, "application/json")], response.body).into_response()
+}
+
+async fn invoke<
+    R: LicenseRepository + HistoryRepository,
+    P: ProductRepository,
+    Q: PoolRepository,
+    T: TrialRepository,
+>(
+    State(state): State<Arc<LocalState<R, P, Q, T>>>,
+    Path(function_name): Path<String>,
+    body: Bytes,
+) -> Response {
+    let (repository, products, pools, trials) = (&state.repository, &state.products, &state.pools, &state.trials);
+    let (codec, rules) = (&state.codec, &state.rules);
+
+    match function_name.as_str() {
+        "licenses-api" => {
+            handle(&body, |request| {
+                licenses_api::handler(repository, products, codec, rules, request)
+            })
+            .await
+        }
+        "licenses-creator" => {
+            handle(&body, |request| {
+                licenses_creator::handler(repository, products, rules, request)
+            })
+            .await
+        }
+        "licenses-deleter" => handle(&body, |request| licenses_deleter::handler(repository, rules, request)).await,
+        "licenses-fetcher" => handle(&body, |request| licenses_fetcher::handler(repository, rules, request)).await,
+        "licenses-lister" => {
+            handle(&body, |request| {
+                licenses_lister::handler(repository, codec, rules, request)
+            })
+            .await
+        }
+        "licenses-summary" => handle(&body, |request| licenses_summary::handler(repository, rules, request)).await,
+        "licenses-entitlements" => {
+            handle(&body, |request| {
+                licenses_entitlements::handler(repository, rules, request)
+            })
+            .await
+        }
+        "licenses-products" => handle(&body, |request| licenses_products::handler(products, rules, request)).await,
+        "licenses-pools" => {
+            handle(&body, |request| {
+                licenses_pools::handler(pools, products, rules, request)
+            })
+            .await
+        }
+        "licenses-trial-starter" => {
+            handle(&body, |request| {
+                licenses_trial_starter::handler(
+                    trials,
+                    repository,
+                    products,
+                    rules,
+                    Duration::days(DEFAULT_TRIAL_DURATION_DAYS),
+                    request,
+                )
+            })
+            .await
+        }
+        _ => (
+            StatusCode::NOT_FOUND,
+            json!({
+                "Type": "User",
+                "Message": format!("Function not found: {function_name}"),
+            })
+            .to_string(),
+        )
+            .into_response(),
+    }
+}
+
+async fn handle<RequestType, HandlerType, FutureType, ResponseType>(body: &[u8], handler: HandlerType) -> Response
+where
+    RequestType: DeserializeOwned,
+    HandlerType: FnOnce(RequestType) -> FutureType,
+    FutureType: Future<Output = Result<ResponseType, ApiError>>,
+    ResponseType: Serialize,
+{
+    let request = match from_slice(body) {
+        Ok(request) => request,
+        Err(error) => return function_error::<serde_json::Error>(error.to_string()),
+    };
+
+    match handler(request).await {
+        Ok(response) => match to_string(&response) {
+            Ok(body) => (StatusCode::OK, body).into_response(),
+            Err(error) => function_error::<serde_json::Error>(error.to_string()),
+        },
+        Err(error) => {
+            error!("Request failed: {error:?}");
+
+            // same format as produced by `run_lambda()` - error message carries serialized public error
+            match to_string(&ErrorResponse::from(&error)) {
+                Ok(message) => function_error::<ApiError>(message),
+                Err(error) => function_error::<serde_json::Error>(error.to_string()),
+            }
+        }
+    }
+}
+
+// mimics error payload produced by Lambda runtime
+fn function_error<ErrorType>(message: String) -> Response {
+    error!("Invocation failed: {message}");
+
+    let payload = InvocationError {
+        error_type: type_name::<ErrorType>().into(),
+        error_message: message,
+    };
+    let mut response = (StatusCode::OK, to_string(&payload).unwrap_or_default()).into_response();
+    response
+        .headers_mut()
+        .insert(FUNCTION_ERROR_HEADER, HeaderValue::from_static("Unhandled"));
+    response
+}
+
+async fn table_exists(client: &Client, table_name: &str) -> Result<bool, aws_sdk_dynamodb::Error> {
+    Ok(client
+        .list_tables()
+        .send()
+        .await?
+        .table_names()
+        .unwrap_or_default()
+        .iter()
+        .any(|name| name == table_name))
+}
+
+/**
+Creates licenses table, with the same structure as defined in <code>db.yaml</code>, unless it already exists.
+ */
+pub async fn ensure_table(client: &Client, table_name: &str) -> Result<(), aws_sdk_dynamodb::Error> {
+    if table_exists(client, table_name).await? {
+        return Ok(());
+    }
+
+    client
+        .create_table()
+        .table_name(table_name)
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("customerAndVesselId")
+                .attribute_type(ScalarAttributeType::S)
+                .build(),
+        )
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("licenseKey")
+                .attribute_type(ScalarAttributeType::S)
+                .build(),
+        )
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("customerId")
+                .attribute_type(ScalarAttributeType::S)
+                .build(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("customerAndVesselId")
+                .key_type(KeyType::Hash)
+                .build(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("licenseKey")
+                .key_type(KeyType::Range)
+                .build(),
+        )
+        .global_secondary_indexes(
+            GlobalSecondaryIndex::builder()
+                .index_name("customerId-licenseKey-index")
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("customerId")
+                        .key_type(KeyType::Hash)
+                        .build(),
+                )
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("licenseKey")
+                        .key_type(KeyType::Range)
+                        .build(),
+                )
+                .projection(Projection::builder().projection_type(ProjectionType::All).build())
+                .build(),
+        )
+        .global_secondary_indexes(
+            GlobalSecondaryIndex::builder()
+                .index_name("customerId-customerAndVesselId-index")
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("customerId")
+                        .key_type(KeyType::Hash)
+                        .build(),
+                )
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("customerAndVesselId")
+                        .key_type(KeyType::Range)
+                        .build(),
+                )
+                .projection(
+                    Projection::builder()
+                        .projection_type(ProjectionType::Include)
+                        .non_key_attributes("expiresAt")
+                        .non_key_attributes("count")
+                        .non_key_attributes("ttl")
+                        .build(),
+                )
+                .build(),
+        )
+        .billing_mode(BillingMode::PayPerRequest)
+        .send()
+        .await?;
+
+    client
+        .update_time_to_live()
+        .table_name(table_name)
+        .time_to_live_specification(
+            TimeToLiveSpecification::builder()
+                .attribute_name("ttl")
+                .enabled(true)
+                .build(),
+        )
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+/**
+Creates product catalogue table, with the same structure as defined in <code>db.yaml</code>, unless it already exists.
+ */
+pub async fn ensure_products_table(client: &Client, table_name: &str) -> Result<(), aws_sdk_dynamodb::Error> {
+    if table_exists(client, table_name).await? {
+        return Ok(());
+    }
+
+    client
+        .create_table()
+        .table_name(table_name)
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("productKey")
+                .attribute_type(ScalarAttributeType::S)
+                .build(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("productKey")
+                .key_type(KeyType::Hash)
+                .build(),
+        )
+        .billing_mode(BillingMode::PayPerRequest)
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+/**
+Creates customer pools table, with the same structure as defined in <code>db.yaml</code>, unless it already exists.
+ */
+pub async fn ensure_pools_table(client: &Client, table_name: &str) -> Result<(), aws_sdk_dynamodb::Error> {
+    if table_exists(client, table_name).await? {
+        return Ok(());
+    }
+
+    client
+        .create_table()
+        .table_name(table_name)
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("customerId")
+                .attribute_type(ScalarAttributeType::S)
+                .build(),
+        )
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("productKey")
+                .attribute_type(ScalarAttributeType::S)
+                .build(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("customerId")
+                .key_type(KeyType::Hash)
+                .build(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("productKey")
+                .key_type(KeyType::Range)
+                .build(),
+        )
+        .billing_mode(BillingMode::PayPerRequest)
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+/**
+Creates trials history table, with the same structure as defined in <code>db.yaml</code>, unless it already exists.
+ */
+pub async fn ensure_trials_table(client: &Client, table_name: &str) -> Result<(), aws_sdk_dynamodb::Error> {
+    if table_exists(client, table_name).await? {
+        return Ok(());
+    }
+
+    client
+        .create_table()
+        .table_name(table_name)
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("customerAndVesselId")
+                .attribute_type(ScalarAttributeType::S)
+                .build(),
+        )
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("licenseKey")
+                .attribute_type(ScalarAttributeType::S)
+                .build(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("customerAndVesselId")
+                .key_type(KeyType::Hash)
+                .build(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("licenseKey")
+                .key_type(KeyType::Range)
+                .build(),
+        )
+        .billing_mode(BillingMode::PayPerRequest)
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+/**
+Creates license history table, with the same structure as defined in <code>db.yaml</code>, unless it already exists.
+ */
+pub async fn ensure_history_table(client: &Client, table_name: &str) -> Result<(), aws_sdk_dynamodb::Error> {
+    if table_exists(client, table_name).await? {
+        return Ok(());
+    }
+
+    client
+        .create_table()
+        .table_name(table_name)
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("customerAndVesselId")
+                .attribute_type(ScalarAttributeType::S)
+                .build(),
+        )
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("licenseVersion")
+                .attribute_type(ScalarAttributeType::S)
+                .build(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("customerAndVesselId")
+                .key_type(KeyType::Hash)
+                .build(),
+        )
+        .key_schema(
+            KeySchemaElement::builder()
+                .attribute_name("licenseVersion")
+                .key_type(KeyType::Range)
+                .build(),
+        )
+        .billing_mode(BillingMode::PayPerRequest)
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ensure_table, router, LocalState, FUNCTION_ERROR_HEADER};
+    use aws_sdk_dynamodb::types::AttributeValue::S;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::Router;
+    use hyper::body::to_bytes;
+    use licenses_core::{
+        key_of, load_client, InMemoryLicenseRepository, InMemoryPoolRepository, InMemoryProductRepository,
+        InMemoryTrialRepository, LicenseDao, PageTokenCodec, ValidationRules,
+    };
+    use serde_json::{from_slice, from_str, Value};
+    use std::sync::Arc;
+    use tokio::test as tokio_test;
+    use tower::ServiceExt;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+
+    fn app() -> Router {
+        router(LocalState {
+            repository: InMemoryLicenseRepository::new(),
+            products: InMemoryProductRepository::new(),
+            pools: InMemoryPoolRepository::new(Arc::new(InMemoryLicenseRepository::new())),
+            trials: InMemoryTrialRepository::new(Arc::new(InMemoryLicenseRepository::new())),
+            codec: PageTokenCodec::new(b"secret").unwrap(),
+            rules: ValidationRules::default(),
+        })
+    }
+
+    fn invocation(function_name: &str, payload: String) -> Request<Body> {
+        Request::post(format!("/2015-03-31/functions/{function_name}/invocations"))
+            .body(Body::from(payload))
+            .unwrap()
+    }
+
+    async fn read_body(response: axum::response::Response) -> Value {
+        from_slice(&to_bytes(response.into_body()).await.unwrap()).unwrap()
+    }
+
+    #[tokio_test]
+    async fn invoke_function() {
+        let app = app();
+
+        let response = app
+            .clone()
+            .oneshot(invocation(
+                "licenses-products",
+                "{\"operation\":\"save\",\"productKey\":\"tides\",\"displayName\":\"Tides\"}".into(),
+            ))
+            .await
+            .unwrap();
+        assert!(response.headers().get(FUNCTION_ERROR_HEADER).is_none());
+
+        let response = app
+            .clone()
+            .oneshot(invocation(
+                "licenses-creator",
+                format!("{{\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"licenseKey\":\"tides\"}}"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        assert!(response.headers().get(FUNCTION_ERROR_HEADER).is_none());
+
+        let response = app
+            .oneshot(invocation(
+                "licenses-lister",
+                format!("{{\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\"}}"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(1, read_body(response).await["licenses"].as_array().unwrap().len());
+    }
+
+    #[tokio_test]
+    async fn invoke_pools_function() {
+        let app = app();
+
+        app.clone()
+            .oneshot(invocation(
+                "licenses-products",
+                "{\"operation\":\"save\",\"productKey\":\"tides\",\"displayName\":\"Tides\"}".into(),
+            ))
+            .await
+            .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(invocation(
+                "licenses-pools",
+                format!("{{\"operation\":\"create\",\"customerId\":\"{CUSTOMER_ID}\",\"productKey\":\"tides\",\"totalSeats\":5}}"),
+            ))
+            .await
+            .unwrap();
+        assert!(response.headers().get(FUNCTION_ERROR_HEADER).is_none());
+
+        let response = app
+            .oneshot(invocation(
+                "licenses-pools",
+                format!("{{\"operation\":\"list\",\"customerId\":\"{CUSTOMER_ID}\"}}"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(5, read_body(response).await["pools"][0]["totalSeats"]);
+    }
+
+    #[tokio_test]
+    async fn invoke_trial_starter_function() {
+        let app = app();
+
+        app.clone()
+            .oneshot(invocation(
+                "licenses-products",
+                "{\"operation\":\"save\",\"productKey\":\"tides\",\"displayName\":\"Tides\"}".into(),
+            ))
+            .await
+            .unwrap();
+
+        let start = format!(
+            "{{\"operation\":\"start\",\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"licenseKey\":\"tides\",\"origin\":\"web\"}}"
+        );
+
+        let response = app
+            .clone()
+            .oneshot(invocation("licenses-trial-starter", start.clone()))
+            .await
+            .unwrap();
+        assert!(response.headers().get(FUNCTION_ERROR_HEADER).is_none());
+        assert_eq!("web", read_body(response).await["trial"]["origin"]);
+
+        let response = app.oneshot(invocation("licenses-trial-starter", start)).await.unwrap();
+        assert_eq!("Unhandled", response.headers()[FUNCTION_ERROR_HEADER]);
+        let body = read_body(response).await;
+        let error: Value = from_str(body["errorMessage"].as_str().unwrap()).unwrap();
+        assert_eq!("TRIAL_ALREADY_USED", error["code"]);
+    }
+
+    #[tokio_test]
+    async fn invoke_function_error() {
+        let response = app()
+            .oneshot(invocation(
+                "licenses-fetcher",
+                format!("{{\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"licenseKey\":\"tides\"}}"),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!("Unhandled", response.headers()[FUNCTION_ERROR_HEADER]);
+        let body = read_body(response).await;
+        let error: Value = from_str(body["errorMessage"].as_str().unwrap()).unwrap();
+        assert_eq!("LICENSE_NOT_FOUND", error["code"]);
+        assert_eq!("tides", error["details"]["licenseKey"]);
+    }
+
+    #[tokio_test]
+    async fn invoke_unknown_function() {
+        let response = app()
+            .oneshot(invocation("licenses-updater", "{}".into()))
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+    }
+
+    #[tokio_test]
+    async fn rest_api() {
+        let response = app()
+            .oneshot(
+                Request::get(format!("/customers/{CUSTOMER_ID}/vessels/{VESSEL_ID}/licenses"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(0, read_body(response).await["licenses"].as_array().unwrap().len());
+    }
+
+    #[tokio_test]
+    async fn rest_api_partial_page() {
+        let client = load_client(Some("http://localhost:8000".into())).await;
+        let table_name = format!("Local{}", Uuid::new_v4().simple());
+        ensure_table(&client, &table_name).await.unwrap();
+
+        // corrupted entry, eg. written by hand
+        client
+            .put_item()
+            .table_name(table_name.as_str())
+            .item("customerAndVesselId", S(key_of(&CUSTOMER_ID, &VESSEL_ID)))
+            .item("customerId", S(CUSTOMER_ID.to_string()))
+            .item("vesselId", S(VESSEL_ID.to_string()))
+            .item("licenseKey", S("tides.2023".into()))
+            .item("expiresAt", S("01.07.2023".into()))
+            .send()
+            .await
+            .unwrap();
+
+        let response = router(LocalState {
+            repository: LicenseDao::new(client.clone(), table_name.clone()),
+            products: InMemoryProductRepository::new(),
+            pools: InMemoryPoolRepository::new(Arc::new(InMemoryLicenseRepository::new())),
+            trials: InMemoryTrialRepository::new(Arc::new(InMemoryLicenseRepository::new())),
+            codec: PageTokenCodec::new(b"secret").unwrap(),
+            rules: ValidationRules::default(),
+        })
+        .oneshot(
+            Request::get(format!("/customers/{CUSTOMER_ID}/vessels/{VESSEL_ID}/licenses"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        client.delete_table().table_name(table_name).send().await.unwrap();
+
+        assert_eq!(StatusCode::OK, response.status());
+        let body = read_body(response).await;
+        assert!(body["licenses"].as_array().unwrap().is_empty());
+        assert_eq!(Some(true), body["partial"].as_bool());
+        assert_eq!("tides.2023", body["warnings"][0]["licenseKey"]);
+    }
+}
@@ -0,0 +1,93 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use axum::Server;
+use licenses_core::{
+    load_client_from_env, retention_from_env, LicenseDao, PageTokenCodec, PoolDao, ProductDao, TrialDao,
+    ValidationRules,
+};
+use licenses_local::{
+    ensure_history_table, ensure_pools_table, ensure_products_table, ensure_table, ensure_trials_table, router,
+    LocalState,
+};
+use log::info;
+use std::env::var;
+use std::error::Error;
+use std::net::SocketAddr;
+use tokio::main as tokio_main;
+
+const DEFAULT_LISTEN_ADDRESS: &str = "127.0.0.1:3000";
+
+/**
+Required environment variables:
+<dl>
+    <dt><code>LICENSES_TABLE</code></dt>
+    <dd>Name of DynamoDB licenses table - created on startup if it doesn't exist.</dd>
+    <dt><code>PRODUCTS_TABLE</code></dt>
+    <dd>Name of DynamoDB product catalogue table - created on startup if it doesn't exist.</dd>
+    <dt><code>POOLS_TABLE</code></dt>
+    <dd>Name of DynamoDB customer pools table - created on startup if it doesn't exist.</dd>
+    <dt><code>TRIALS_TABLE</code></dt>
+    <dd>Name of DynamoDB trials history table - created on startup if it doesn't exist.</dd>
+    <dt><code>LICENSE_HISTORY_TABLE</code></dt>
+    <dd>Name of DynamoDB license history table - created on startup if it doesn't exist.</dd>
+    <dt><code>PAGE_TOKEN_SECRET</code></dt>
+    <dd>Secret used to sign page tokens.</dd>
+</dl>
+
+Optional environment variables:
+<dl>
+    <dt><code>DYNAMODB_ENDPOINT_URL</code></dt>
+    <dd>DynamoDB endpoint, usually DynamoDB Local - <code>http://localhost:8000</code>.</dd>
+    <dt><code>LICENSE_RETENTION_DAYS</code></dt>
+    <dd>How long expired licenses are kept before DynamoDB removes them, by default <code>365</code> days.</dd>
+    <dt><code>LISTEN_ADDRESS</code></dt>
+    <dd>Server bind address, by default <code>127.0.0.1:3000</code>.</dd>
+    <dt><code>LICENSE_KEY_MAX_LENGTH</code>, <code>LICENSE_KEY_CHARACTERS</code>, <code>LICENSE_PRODUCTS</code></dt>
+    <dd>License key validation rules, see <code>ValidationRules</code>.</dd>
+</dl>
+ */
+#[tokio_main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+
+    let table_name = var("LICENSES_TABLE")?;
+    let products_table_name = var("PRODUCTS_TABLE")?;
+    let pools_table_name = var("POOLS_TABLE")?;
+    let trials_table_name = var("TRIALS_TABLE")?;
+    let history_table_name = var("LICENSE_HISTORY_TABLE")?;
+    let retention = retention_from_env()?;
+    let client = load_client_from_env().await;
+    ensure_table(&client, &table_name).await?;
+    ensure_products_table(&client, &products_table_name).await?;
+    ensure_pools_table(&client, &pools_table_name).await?;
+    ensure_trials_table(&client, &trials_table_name).await?;
+    ensure_history_table(&client, &history_table_name).await?;
+
+    let state = LocalState {
+        repository: LicenseDao::new(client.clone(), table_name.clone())
+            .with_retention(retention)
+            .with_history(history_table_name.clone()),
+        products: ProductDao::new(client.clone(), products_table_name),
+        pools: PoolDao::new(client.clone(), pools_table_name, table_name.clone())
+            .with_retention(retention)
+            .with_history(history_table_name.clone()),
+        trials: TrialDao::new(client, trials_table_name, table_name)
+            .with_retention(retention)
+            .with_history(history_table_name),
+        codec: PageTokenCodec::load_from_env()?,
+        rules: ValidationRules::load_from_env()?,
+    };
+
+    let address: SocketAddr = var("LISTEN_ADDRESS")
+        .unwrap_or_else(|_| DEFAULT_LISTEN_ADDRESS.into())
+        .parse()?;
+    info!("Listening on {address}.");
+
+    Server::bind(&address).serve(router(state).into_make_service()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,1122 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+mod migrations;
+
+pub use crate::migrations::{migrations, BackfillTtl, SeedHistory};
+
+use aws_sdk_dynamodb::operation::put_item::PutItemError;
+use aws_sdk_dynamodb::types::AttributeValue::{Bool, M, N, S};
+use aws_sdk_dynamodb::types::{AttributeValue, ConditionCheck, Delete, Put, TransactWriteItem};
+use aws_sdk_dynamodb::Client;
+use clap::Parser;
+use futures::future::try_join_all;
+use licenses_core::{
+    failed_conditions, history_entry, item_state_condition, key_of as partition_of, License, RuntimeError,
+    DEFAULT_RETENTION_DAYS, SCHEMA_PARTITION,
+};
+use log::info;
+use serde_dynamo::from_item;
+use std::collections::HashMap;
+use std::str::FromStr;
+use thiserror::Error;
+
+const PARTITION_KEY: &str = "customerAndVesselId";
+const SORT_KEY: &str = "licenseKey";
+const VERSION_KEY: &str = "version";
+const VERSION_ATTRIBUTE: &str = "version";
+const SEGMENT_ATTRIBUTE: &str = "segment";
+const TOTAL_SEGMENTS_ATTRIBUTE: &str = "totalSegments";
+const POSITION_ATTRIBUTE: &str = "lastEvaluatedKey";
+const DONE_ATTRIBUTE: &str = "done";
+
+pub const DEFAULT_SEGMENTS: i32 = 4;
+
+#[derive(Parser)]
+#[command(
+    name = "licenses-migrator",
+    about = "Applies pending schema migrations to licenses table."
+)]
+pub struct Cli {
+    #[doc = "DynamoDB endpoint override, eg. http://localhost:8000 for DynamoDB Local."]
+    #[arg(long, env = "DYNAMODB_ENDPOINT_URL")]
+    pub endpoint_url: Option<String>,
+    #[doc = "Licenses table name."]
+    #[arg(long, env = "LICENSES_TABLE")]
+    pub table: String,
+    #[doc = "Number of parallel scan segments."]
+    #[arg(long, default_value_t = DEFAULT_SEGMENTS, value_parser = clap::value_parser!(i32).range(1..))]
+    pub segments: i32,
+    #[doc = "Only reports items that would be changed, without writing anything."]
+    #[arg(long)]
+    pub dry_run: bool,
+    #[doc = "Days after expiration when license is removed (must match LICENSE_RETENTION_DAYS of Lambdas)."]
+    #[arg(long, default_value_t = DEFAULT_RETENTION_DAYS)]
+    pub retention_days: i64,
+    #[doc = "License history table name - rewritten licenses are recorded there if specified."]
+    #[arg(long, env = "LICENSE_HISTORY_TABLE")]
+    pub history_table: Option<String>,
+}
+
+#[doc = "Raw DynamoDB item."]
+pub type Item = HashMap<String, AttributeValue>;
+
+/**
+Single schema change.
+
+Items may be processed more than once (eg. when interrupted run resumes from the last checkpoint), so migrations need
+to be idempotent - items that are already in the target shape should be left untouched.
+
+Migrations run against live table - item changed since it was scanned is skipped, so migrations should only fix shapes
+that current writers no longer produce.
+ */
+pub trait Migration: Send + Sync {
+    /**
+    Schema version of the table after applying this migration.
+     */
+    fn version(&self) -> u32;
+
+    fn description(&self) -> &str;
+
+    /**
+    Rewrites single item - returns `None` if it doesn't need to change. Returned item may have different key, in which
+    case it replaces the original one.
+     */
+    fn migrate(&self, item: &Item) -> Result<Option<Item>, RuntimeError>;
+
+    /**
+    Whether items left unchanged by the migration, that have no license history yet, should get their current state
+    recorded as the initial history entry - requires history table to be configured.
+     */
+    fn seeds_history(&self) -> bool {
+        false
+    }
+}
+
+#[derive(PartialEq, Debug)]
+#[doc = "Outcome of single migration."]
+pub struct MigrationReport {
+    #[doc = "Schema version after migration."]
+    pub version: u32,
+    #[doc = "Migration description."]
+    pub description: String,
+    #[doc = "Number of processed items (in current run, when resumed from checkpoint)."]
+    pub scanned: usize,
+    #[doc = "Number of rewritten items (or the ones that would be rewritten in dry run)."]
+    pub changed: usize,
+    #[doc = "Number of items skipped, because they were changed (or deleted) after being scanned, or because they are not \
+    valid licenses and can't be recorded in history."]
+    pub skipped: usize,
+}
+
+#[derive(Error, Debug)]
+pub enum MigrationError {
+    #[error("Migration {0} is not ordered after the previous one.")]
+    Unordered(u32),
+    #[error("Table schema version {0} is newer than the latest known migration.")]
+    UnknownVersion(u32),
+    #[error("Checkpoints of migration {0} were created with {1} segments.")]
+    SegmentsMismatch(u32, i32),
+    #[error("Schema version was changed by another run while migrating to {0}.")]
+    Conflict(u32),
+    #[error("Migration {0} requires license history table.")]
+    HistoryRequired(u32),
+    #[error("{0}")]
+    RuntimeError(#[from] RuntimeError),
+}
+
+enum Checkpoint {
+    Position(Item),
+    Done,
+}
+
+fn number_of<T: FromStr>(item: &Item, name: &str) -> Result<T, RuntimeError> {
+    let value = item
+        .get(name)
+        .ok_or_else(|| RuntimeError::DataError(AttributeValue::Null(true), name.into()))?;
+
+    value
+        .as_n()
+        .ok()
+        .and_then(|number| number.parse().ok())
+        .ok_or_else(|| RuntimeError::DataError(value.clone(), name.into()))
+}
+
+fn key_of(item: &Item) -> Item {
+    item.iter()
+        .filter(|(name, _)| *name == PARTITION_KEY || *name == SORT_KEY)
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+fn is_schema_entry(item: &Item) -> bool {
+    item.get(PARTITION_KEY)
+        .and_then(|value| value.as_s().ok())
+        .map(String::as_str)
+        == Some(SCHEMA_PARTITION)
+}
+
+fn checkpoints_prefix(version: u32) -> String {
+    format!("checkpoint#{version}#")
+}
+
+fn checkpoint_key(version: u32, segment: i32) -> String {
+    format!("{}{segment}", checkpoints_prefix(version))
+}
+
+/**
+Applies pending migrations to licenses table.
+
+Current schema version is kept in the table itself, as a bookkeeping entry in `#schema` partition. Each migration
+scans the whole table in parallel segments and stores position of every segment after each scanned page - interrupted
+run continues from the last checkpoint.
+ */
+pub struct Migrator {
+    client: Client,
+    table_name: String,
+    history_table_name: Option<String>,
+    segments: i32,
+    dry_run: bool,
+}
+
+impl Migrator {
+    pub fn new(client: Client, table_name: String) -> Self {
+        Self {
+            client,
+            table_name,
+            history_table_name: None,
+            segments: DEFAULT_SEGMENTS,
+            dry_run: false,
+        }
+    }
+
+    /**
+    Records rewritten licenses in license history, the same way as changes made by Lambdas.
+     */
+    pub fn with_history(self, history_table_name: String) -> Self {
+        Self {
+            history_table_name: Some(history_table_name),
+            ..self
+        }
+    }
+
+    pub fn with_segments(self, segments: i32) -> Self {
+        Self { segments, ..self }
+    }
+
+    /**
+    In dry run items are only scanned and counted - neither items nor version and checkpoints are written.
+     */
+    pub fn with_dry_run(self, dry_run: bool) -> Self {
+        Self { dry_run, ..self }
+    }
+
+    pub async fn current_version(&self) -> Result<u32, RuntimeError> {
+        self.client
+            .get_item()
+            .table_name(self.table_name.as_str())
+            .key(PARTITION_KEY, S(SCHEMA_PARTITION.into()))
+            .key(SORT_KEY, S(VERSION_KEY.into()))
+            .consistent_read(true)
+            .send()
+            .await?
+            .item
+            .map_or(Ok(0), |item| number_of(&item, VERSION_ATTRIBUTE))
+    }
+
+    /**
+    Applies migrations newer than current table schema version, in order.
+     */
+    pub async fn run(&self, migrations: &[Box<dyn Migration>]) -> Result<Vec<MigrationReport>, MigrationError> {
+        if let Some(pair) = migrations
+            .windows(2)
+            .find(|pair| pair[0].version() >= pair[1].version())
+        {
+            return Err(MigrationError::Unordered(pair[1].version()));
+        }
+
+        let mut current = self.current_version().await?;
+        if current > migrations.last().map_or(0, |migration| migration.version()) {
+            return Err(MigrationError::UnknownVersion(current));
+        }
+
+        let mut reports = vec![];
+        for migration in migrations.iter().filter(|migration| migration.version() > current) {
+            let migration = migration.as_ref();
+            if migration.seeds_history() && self.history_table_name.is_none() {
+                return Err(MigrationError::HistoryRequired(migration.version()));
+            }
+
+            info!(
+                "Migrating to version {}: {}{}",
+                migration.version(),
+                migration.description(),
+                if self.dry_run { " (dry run)" } else { "" }
+            );
+
+            let mut checkpoints = if self.dry_run {
+                HashMap::new()
+            } else {
+                self.load_checkpoints(migration.version()).await?
+            };
+            let segments = try_join_all(
+                (0..self.segments)
+                    .map(|segment| self.migrate_segment(migration, segment, checkpoints.remove(&segment))),
+            )
+            .await?;
+
+            if !self.dry_run {
+                self.complete(current, migration.version()).await?;
+            }
+
+            current = migration.version();
+            reports.push(MigrationReport {
+                version: migration.version(),
+                description: migration.description().into(),
+                scanned: segments.iter().map(|(scanned, _, _)| scanned).sum(),
+                changed: segments.iter().map(|(_, changed, _)| changed).sum(),
+                skipped: segments.iter().map(|(_, _, skipped)| skipped).sum(),
+            });
+        }
+
+        Ok(reports)
+    }
+
+    async fn load_checkpoints(&self, version: u32) -> Result<HashMap<i32, Checkpoint>, MigrationError> {
+        // at most one entry per segment, always fits in single page
+        let items = self
+            .client
+            .query()
+            .table_name(self.table_name.as_str())
+            .key_condition_expression("customerAndVesselId = :partition AND begins_with(licenseKey, :prefix)")
+            .expression_attribute_values(":partition", S(SCHEMA_PARTITION.into()))
+            .expression_attribute_values(":prefix", S(checkpoints_prefix(version)))
+            .consistent_read(true)
+            .send()
+            .await
+            .map_err(RuntimeError::from)?
+            .items
+            .unwrap_or_default();
+
+        let mut checkpoints = HashMap::new();
+        for mut item in items {
+            let segments = number_of(&item, TOTAL_SEGMENTS_ATTRIBUTE)?;
+            if segments != self.segments {
+                return Err(MigrationError::SegmentsMismatch(version, segments));
+            }
+
+            let checkpoint = match item.remove(POSITION_ATTRIBUTE) {
+                Some(M(position)) => Checkpoint::Position(position),
+                _ => Checkpoint::Done,
+            };
+            checkpoints.insert(number_of(&item, SEGMENT_ATTRIBUTE)?, checkpoint);
+        }
+
+        Ok(checkpoints)
+    }
+
+    async fn migrate_segment(
+        &self,
+        migration: &dyn Migration,
+        segment: i32,
+        checkpoint: Option<Checkpoint>,
+    ) -> Result<(usize, usize, usize), RuntimeError> {
+        let mut position = match checkpoint {
+            Some(Checkpoint::Done) => return Ok((0, 0, 0)),
+            Some(Checkpoint::Position(position)) => Some(position),
+            None => None,
+        };
+
+        let (mut scanned, mut changed, mut skipped) = (0, 0, 0);
+        loop {
+            let results = self
+                .client
+                .scan()
+                .table_name(self.table_name.as_str())
+                .segment(segment)
+                .total_segments(self.segments)
+                .consistent_read(true)
+                .set_exclusive_start_key(position)
+                .send()
+                .await?;
+
+            for item in results.items.unwrap_or_default() {
+                if is_schema_entry(&item) {
+                    continue;
+                }
+
+                scanned += 1;
+                let written = match migration.migrate(&item)? {
+                    Some(migrated) => Some(self.dry_run || self.save(&item, migrated).await?),
+                    None if migration.seeds_history() => self.seed_history(&item).await?,
+                    None => None,
+                };
+                match written {
+                    Some(true) => changed += 1,
+                    Some(false) => skipped += 1,
+                    None => {}
+                }
+            }
+
+            position = results.last_evaluated_key;
+            if !self.dry_run {
+                self.save_checkpoint(migration.version(), segment, position.clone())
+                    .await?;
+            }
+
+            if position.is_none() {
+                return Ok((scanned, changed, skipped));
+            }
+        }
+    }
+
+    /**
+    Writes are conditioned on the scanned state of the item, so concurrent changes are never reverted and deleted
+    licenses are not brought back - `false` if the item changed in the meantime. With history enabled, migrated item
+    that is not a valid license can't be recorded and is skipped as well.
+     */
+    async fn save(&self, item: &Item, migrated: Item) -> Result<bool, RuntimeError> {
+        let key = key_of(item);
+        let moved = key != key_of(&migrated);
+
+        let mut history = vec![];
+        if let Some(history_table_name) = &self.history_table_name {
+            let Ok(license) = from_item::<_, License>(migrated.clone()) else {
+                return Ok(false);
+            };
+            history.push(history_entry(
+                history_table_name,
+                &license.customer_id,
+                &license.vessel_id,
+                &license.license_key,
+                Some(license.clone()),
+            )?);
+
+            // tombstone closes history of the old entry - unreadable one has no history to close
+            if let (true, Ok(previous)) = (moved, from_item::<_, License>(item.clone())) {
+                history.push(history_entry(
+                    history_table_name,
+                    &previous.customer_id,
+                    &previous.vessel_id,
+                    &previous.license_key,
+                    None,
+                )?);
+            }
+        }
+
+        let mut writes = if moved {
+            // key change - new entry replaces the old one atomically
+            let (condition, names, values) = item_state_condition(item, &[]);
+            vec![
+                TransactWriteItem::builder()
+                    .put(
+                        Put::builder()
+                            .table_name(self.table_name.as_str())
+                            .set_item(Some(migrated))
+                            .condition_expression("attribute_not_exists(licenseKey)")
+                            .build(),
+                    )
+                    .build(),
+                TransactWriteItem::builder()
+                    .delete(
+                        Delete::builder()
+                            .table_name(self.table_name.as_str())
+                            .set_key(Some(key))
+                            .condition_expression(condition)
+                            .set_expression_attribute_names(Some(names))
+                            .set_expression_attribute_values(Some(values))
+                            .build(),
+                    )
+                    .build(),
+            ]
+        } else {
+            let added = migrated
+                .keys()
+                .filter(|name| !item.contains_key(*name))
+                .collect::<Vec<_>>();
+            let (condition, names, values) = item_state_condition(item, &added);
+            vec![TransactWriteItem::builder()
+                .put(
+                    Put::builder()
+                        .table_name(self.table_name.as_str())
+                        .set_item(Some(migrated))
+                        .condition_expression(condition)
+                        .set_expression_attribute_names(Some(names))
+                        .set_expression_attribute_values(Some(values))
+                        .build(),
+                )
+                .build()]
+        };
+        writes.extend(history);
+
+        match self
+            .client
+            .transact_write_items()
+            .set_transact_items(Some(writes))
+            .send()
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(error) if failed_conditions(&error).map_or(false, |failed| failed.contains(&true)) => Ok(false),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /**
+    Records current state of the license as its initial history entry, unless it already has one - `None` if nothing
+    needed to be recorded. Entry is conditioned on the scanned state of the license, so that stale state never lands
+    in history after the entry of a concurrent change.
+     */
+    async fn seed_history(&self, item: &Item) -> Result<Option<bool>, RuntimeError> {
+        let Some(history_table_name) = &self.history_table_name else {
+            return Ok(None);
+        };
+        let Ok(license) = from_item::<_, License>(item.clone()) else {
+            return Ok(Some(false));
+        };
+
+        let recorded = self
+            .client
+            .query()
+            .table_name(history_table_name.as_str())
+            .key_condition_expression("customerAndVesselId = :key AND begins_with(licenseVersion, :prefix)")
+            .expression_attribute_values(":key", S(partition_of(&license.customer_id, &license.vessel_id)))
+            .expression_attribute_values(":prefix", S(format!("{}#", license.license_key)))
+            .limit(1)
+            .consistent_read(true)
+            .send()
+            .await?
+            .count
+            > 0;
+        if recorded {
+            return Ok(None);
+        }
+        if self.dry_run {
+            return Ok(Some(true));
+        }
+
+        let (condition, names, values) = item_state_condition(item, &[]);
+        match self
+            .client
+            .transact_write_items()
+            .transact_items(
+                TransactWriteItem::builder()
+                    .condition_check(
+                        ConditionCheck::builder()
+                            .table_name(self.table_name.as_str())
+                            .set_key(Some(key_of(item)))
+                            .condition_expression(condition)
+                            .set_expression_attribute_names(Some(names))
+                            .set_expression_attribute_values(Some(values))
+                            .build(),
+                    )
+                    .build(),
+            )
+            .transact_items(history_entry(
+                history_table_name,
+                &license.customer_id,
+                &license.vessel_id,
+                &license.license_key,
+                Some(license.clone()),
+            )?)
+            .send()
+            .await
+        {
+            Ok(_) => Ok(Some(true)),
+            Err(error) if failed_conditions(&error).map_or(false, |failed| failed.contains(&true)) => Ok(Some(false)),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    async fn save_checkpoint(&self, version: u32, segment: i32, position: Option<Item>) -> Result<(), RuntimeError> {
+        let request = self
+            .client
+            .put_item()
+            .table_name(self.table_name.as_str())
+            .item(PARTITION_KEY, S(SCHEMA_PARTITION.into()))
+            .item(SORT_KEY, S(checkpoint_key(version, segment)))
+            .item(SEGMENT_ATTRIBUTE, N(segment.to_string()))
+            .item(TOTAL_SEGMENTS_ATTRIBUTE, N(self.segments.to_string()));
+
+        match position {
+            Some(position) => request.item(POSITION_ATTRIBUTE, M(position)),
+            None => request.item(DONE_ATTRIBUTE, Bool(true)),
+        }
+        .send()
+        .await?;
+        Ok(())
+    }
+
+    async fn complete(&self, previous: u32, version: u32) -> Result<(), MigrationError> {
+        let request = self
+            .client
+            .put_item()
+            .table_name(self.table_name.as_str())
+            .item(PARTITION_KEY, S(SCHEMA_PARTITION.into()))
+            .item(SORT_KEY, S(VERSION_KEY.into()))
+            .item(VERSION_ATTRIBUTE, N(version.to_string()))
+            .expression_attribute_names("#version", VERSION_ATTRIBUTE);
+        let request = if previous == 0 {
+            request.condition_expression("attribute_not_exists(#version)")
+        } else {
+            request
+                .condition_expression("#version = :previous")
+                .expression_attribute_values(":previous", N(previous.to_string()))
+        };
+
+        match request.send().await {
+            Ok(_) => {}
+            Err(error)
+                if error
+                    .as_service_error()
+                    .map_or(false, PutItemError::is_conditional_check_failed_exception) =>
+            {
+                return Err(MigrationError::Conflict(version))
+            }
+            Err(error) => return Err(RuntimeError::from(error).into()),
+        }
+
+        for segment in 0..self.segments {
+            self.client
+                .delete_item()
+                .table_name(self.table_name.as_str())
+                .key(PARTITION_KEY, S(SCHEMA_PARTITION.into()))
+                .key(SORT_KEY, S(checkpoint_key(version, segment)))
+                .send()
+                .await
+                .map_err(RuntimeError::from)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Item, Migration, MigrationError, MigrationReport, Migrator, SeedHistory};
+    use async_trait::async_trait;
+    use aws_config::load_from_env;
+    use aws_sdk_dynamodb::config::Builder;
+    use aws_sdk_dynamodb::types::AttributeValue::{Bool, N, S};
+    use aws_sdk_dynamodb::types::{AttributeDefinition, BillingMode, KeySchemaElement, KeyType, ScalarAttributeType};
+    use aws_sdk_dynamodb::Client;
+    use licenses_core::RuntimeError;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use test_context::{test_context, AsyncTestContext};
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000000");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+
+    static NUMBER: AtomicUsize = AtomicUsize::new(0);
+
+    struct DynamoDbTestContext {
+        client: Client,
+        table_name: String,
+        history_table_name: String,
+    }
+
+    #[async_trait]
+    impl AsyncTestContext for DynamoDbTestContext {
+        async fn setup() -> DynamoDbTestContext {
+            let number = NUMBER.fetch_add(1, Ordering::SeqCst);
+            let table_name = format!("Migrations{number}");
+            let history_table_name = format!("MigrationsHistory{number}");
+            let config = load_from_env().await;
+            let client = Client::from_conf(Builder::from(&config).endpoint_url("http://localhost:8000").build());
+
+            client
+                .create_table()
+                .table_name(table_name.as_str())
+                .attribute_definitions(
+                    AttributeDefinition::builder()
+                        .attribute_name("customerAndVesselId")
+                        .attribute_type(ScalarAttributeType::S)
+                        .build(),
+                )
+                .attribute_definitions(
+                    AttributeDefinition::builder()
+                        .attribute_name("licenseKey")
+                        .attribute_type(ScalarAttributeType::S)
+                        .build(),
+                )
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("customerAndVesselId")
+                        .key_type(KeyType::Hash)
+                        .build(),
+                )
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("licenseKey")
+                        .key_type(KeyType::Range)
+                        .build(),
+                )
+                .billing_mode(BillingMode::PayPerRequest)
+                .send()
+                .await
+                .unwrap();
+
+            client
+                .create_table()
+                .table_name(history_table_name.as_str())
+                .attribute_definitions(
+                    AttributeDefinition::builder()
+                        .attribute_name("customerAndVesselId")
+                        .attribute_type(ScalarAttributeType::S)
+                        .build(),
+                )
+                .attribute_definitions(
+                    AttributeDefinition::builder()
+                        .attribute_name("licenseVersion")
+                        .attribute_type(ScalarAttributeType::S)
+                        .build(),
+                )
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("customerAndVesselId")
+                        .key_type(KeyType::Hash)
+                        .build(),
+                )
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("licenseVersion")
+                        .key_type(KeyType::Range)
+                        .build(),
+                )
+                .billing_mode(BillingMode::PayPerRequest)
+                .send()
+                .await
+                .unwrap();
+
+            let context = DynamoDbTestContext {
+                client,
+                table_name,
+                history_table_name,
+            };
+            for (partition, license_key) in [
+                ("customer:vessel0", "tides.2023"),
+                ("customer:vessel0", "old.weather"),
+                ("customer:vessel1", "tides.2023"),
+            ] {
+                context.put(partition, license_key, vec![]).await;
+            }
+
+            context
+        }
+
+        async fn teardown(self) {
+            self.client
+                .delete_table()
+                .table_name(self.table_name)
+                .send()
+                .await
+                .unwrap();
+            self.client
+                .delete_table()
+                .table_name(self.history_table_name)
+                .send()
+                .await
+                .unwrap();
+        }
+    }
+
+    impl DynamoDbTestContext {
+        fn migrator(&self, segments: i32) -> Migrator {
+            Migrator::new(self.client.clone(), self.table_name.clone()).with_segments(segments)
+        }
+
+        async fn put(
+            &self,
+            partition: &str,
+            license_key: &str,
+            attributes: Vec<(&str, aws_sdk_dynamodb::types::AttributeValue)>,
+        ) {
+            let mut request = self
+                .client
+                .put_item()
+                .table_name(self.table_name.as_str())
+                .item("customerAndVesselId", S(partition.into()))
+                .item("licenseKey", S(license_key.into()));
+            for (name, value) in attributes {
+                request = request.item(name, value);
+            }
+            request.send().await.unwrap();
+        }
+
+        async fn get(&self, partition: &str, license_key: &str) -> Option<Item> {
+            self.client
+                .get_item()
+                .table_name(self.table_name.as_str())
+                .key("customerAndVesselId", S(partition.into()))
+                .key("licenseKey", S(license_key.into()))
+                .send()
+                .await
+                .unwrap()
+                .item
+        }
+
+        async fn put_license(&self, license_key: &str) {
+            self.put(
+                &format!("{CUSTOMER_ID}:{VESSEL_ID}"),
+                license_key,
+                vec![
+                    ("customerId", S(CUSTOMER_ID.to_string())),
+                    ("vesselId", S(VESSEL_ID.to_string())),
+                ],
+            )
+            .await;
+        }
+
+        async fn history(&self) -> Vec<Item> {
+            self.client
+                .query()
+                .table_name(self.history_table_name.as_str())
+                .key_condition_expression("customerAndVesselId = :key")
+                .expression_attribute_values(":key", S(format!("{CUSTOMER_ID}:{VESSEL_ID}")))
+                .send()
+                .await
+                .unwrap()
+                .items
+                .unwrap_or_default()
+        }
+
+        async fn count(&self) -> i32 {
+            self.client
+                .scan()
+                .table_name(self.table_name.as_str())
+                .send()
+                .await
+                .unwrap()
+                .count
+        }
+    }
+
+    // marks every item with `migrated{version}` flag
+    struct Mark(u32);
+
+    impl Migration for Mark {
+        fn version(&self) -> u32 {
+            self.0
+        }
+
+        fn description(&self) -> &str {
+            "Mark items."
+        }
+
+        fn migrate(&self, item: &Item) -> Result<Option<Item>, RuntimeError> {
+            let attribute = format!("migrated{}", self.0);
+            if item.contains_key(&attribute) {
+                return Ok(None);
+            }
+
+            let mut migrated = item.clone();
+            migrated.insert(attribute, Bool(true));
+            Ok(Some(migrated))
+        }
+    }
+
+    // renames `old.*` license keys to `new.*`
+    struct Rename;
+
+    impl Migration for Rename {
+        fn version(&self) -> u32 {
+            1
+        }
+
+        fn description(&self) -> &str {
+            "Rename keys."
+        }
+
+        fn migrate(&self, item: &Item) -> Result<Option<Item>, RuntimeError> {
+            let Some(license_key) = item["licenseKey"].as_s().ok().and_then(|key| key.strip_prefix("old.")) else {
+                return Ok(None);
+            };
+
+            let mut migrated = item.clone();
+            migrated.insert("licenseKey".into(), S(format!("new.{license_key}")));
+            Ok(Some(migrated))
+        }
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn migrate(ctx: &DynamoDbTestContext) -> Result<(), MigrationError> {
+        let migrator = ctx.migrator(2);
+
+        let reports = migrator.run(&[Box::new(Mark(1)), Box::new(Mark(2))]).await?;
+        assert_eq!(
+            vec![
+                MigrationReport {
+                    version: 1,
+                    description: "Mark items.".into(),
+                    scanned: 3,
+                    changed: 3,
+                    skipped: 0,
+                },
+                MigrationReport {
+                    version: 2,
+                    description: "Mark items.".into(),
+                    scanned: 3,
+                    changed: 3,
+                    skipped: 0,
+                },
+            ],
+            reports
+        );
+        assert_eq!(2, migrator.current_version().await?);
+
+        let item = ctx.get("customer:vessel1", "tides.2023").await.unwrap();
+        assert!(item.contains_key("migrated1"));
+        assert!(item.contains_key("migrated2"));
+
+        // three licenses and version entry - checkpoints are cleaned up
+        assert_eq!(4, ctx.count().await);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn migrate_applied(ctx: &DynamoDbTestContext) -> Result<(), MigrationError> {
+        let migrator = ctx.migrator(1);
+
+        migrator.run(&[Box::new(Mark(1))]).await?;
+        assert!(migrator.run(&[Box::new(Mark(1))]).await?.is_empty());
+
+        let reports = migrator.run(&[Box::new(Mark(1)), Box::new(Mark(2))]).await?;
+        assert_eq!(1, reports.len());
+        assert_eq!(2, reports[0].version);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn migrate_dry_run(ctx: &DynamoDbTestContext) -> Result<(), MigrationError> {
+        let migrator = ctx.migrator(2).with_dry_run(true);
+
+        let reports = migrator.run(&[Box::new(Mark(1))]).await?;
+        assert_eq!(3, reports[0].changed);
+        assert_eq!(0, migrator.current_version().await?);
+
+        let item = ctx.get("customer:vessel0", "tides.2023").await.unwrap();
+        assert!(!item.contains_key("migrated1"));
+        assert_eq!(3, ctx.count().await);
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn migrate_from_checkpoint(ctx: &DynamoDbTestContext) -> Result<(), MigrationError> {
+        ctx.put(
+            "#schema",
+            "checkpoint#1#0",
+            vec![
+                ("segment", N("0".into())),
+                ("totalSegments", N("1".into())),
+                ("done", Bool(true)),
+            ],
+        )
+        .await;
+
+        let migrator = ctx.migrator(1);
+        let reports = migrator.run(&[Box::new(Mark(1))]).await?;
+        assert_eq!(0, reports[0].scanned);
+        assert_eq!(1, migrator.current_version().await?);
+
+        // segment was already done, so items were not touched again
+        let item = ctx.get("customer:vessel0", "tides.2023").await.unwrap();
+        assert!(!item.contains_key("migrated1"));
+        assert!(ctx.get("#schema", "checkpoint#1#0").await.is_none());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn migrate_segments_mismatch(ctx: &DynamoDbTestContext) -> Result<(), MigrationError> {
+        ctx.put(
+            "#schema",
+            "checkpoint#1#0",
+            vec![
+                ("segment", N("0".into())),
+                ("totalSegments", N("3".into())),
+                ("done", Bool(true)),
+            ],
+        )
+        .await;
+
+        match ctx.migrator(1).run(&[Box::new(Mark(1))]).await {
+            Err(MigrationError::SegmentsMismatch(1, 3)) => {}
+            _ => panic!("Segments mismatch error expected."),
+        }
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn migrate_key(ctx: &DynamoDbTestContext) -> Result<(), MigrationError> {
+        let reports = ctx.migrator(2).run(&[Box::new(Rename)]).await?;
+        assert_eq!(1, reports[0].changed);
+
+        assert!(ctx.get("customer:vessel0", "old.weather").await.is_none());
+        assert!(ctx.get("customer:vessel0", "new.weather").await.is_some());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn migrate_history(ctx: &DynamoDbTestContext) -> Result<(), MigrationError> {
+        ctx.put_license("old.weather").await;
+
+        let reports = ctx
+            .migrator(1)
+            .with_history(ctx.history_table_name.clone())
+            .run(&[Box::new(Rename)])
+            .await?;
+        // invalid license can't be recorded in history
+        assert_eq!(1, reports[0].changed);
+        assert_eq!(1, reports[0].skipped);
+        assert!(ctx.get("customer:vessel0", "old.weather").await.is_some());
+
+        let history = ctx.history().await;
+        assert_eq!(2, history.len());
+        // entries are sorted by license key - new license state and tombstone of the old key
+        assert_eq!(Some(&S("new.weather".into())), history[0].get("licenseKey"));
+        assert!(!history[0].contains_key("deleted"));
+        assert_eq!(Some(&S("old.weather".into())), history[1].get("licenseKey"));
+        assert_eq!(Some(&Bool(true)), history[1].get("deleted"));
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn migrate_seed_history(ctx: &DynamoDbTestContext) -> Result<(), MigrationError> {
+        ctx.put_license("tides.2023").await;
+        let migrator = ctx.migrator(2).with_history(ctx.history_table_name.clone());
+
+        let reports = migrator.run(&[Box::new(SeedHistory)]).await?;
+        // invalid licenses can't be recorded in history
+        assert_eq!(1, reports[0].changed);
+        assert_eq!(3, reports[0].skipped);
+
+        let history = ctx.history().await;
+        assert_eq!(1, history.len());
+        assert_eq!(Some(&S("tides.2023".into())), history[0].get("licenseKey"));
+
+        // license with history already recorded is left alone
+        let item = ctx
+            .get(&format!("{CUSTOMER_ID}:{VESSEL_ID}"), "tides.2023")
+            .await
+            .unwrap();
+        assert_eq!(None, migrator.seed_history(&item).await?);
+        assert_eq!(1, ctx.history().await.len());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn migrate_seed_history_changed_item(ctx: &DynamoDbTestContext) -> Result<(), MigrationError> {
+        ctx.put_license("tides.2023").await;
+        let migrator = ctx.migrator(1).with_history(ctx.history_table_name.clone());
+        let scanned = ctx
+            .get(&format!("{CUSTOMER_ID}:{VESSEL_ID}"), "tides.2023")
+            .await
+            .unwrap();
+
+        // concurrent update
+        let mut changed = scanned.clone();
+        changed.insert("count".into(), N("5".into()));
+        ctx.client
+            .put_item()
+            .table_name(ctx.table_name.as_str())
+            .set_item(Some(changed))
+            .send()
+            .await
+            .map_err(RuntimeError::from)?;
+
+        assert_eq!(Some(false), migrator.seed_history(&scanned).await?);
+        assert!(ctx.history().await.is_empty());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn migrate_seed_history_required(ctx: &DynamoDbTestContext) -> Result<(), MigrationError> {
+        match ctx.migrator(1).run(&[Box::new(SeedHistory)]).await {
+            Err(MigrationError::HistoryRequired(2)) => {}
+            _ => panic!("History required error expected."),
+        }
+        assert!(ctx.history().await.is_empty());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn migrate_changed_item(ctx: &DynamoDbTestContext) -> Result<(), MigrationError> {
+        let migrator = ctx.migrator(1);
+        ctx.put("customer:vessel0", "tides.2023", vec![("count", N("2".into()))])
+            .await;
+        let scanned = ctx.get("customer:vessel0", "tides.2023").await.unwrap();
+        let migrated = Mark(1).migrate(&scanned)?.unwrap();
+
+        // concurrent update
+        ctx.put("customer:vessel0", "tides.2023", vec![("count", N("5".into()))])
+            .await;
+        assert!(!migrator.save(&scanned, migrated.clone()).await?);
+
+        let item = ctx.get("customer:vessel0", "tides.2023").await.unwrap();
+        assert!(!item.contains_key("migrated1"));
+        assert_eq!("5", item["count"].as_n().unwrap());
+
+        // concurrent deletion
+        ctx.client
+            .delete_item()
+            .table_name(ctx.table_name.as_str())
+            .key("customerAndVesselId", S("customer:vessel0".into()))
+            .key("licenseKey", S("tides.2023".into()))
+            .send()
+            .await
+            .map_err(RuntimeError::from)?;
+        assert!(!migrator.save(&scanned, migrated).await?);
+        assert!(ctx.get("customer:vessel0", "tides.2023").await.is_none());
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn migrate_unordered(ctx: &DynamoDbTestContext) -> Result<(), MigrationError> {
+        match ctx.migrator(1).run(&[Box::new(Mark(2)), Box::new(Mark(1))]).await {
+            Err(MigrationError::Unordered(1)) => {}
+            _ => panic!("Unordered migrations error expected."),
+        }
+
+        Ok(())
+    }
+
+    #[test_context(DynamoDbTestContext)]
+    #[tokio_test]
+    async fn migrate_unknown_version(ctx: &DynamoDbTestContext) -> Result<(), MigrationError> {
+        ctx.put("#schema", "version", vec![("version", N("5".into()))]).await;
+
+        match ctx.migrator(1).run(&[Box::new(Mark(1))]).await {
+            Err(MigrationError::UnknownVersion(5)) => {}
+            _ => panic!("Unknown version error expected."),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use chrono::Duration;
+use clap::Parser;
+use licenses_core::load_client;
+use licenses_migrator::{migrations, Cli, Migrator};
+use std::error::Error;
+use tokio::main as tokio_main;
+
+#[tokio_main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+
+    let cli = Cli::parse();
+
+    let client = load_client(cli.endpoint_url).await;
+    let migrator = Migrator::new(client, cli.table)
+        .with_segments(cli.segments)
+        .with_dry_run(cli.dry_run);
+    let migrator = match cli.history_table {
+        Some(history_table) => migrator.with_history(history_table),
+        None => migrator,
+    };
+
+    for report in migrator.run(&migrations(Duration::days(cli.retention_days))).await? {
+        println!(
+            "{}: {} ({} items scanned, {} changed, {} skipped)",
+            report.version, report.description, report.scanned, report.changed, report.skipped
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use crate::{Item, Migration};
+use aws_sdk_dynamodb::types::AttributeValue::N;
+use chrono::{DateTime, Duration};
+use licenses_core::{ttl_of, RuntimeError, TTL_ATTRIBUTE};
+
+/**
+Licenses written before TTL support have no `ttl` attribute, so DynamoDB would keep them forever.
+ */
+pub struct BackfillTtl {
+    retention: Duration,
+}
+
+impl BackfillTtl {
+    pub fn new(retention: Duration) -> Self {
+        Self { retention }
+    }
+}
+
+impl Migration for BackfillTtl {
+    fn version(&self) -> u32 {
+        1
+    }
+
+    fn description(&self) -> &str {
+        "Fill TTL of licenses with expiration date."
+    }
+
+    fn migrate(&self, item: &Item) -> Result<Option<Item>, RuntimeError> {
+        let Some(value) = item.get("expiresAt").filter(|_| !item.contains_key(TTL_ATTRIBUTE)) else {
+            return Ok(None);
+        };
+        let expires_at = value
+            .as_s()
+            .ok()
+            .and_then(|expires_at| DateTime::parse_from_rfc3339(expires_at).ok())
+            .ok_or_else(|| RuntimeError::DataError(value.clone(), "expiresAt".into()))?;
+
+        let mut migrated = item.clone();
+        migrated.insert(TTL_ATTRIBUTE.into(), N(ttl_of(&expires_at, self.retention).to_string()));
+        Ok(Some(migrated))
+    }
+}
+
+/**
+Licenses created before history was introduced have no history entries, so their state can't be resolved for any moment
+- current state is recorded as the initial entry. Items themselves are not changed.
+ */
+pub struct SeedHistory;
+
+impl Migration for SeedHistory {
+    fn version(&self) -> u32 {
+        2
+    }
+
+    fn description(&self) -> &str {
+        "Record initial history entry of licenses without history."
+    }
+
+    fn migrate(&self, _item: &Item) -> Result<Option<Item>, RuntimeError> {
+        Ok(None)
+    }
+
+    fn seeds_history(&self) -> bool {
+        true
+    }
+}
+
+/**
+All schema migrations, in order of application - new steps are always appended with the next version number.
+ */
+pub fn migrations(retention: Duration) -> Vec<Box<dyn Migration>> {
+    vec![Box::new(BackfillTtl::new(retention)), Box::new(SeedHistory)]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BackfillTtl, Item, Migration, SeedHistory};
+    use aws_sdk_dynamodb::types::AttributeValue::{N, S};
+    use chrono::Duration;
+    use licenses_core::RuntimeError;
+
+    fn item(expires_at: Option<&str>) -> Item {
+        let mut item = Item::from([
+            ("customerAndVesselId".into(), S("customer:vessel".into())),
+            ("licenseKey".into(), S("tides.2023".into())),
+        ]);
+        if let Some(expires_at) = expires_at {
+            item.insert("expiresAt".into(), S(expires_at.into()));
+        }
+        item
+    }
+
+    #[test]
+    fn backfill_ttl() {
+        let migrated = BackfillTtl::new(Duration::days(1))
+            .migrate(&item(Some("2023-07-01T02:00:00+02:00")))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!("1688256000", migrated["ttl"].as_n().unwrap());
+        assert_eq!("tides.2023", migrated["licenseKey"].as_s().unwrap());
+    }
+
+    #[test]
+    fn backfill_ttl_perpetual() {
+        assert!(BackfillTtl::new(Duration::days(1))
+            .migrate(&item(None))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn backfill_ttl_already_set() {
+        let mut item = item(Some("2023-07-01T02:00:00+02:00"));
+        item.insert("ttl".into(), N("1".into()));
+
+        assert!(BackfillTtl::new(Duration::days(1)).migrate(&item).unwrap().is_none());
+    }
+
+    #[test]
+    fn backfill_ttl_invalid_date() {
+        match BackfillTtl::new(Duration::days(1)).migrate(&item(Some("yesterday"))) {
+            Err(RuntimeError::DataError(_, attribute)) => assert_eq!("expiresAt", attribute),
+            _ => panic!("Data error expected."),
+        }
+    }
+
+    #[test]
+    fn seed_history() {
+        let item = item(Some("2023-07-01T02:00:00+02:00"));
+
+        assert!(SeedHistory.migrate(&item).unwrap().is_none());
+        assert!(SeedHistory.seeds_history());
+    }
+}
@@ -0,0 +1,247 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use licenses_api_types::{ListPoolsResponse, PoolResponse, PoolsRequest, PoolsResponse};
+use licenses_core::{ApiError, Pool, PoolRepository, ProductRepository, SeatsAllocation, Validate, ValidationRules};
+
+pub async fn handler<Q: PoolRepository, P: ProductRepository>(
+    pools: &Q,
+    products: &P,
+    rules: &ValidationRules,
+    request: PoolsRequest,
+) -> Result<PoolsResponse, ApiError> {
+    Ok(match request {
+        PoolsRequest::Create(request) => {
+            request.validate(rules)?;
+
+            let product_key = request.product_key.clone();
+            if products.get_product(product_key.clone()).await?.is_none() {
+                return Err(ApiError::ProductNotFound(product_key));
+            }
+
+            if !pools.insert_pool(Pool::from(request)).await? {
+                return Err(ApiError::PoolAlreadyExists(product_key));
+            }
+            PoolsResponse::Create(product_key)
+        }
+        PoolsRequest::List(request) => {
+            request.validate(rules)?;
+
+            PoolsResponse::List(ListPoolsResponse {
+                pools: pools
+                    .list_pools(request.customer_id)
+                    .await?
+                    .into_iter()
+                    .map(PoolResponse::from)
+                    .collect(),
+            })
+        }
+        PoolsRequest::Allocate(request) => {
+            request.validate(rules)?;
+
+            let Some(pool) = pools.get_pool(request.customer_id, request.product_key.clone()).await? else {
+                return Err(ApiError::PoolNotFound(request.product_key));
+            };
+
+            match pools.allocate_seats(&pool, request.vessel_id, request.seats).await? {
+                SeatsAllocation::Allocated => PoolsResponse::Allocate(()),
+                SeatsAllocation::InsufficientSeats => return Err(ApiError::InsufficientSeats(request.product_key)),
+                SeatsAllocation::AlreadyAllocated => return Err(ApiError::LicenseAlreadyExists(request.product_key)),
+            }
+        }
+        PoolsRequest::Release(request) => {
+            request.validate(rules)?;
+
+            if !pools
+                .release_seats(request.customer_id, request.product_key.clone(), request.vessel_id)
+                .await?
+            {
+                return Err(ApiError::LicenseNotFound(request.product_key));
+            }
+            PoolsResponse::Release(())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handler;
+    use licenses_api_types::{PoolsRequest, PoolsResponse, Seats};
+    use licenses_core::{
+        ApiError, InMemoryLicenseRepository, InMemoryPoolRepository, InMemoryProductRepository, LicenseRepository,
+        Product, ValidationRules,
+    };
+    use serde_json::{from_str, json, to_value};
+    use std::sync::Arc;
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    const VESSEL_ID_0: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    const VESSEL_ID_1: Uuid = uuid!("00000000-0000-0000-0000-000000000003");
+    const PRODUCT_KEY: &str = "tides.2023";
+
+    fn request(payload: &str) -> PoolsRequest {
+        from_str(payload).unwrap()
+    }
+
+    fn products() -> InMemoryProductRepository {
+        InMemoryProductRepository::from([Product {
+            product_key: PRODUCT_KEY.into(),
+            display_name: "Tides 2023".into(),
+            default_count: None,
+            default_duration_days: None,
+        }])
+    }
+
+    fn create(total_seats: u32) -> PoolsRequest {
+        request(&format!(
+            "{{\"operation\":\"create\",\"customerId\":\"{CUSTOMER_ID}\",\"productKey\":\"{PRODUCT_KEY}\",\"totalSeats\":{total_seats}}}"
+        ))
+    }
+
+    fn allocate(vessel_id: Uuid, seats: u32) -> PoolsRequest {
+        request(&format!(
+            "{{\"operation\":\"allocate\",\"customerId\":\"{CUSTOMER_ID}\",\"productKey\":\"{PRODUCT_KEY}\",\"vesselId\":\"{vessel_id}\",\"seats\":{seats}}}"
+        ))
+    }
+
+    fn release(vessel_id: Uuid) -> PoolsRequest {
+        request(&format!(
+            "{{\"operation\":\"release\",\"customerId\":\"{CUSTOMER_ID}\",\"productKey\":\"{PRODUCT_KEY}\",\"vesselId\":\"{vessel_id}\"}}"
+        ))
+    }
+
+    #[tokio_test]
+    async fn manage_pool() {
+        let licenses = Arc::new(InMemoryLicenseRepository::new());
+        let pools = InMemoryPoolRepository::new(licenses.clone());
+        let products = products();
+        let rules = ValidationRules::default();
+
+        let response = handler(&pools, &products, &rules, create(10)).await.unwrap();
+        assert_eq!(json!(PRODUCT_KEY), to_value(response).unwrap());
+
+        handler(&pools, &products, &rules, allocate(VESSEL_ID_0, 4))
+            .await
+            .unwrap();
+        handler(&pools, &products, &rules, allocate(VESSEL_ID_1, 6))
+            .await
+            .unwrap();
+
+        let license = licenses
+            .get_license(CUSTOMER_ID, VESSEL_ID_0, PRODUCT_KEY.into())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some(Seats::Limited(4)), license.count);
+
+        handler(&pools, &products, &rules, release(VESSEL_ID_0)).await.unwrap();
+
+        let list = handler(
+            &pools,
+            &products,
+            &rules,
+            request(&format!("{{\"operation\":\"list\",\"customerId\":\"{CUSTOMER_ID}\"}}")),
+        )
+        .await
+        .unwrap();
+        match list {
+            PoolsResponse::List(response) => {
+                assert_eq!(1, response.pools.len());
+                assert_eq!(10, response.pools[0].total_seats);
+                assert_eq!(6, response.pools[0].allocated_seats);
+            }
+            _ => panic!("List response expected."),
+        }
+    }
+
+    #[tokio_test]
+    async fn create_duplicated_pool() {
+        let pools = InMemoryPoolRepository::new(Arc::new(InMemoryLicenseRepository::new()));
+        let products = products();
+        let rules = ValidationRules::default();
+
+        handler(&pools, &products, &rules, create(10)).await.unwrap();
+        match handler(&pools, &products, &rules, create(20)).await {
+            Err(ApiError::PoolAlreadyExists(product_key)) => assert_eq!(PRODUCT_KEY, product_key),
+            _ => panic!("Pool already exists error expected."),
+        }
+    }
+
+    #[tokio_test]
+    async fn create_pool_of_unknown_product() {
+        let pools = InMemoryPoolRepository::new(Arc::new(InMemoryLicenseRepository::new()));
+
+        match handler(
+            &pools,
+            &InMemoryProductRepository::new(),
+            &ValidationRules::default(),
+            create(10),
+        )
+        .await
+        {
+            Err(ApiError::ProductNotFound(product_key)) => assert_eq!(PRODUCT_KEY, product_key),
+            _ => panic!("Product not found error expected."),
+        }
+    }
+
+    #[tokio_test]
+    async fn allocate_without_pool() {
+        let pools = InMemoryPoolRepository::new(Arc::new(InMemoryLicenseRepository::new()));
+
+        match handler(
+            &pools,
+            &products(),
+            &ValidationRules::default(),
+            allocate(VESSEL_ID_0, 1),
+        )
+        .await
+        {
+            Err(ApiError::PoolNotFound(product_key)) => assert_eq!(PRODUCT_KEY, product_key),
+            _ => panic!("Pool not found error expected."),
+        }
+    }
+
+    #[tokio_test]
+    async fn allocate_too_many_seats() {
+        let pools = InMemoryPoolRepository::new(Arc::new(InMemoryLicenseRepository::new()));
+        let products = products();
+        let rules = ValidationRules::default();
+
+        handler(&pools, &products, &rules, create(3)).await.unwrap();
+        match handler(&pools, &products, &rules, allocate(VESSEL_ID_0, 4)).await {
+            Err(ApiError::InsufficientSeats(product_key)) => assert_eq!(PRODUCT_KEY, product_key),
+            _ => panic!("Insufficient seats error expected."),
+        }
+    }
+
+    #[tokio_test]
+    async fn allocate_twice() {
+        let pools = InMemoryPoolRepository::new(Arc::new(InMemoryLicenseRepository::new()));
+        let products = products();
+        let rules = ValidationRules::default();
+
+        handler(&pools, &products, &rules, create(3)).await.unwrap();
+        handler(&pools, &products, &rules, allocate(VESSEL_ID_0, 1))
+            .await
+            .unwrap();
+        match handler(&pools, &products, &rules, allocate(VESSEL_ID_0, 1)).await {
+            Err(ApiError::LicenseAlreadyExists(license_key)) => assert_eq!(PRODUCT_KEY, license_key),
+            _ => panic!("License already exists error expected."),
+        }
+    }
+
+    #[tokio_test]
+    async fn release_unallocated() {
+        let pools = InMemoryPoolRepository::new(Arc::new(InMemoryLicenseRepository::new()));
+
+        match handler(&pools, &products(), &ValidationRules::default(), release(VESSEL_ID_0)).await {
+            Err(ApiError::LicenseNotFound(license_key)) => assert_eq!(PRODUCT_KEY, license_key),
+            _ => panic!("License not found error expected."),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+#![feature(future_join)]
+
+use lambda_runtime::{Error, LambdaEvent};
+use licenses_api_types::PoolsRequest;
+use licenses_core::{run_lambda, PoolDao, ProductDao, ValidationRules};
+use licenses_pools::handler;
+use tokio::main as tokio_main;
+
+#[tokio_main]
+async fn main() -> Result<(), Error> {
+    let pools = &PoolDao::load_from_env().await?;
+    let products = &ProductDao::load_from_env().await?;
+    let rules = &ValidationRules::load_from_env()?;
+
+    run_lambda!(move |event: LambdaEvent<PoolsRequest>| handler(pools, products, rules, event.payload))
+}
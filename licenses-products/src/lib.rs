@@ -0,0 +1,127 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use licenses_api_types::{ListProductsResponse, ProductResponse, ProductsRequest, ProductsResponse};
+use licenses_core::{ApiError, Product, ProductRepository, Validate, ValidationRules};
+
+pub async fn handler<P: ProductRepository>(
+    products: &P,
+    rules: &ValidationRules,
+    request: ProductsRequest,
+) -> Result<ProductsResponse, ApiError> {
+    Ok(match request {
+        ProductsRequest::Save(request) => {
+            request.validate(rules)?;
+
+            let product_key = request.product_key.clone();
+            products.save_product(Product::from(request)).await?;
+            ProductsResponse::Save(product_key)
+        }
+        ProductsRequest::Get(request) => {
+            request.validate(rules)?;
+
+            match products.get_product(request.product_key.clone()).await? {
+                None => return Err(ApiError::ProductNotFound(request.product_key)),
+                Some(product) => ProductsResponse::Get(ProductResponse::from(product)),
+            }
+        }
+        ProductsRequest::List => ProductsResponse::List(ListProductsResponse {
+            products: products
+                .list_products()
+                .await?
+                .into_iter()
+                .map(ProductResponse::from)
+                .collect(),
+        }),
+        ProductsRequest::Delete(request) => {
+            request.validate(rules)?;
+
+            products.delete_product(request.product_key).await?;
+            ProductsResponse::Delete(())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handler;
+    use licenses_api_types::{ProductsRequest, ProductsResponse, Seats};
+    use licenses_core::{ApiError, InMemoryProductRepository, ProductRepository, ValidationRules};
+    use serde_json::{from_str, json, to_value};
+    use tokio::test as tokio_test;
+
+    const PRODUCT_KEY: &str = "tides.2023";
+
+    fn request(payload: &str) -> ProductsRequest {
+        from_str(payload).unwrap()
+    }
+
+    #[tokio_test]
+    async fn manage_products() {
+        let products = InMemoryProductRepository::new();
+        let rules = ValidationRules::default();
+
+        let response = handler(
+            &products,
+            &rules,
+            request(&format!(
+                "{{\"operation\":\"save\",\"productKey\":\"{PRODUCT_KEY}\",\"displayName\":\"Tides 2023\",\"defaultCount\":2}}"
+            )),
+        )
+        .await
+        .unwrap();
+        assert_eq!(json!(PRODUCT_KEY), to_value(response).unwrap());
+
+        let get = || request(&format!("{{\"operation\":\"get\",\"productKey\":\"{PRODUCT_KEY}\"}}"));
+        match handler(&products, &rules, get()).await.unwrap() {
+            ProductsResponse::Get(product) => {
+                assert_eq!("Tides 2023", product.display_name);
+                assert_eq!(Some(Seats::Limited(2)), product.default_count);
+            }
+            _ => panic!("Get response expected."),
+        }
+
+        match handler(&products, &rules, request("{\"operation\":\"list\"}"))
+            .await
+            .unwrap()
+        {
+            ProductsResponse::List(response) => assert_eq!(1, response.products.len()),
+            _ => panic!("List response expected."),
+        }
+
+        handler(
+            &products,
+            &rules,
+            request(&format!(
+                "{{\"operation\":\"delete\",\"productKey\":\"{PRODUCT_KEY}\"}}"
+            )),
+        )
+        .await
+        .unwrap();
+        match handler(&products, &rules, get()).await {
+            Err(ApiError::ProductNotFound(product_key)) => assert_eq!(PRODUCT_KEY, product_key),
+            _ => panic!("Product not found error expected."),
+        }
+    }
+
+    #[tokio_test]
+    async fn save_invalid_product() {
+        let products = InMemoryProductRepository::new();
+
+        let result = handler(
+            &products,
+            &ValidationRules::default(),
+            request("{\"operation\":\"save\",\"productKey\":\"\",\"displayName\":\"Empty\"}"),
+        )
+        .await;
+
+        match result {
+            Err(ApiError::ValidationFailed(errors)) => assert_eq!("productKey", errors[0].field),
+            _ => panic!("Validation error expected."),
+        }
+        assert!(products.list_products().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,73 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use chrono::Duration;
+use licenses_api_types::{SummaryRequest, SummaryResponse};
+use licenses_core::{ApiError, LicenseRepository, Validate, ValidationRules};
+
+pub async fn handler<R: LicenseRepository>(
+    repository: &R,
+    rules: &ValidationRules,
+    request: SummaryRequest,
+) -> Result<SummaryResponse, ApiError> {
+    request.validate(rules)?;
+
+    repository
+        .summarize(
+            request.customer_id,
+            request.vessel_id,
+            Duration::days(request.expiring_within_days.into()),
+        )
+        .await
+        .map(SummaryResponse::from)
+        .map_err(ApiError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handler;
+    use licenses_api_types::{Entitlements, Seats, SummaryRequest};
+    use licenses_core::{InMemoryLicenseRepository, License, LicenseRepository, ValidationRules};
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    const EXPIRING_WITHIN_DAYS: u16 = 7;
+
+    #[tokio_test]
+    async fn summarize_licenses() {
+        let repository = InMemoryLicenseRepository::new();
+        repository
+            .create_license(License {
+                customer_id: CUSTOMER_ID,
+                vessel_id: VESSEL_ID,
+                license_key: "tides.2023".to_string(),
+                count: Some(Seats::Limited(3)),
+                expires_at: None,
+                entitlements: Entitlements::new(),
+                trial: None,
+            })
+            .await
+            .unwrap();
+
+        let response = handler(
+            &repository,
+            &ValidationRules::default(),
+            SummaryRequest {
+                customer_id: CUSTOMER_ID,
+                vessel_id: None,
+                expiring_within_days: EXPIRING_WITHIN_DAYS,
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(1, response.total);
+        assert_eq!(1, response.active);
+        assert_eq!(3, response.seats);
+    }
+}
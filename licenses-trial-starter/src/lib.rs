@@ -0,0 +1,226 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+use chrono::{Duration, Utc};
+use licenses_api_types::{CreateLicenseRequest, Entitlements, LicenseResponse, Trial, TrialsRequest, TrialsResponse};
+use licenses_core::{
+    ApiError, License, LicenseRepository, ProductRepository, TrialRepository, TrialStart, Validate, ValidationRules,
+};
+use licenses_creator::license_of;
+
+pub const DEFAULT_TRIAL_DURATION_DAYS: i64 = 30;
+
+pub async fn handler<T: TrialRepository, R: LicenseRepository, P: ProductRepository>(
+    trials: &T,
+    repository: &R,
+    products: &P,
+    rules: &ValidationRules,
+    duration: Duration,
+    request: TrialsRequest,
+) -> Result<TrialsResponse, ApiError> {
+    Ok(match request {
+        TrialsRequest::Start(request) => {
+            request.validate(rules)?;
+
+            // trial is a regular license of catalogue product, only with fixed validity period
+            let now = Utc::now();
+            let mut license = license_of(
+                products,
+                rules,
+                CreateLicenseRequest {
+                    customer_id: request.customer_id,
+                    vessel_id: request.vessel_id,
+                    license_key: request.license_key.clone(),
+                    count: None,
+                    expires_at: Some((now + duration).into()),
+                    entitlements: Entitlements::new(),
+                },
+            )
+            .await?;
+            let trial = Trial {
+                origin: request.origin,
+                started_at: now.into(),
+            };
+
+            match trials.start_trial(license.clone(), trial.clone()).await? {
+                TrialStart::Started => {
+                    license.trial = Some(trial);
+                    TrialsResponse::Start(LicenseResponse::from(license))
+                }
+                TrialStart::AlreadyUsed => return Err(ApiError::TrialAlreadyUsed(request.license_key)),
+                TrialStart::LicenseExists => return Err(ApiError::LicenseAlreadyExists(request.license_key)),
+            }
+        }
+        TrialsRequest::Convert(request) => {
+            request.validate(rules)?;
+
+            let license = match repository
+                .get_license(request.customer_id, request.vessel_id, request.license_key.clone())
+                .await?
+            {
+                None => return Err(ApiError::LicenseNotFound(request.license_key)),
+                Some(license) if !license.is_trial() => return Err(ApiError::NotTrial(request.license_key)),
+                Some(license) => License {
+                    expires_at: request.expires_at,
+                    trial: None,
+                    ..license
+                },
+            };
+
+            if !trials.convert_trial(license.clone()).await? {
+                return Err(ApiError::NotTrial(request.license_key));
+            }
+            TrialsResponse::Convert(LicenseResponse::from(license))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{handler, DEFAULT_TRIAL_DURATION_DAYS};
+    use chrono::{Duration, Utc};
+    use licenses_api_types::{Seats, TrialsRequest, TrialsResponse};
+    use licenses_core::{
+        ApiError, InMemoryLicenseRepository, InMemoryProductRepository, InMemoryTrialRepository, LicenseRepository,
+        Product, ValidationRules,
+    };
+    use serde_json::from_str;
+    use std::sync::Arc;
+    use tokio::test as tokio_test;
+    use uuid::{uuid, Uuid};
+
+    const CUSTOMER_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000001");
+    const VESSEL_ID: Uuid = uuid!("00000000-0000-0000-0000-000000000002");
+    const LICENSE_KEY: &str = "tides.2023";
+
+    struct Context {
+        licenses: Arc<InMemoryLicenseRepository>,
+        trials: InMemoryTrialRepository,
+        products: InMemoryProductRepository,
+        rules: ValidationRules,
+    }
+
+    impl Context {
+        fn new() -> Self {
+            let licenses = Arc::new(InMemoryLicenseRepository::new());
+
+            Self {
+                trials: InMemoryTrialRepository::new(licenses.clone()),
+                licenses,
+                products: InMemoryProductRepository::from([Product {
+                    product_key: LICENSE_KEY.into(),
+                    display_name: "Tides 2023".into(),
+                    default_count: Some(Seats::Limited(3)),
+                    default_duration_days: Some(365),
+                }]),
+                rules: ValidationRules::default(),
+            }
+        }
+
+        async fn call(&self, payload: String) -> Result<TrialsResponse, ApiError> {
+            handler(
+                &self.trials,
+                self.licenses.as_ref(),
+                &self.products,
+                &self.rules,
+                Duration::days(DEFAULT_TRIAL_DURATION_DAYS),
+                from_str::<TrialsRequest>(&payload).unwrap(),
+            )
+            .await
+        }
+    }
+
+    fn start() -> String {
+        format!(
+            "{{\"operation\":\"start\",\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"licenseKey\":\"{LICENSE_KEY}\",\"origin\":\"web\"}}"
+        )
+    }
+
+    fn convert() -> String {
+        format!(
+            "{{\"operation\":\"convert\",\"customerId\":\"{CUSTOMER_ID}\",\"vesselId\":\"{VESSEL_ID}\",\"licenseKey\":\"{LICENSE_KEY}\"}}"
+        )
+    }
+
+    #[tokio_test]
+    async fn start_trial() {
+        let context = Context::new();
+
+        match context.call(start()).await.unwrap() {
+            TrialsResponse::Start(license) => {
+                assert_eq!(LICENSE_KEY, license.license_key);
+                assert_eq!(Some(Seats::Limited(3)), license.count);
+                assert_eq!("web", license.trial.unwrap().origin);
+                // trial duration takes precedence over product default
+                assert!(license.expires_at.unwrap() < Utc::now() + Duration::days(31));
+            }
+            _ => panic!("Start response expected."),
+        }
+    }
+
+    #[tokio_test]
+    async fn start_trial_twice() {
+        let context = Context::new();
+
+        context.call(start()).await.unwrap();
+        context
+            .licenses
+            .delete_license(CUSTOMER_ID, VESSEL_ID, LICENSE_KEY.into())
+            .await
+            .unwrap();
+
+        match context.call(start()).await {
+            Err(ApiError::TrialAlreadyUsed(license_key)) => assert_eq!(LICENSE_KEY, license_key),
+            _ => panic!("Trial already used error expected."),
+        }
+    }
+
+    #[tokio_test]
+    async fn start_trial_of_unknown_product() {
+        let context = Context::new();
+
+        match context.call(start().replace(LICENSE_KEY, "weather.2023")).await {
+            Err(ApiError::ValidationFailed(errors)) => assert_eq!("licenseKey", errors[0].field),
+            _ => panic!("Validation error expected."),
+        }
+    }
+
+    #[tokio_test]
+    async fn convert_trial() {
+        let context = Context::new();
+        context.call(start()).await.unwrap();
+
+        match context.call(convert()).await.unwrap() {
+            TrialsResponse::Convert(license) => {
+                assert_eq!(Some(Seats::Limited(3)), license.count);
+                assert!(license.expires_at.is_none());
+                assert!(license.trial.is_none());
+            }
+            _ => panic!("Convert response expected."),
+        }
+
+        let stored = context
+            .licenses
+            .get_license(CUSTOMER_ID, VESSEL_ID, LICENSE_KEY.into())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!stored.is_trial());
+
+        match context.call(convert()).await {
+            Err(ApiError::NotTrial(license_key)) => assert_eq!(LICENSE_KEY, license_key),
+            _ => panic!("Not trial error expected."),
+        }
+    }
+
+    #[tokio_test]
+    async fn convert_unexisting() {
+        match Context::new().call(convert()).await {
+            Err(ApiError::LicenseNotFound(license_key)) => assert_eq!(LICENSE_KEY, license_key),
+            _ => panic!("License not found error expected."),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+/*
+ * This file is part of the IVMS Online.
+ *
+ * @copyright 2023 © by Rafał Wrzeszcz - Wrzasq.pl.
+ */
+
+#![feature(future_join)]
+
+use chrono::Duration;
+use lambda_runtime::{Error, LambdaEvent};
+use licenses_api_types::TrialsRequest;
+use licenses_core::{run_lambda, LicenseDao, ProductDao, TrialDao, ValidationRules};
+use licenses_trial_starter::{handler, DEFAULT_TRIAL_DURATION_DAYS};
+use std::env::var;
+use tokio::main as tokio_main;
+
+/**
+Optional environment variables:
+<dl>
+    <dt><code>TRIAL_DURATION_DAYS</code></dt>
+    <dd>Validity period of trial licenses, by default <code>30</code> days.</dd>
+</dl>
+ */
+#[tokio_main]
+async fn main() -> Result<(), Error> {
+    let trials = &TrialDao::load_from_env().await?;
+    let licenses = &LicenseDao::load_from_env().await?;
+    let products = &ProductDao::load_from_env().await?;
+    let rules = &ValidationRules::load_from_env()?;
+    let duration = Duration::days(match var("TRIAL_DURATION_DAYS") {
+        Ok(value) => value.parse()?,
+        Err(_) => DEFAULT_TRIAL_DURATION_DAYS,
+    });
+
+    run_lambda!(move |event: LambdaEvent<TrialsRequest>| handler(
+        trials,
+        licenses,
+        products,
+        rules,
+        duration,
+        event.payload
+    ))
+}